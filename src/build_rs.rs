@@ -1,24 +1,49 @@
-use crate::{codegen, config_io, parser};
+use crate::schema::{ConfigItem, ConfigType};
+use crate::{codegen, config_io, graph, parser};
 use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 /// Helper for `build.rs` to integrate Anaxa configuration.
+///
+/// By default only `config.rs` is generated; C headers and DOT graphs can be
+/// enabled with [`BuildHelper::emit_c`] and [`BuildHelper::emit_dot`].
 pub struct BuildHelper {
     kconfig_dir: PathBuf,
     config_file: PathBuf,
+    config_layers: Vec<PathBuf>,
     out_dir: PathBuf,
+    emit_rust: bool,
+    emit_c: bool,
+    emit_dot: bool,
+    rust_file: String,
+    c_file: String,
+    dot_file: String,
+    env_prefix: String,
 }
 
 impl BuildHelper {
     pub fn new() -> Result<Self> {
-        let out_dir = env::var_os("OUT_DIR").context("OUT_DIR not set")?.into();
-        Ok(Self {
+        let out_dir = env::var_os("OUT_DIR").context("OUT_DIR not set")?;
+        Ok(Self::with_out_dir(out_dir.into()))
+    }
+
+    fn with_out_dir(out_dir: PathBuf) -> Self {
+        Self {
             kconfig_dir: PathBuf::from("src"),
             config_file: PathBuf::from(".config"),
+            config_layers: Vec::new(),
             out_dir,
-        })
+            emit_rust: true,
+            emit_c: false,
+            emit_dot: false,
+            rust_file: "config.rs".to_string(),
+            c_file: "autoconf.h".to_string(),
+            dot_file: "depends.dot".to_string(),
+            env_prefix: codegen::env::DEFAULT_PREFIX.to_string(),
+        }
     }
 
     pub fn with_kconfig_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
@@ -31,36 +56,98 @@ impl BuildHelper {
         self
     }
 
+    /// Loads values from a stack of config fragments instead of the config
+    /// file, e.g. a board's base config followed by local overrides. Later
+    /// layers win and missing ones are skipped.
+    pub fn with_config_layers(mut self, layers: Vec<PathBuf>) -> Self {
+        self.config_layers = layers;
+        self
+    }
+
+    /// Generate Rust constants (on by default).
+    pub fn emit_rust(mut self, enable: bool) -> Self {
+        self.emit_rust = enable;
+        self
+    }
+
+    /// Generate a C header.
+    pub fn emit_c(mut self, enable: bool) -> Self {
+        self.emit_c = enable;
+        self
+    }
+
+    /// Generate a DOT dependency graph.
+    pub fn emit_dot(mut self, enable: bool) -> Self {
+        self.emit_dot = enable;
+        self
+    }
+
+    /// File name of the Rust constants in `OUT_DIR` (default `config.rs`).
+    pub fn with_rust_file<S: Into<String>>(mut self, name: S) -> Self {
+        self.rust_file = name.into();
+        self
+    }
+
+    /// File name of the C header in `OUT_DIR` (default `autoconf.h`).
+    pub fn with_c_file<S: Into<String>>(mut self, name: S) -> Self {
+        self.c_file = name.into();
+        self
+    }
+
+    /// File name of the DOT graph in `OUT_DIR` (default `depends.dot`).
+    pub fn with_dot_file<S: Into<String>>(mut self, name: S) -> Self {
+        self.dot_file = name.into();
+        self
+    }
+
+    /// Prefix of the `cargo:rustc-env` variables (default `ANAXA_`).
+    pub fn env_prefix(mut self, prefix: &str) -> Self {
+        self.env_prefix = prefix.to_string();
+        self
+    }
+
     pub fn build(self) -> Result<()> {
         let tree = parser::build_config_tree(&self.kconfig_dir)?;
         let configs = parser::flatten_configs(&tree);
-        let values = config_io::load_config(&self.config_file, &configs)?;
+        let values = if self.config_layers.is_empty() {
+            config_io::load_config(&self.config_file, &configs)?
+        } else {
+            config_io::load_config_layers(&self.config_layers, &configs)?
+        };
+        let values = config_io::expand_templates(&values);
 
-        let out_path = self.out_dir.join("config.rs");
-        let rust_code = codegen::rust::generate_consts(&configs, &values)?;
-        fs::write(&out_path, rust_code)
-            .with_context(|| format!("Failed to write to {:?}", out_path))?;
+        let hash = inputs_hash(&configs, &values)?;
+        if self.emit_rust {
+            write_if_changed(&self.out_dir.join(&self.rust_file), &hash, || {
+                codegen::rust::generate_consts(&configs, &values)
+            })?;
+        }
+        if self.emit_c {
+            write_if_changed(&self.out_dir.join(&self.c_file), &hash, || {
+                codegen::c::generate(&configs, &values)
+            })?;
+        }
+        if self.emit_dot {
+            write_if_changed(&self.out_dir.join(&self.dot_file), &hash, || {
+                codegen::dot::generate(&graph::ConfigGraph::build(&configs)?)
+            })?;
+        }
 
-        println!("cargo:rerun-if-changed={}", self.config_file.display());
+        if self.config_layers.is_empty() {
+            println!("cargo:rerun-if-changed={}", self.config_file.display());
+        }
+        // Missing layers are listed too, so creating one triggers a rebuild.
+        for layer in &self.config_layers {
+            println!("cargo:rerun-if-changed={}", layer.display());
+        }
         emit_rerun_if_changed(&self.kconfig_dir)?;
 
-        for item in &configs {
-            if let Some(val) = values.get(&item.name) {
-                if val.as_bool() == Some(true) {
-                    println!("cargo:rustc-cfg={}", item.name);
-                }
-            }
+        for line in cfg_instructions(&configs, &values) {
+            println!("{}", line);
         }
 
-        for (k, v) in values {
-            let v_str = match v {
-                toml::Value::String(s) => s,
-                toml::Value::Integer(i) => i.to_string(),
-                toml::Value::Float(f) => f.to_string(),
-                toml::Value::Boolean(b) => b.to_string(),
-                _ => continue,
-            };
-            println!("cargo:rustc-env=ANAXA_{}={}", k.to_uppercase(), v_str);
+        for line in env_instructions(&configs, &values, &self.env_prefix) {
+            println!("{}", line);
         }
 
         Ok(())
@@ -73,7 +160,8 @@ impl BuildHelper {
 /// 1. Scans `kconfig_dir` for `Kconfig.toml` files.
 /// 2. Loads configuration values from `config_file`.
 /// 3. Generates `config.rs` in `OUT_DIR`.
-/// 4. Emits `cargo:rustc-cfg` for enabled boolean configs.
+/// 4. Emits `cargo:rustc-cfg` for enabled boolean configs, declaring every
+///    boolean config with `cargo:rustc-check-cfg`.
 /// 5. Emits `cargo:rerun-if-changed` for the config file and all `Kconfig.toml` files.
 pub fn emit_cargo_instructions<P1, P2>(kconfig_dir: P1, config_file: P2) -> Result<()>
 where
@@ -87,6 +175,7 @@ where
     let configs = parser::flatten_configs(&tree);
 
     let values = config_io::load_config(config_file, &configs)?;
+    let values = config_io::expand_templates(&values);
 
     let out_dir = env::var_os("OUT_DIR").context("OUT_DIR not set")?;
     let out_path = PathBuf::from(out_dir).join("config.rs");
@@ -98,21 +187,93 @@ where
 
     emit_rerun_if_changed(kconfig_dir)?;
 
-    for item in &configs {
-        if let Some(val) = values.get(&item.name) {
-            if val.as_bool() == Some(true) {
-                println!("cargo:rustc-cfg={}", item.name);
-            }
-        }
+    for line in cfg_instructions(&configs, &values) {
+        println!("{}", line);
     }
 
     Ok(())
 }
 
+/// Cargo instructions declaring every bool config with `rustc-check-cfg`, so
+/// `#[cfg(NAME)]` doesn't trigger `unexpected_cfgs`, and setting `rustc-cfg`
+/// for the enabled ones.
+fn cfg_instructions(configs: &[ConfigItem], values: &HashMap<String, toml::Value>) -> Vec<String> {
+    let bools = configs
+        .iter()
+        .filter(|item| item.config_type == ConfigType::Bool);
+    let mut lines: Vec<String> = bools
+        .clone()
+        .map(|item| format!("cargo:rustc-check-cfg=cfg({})", item.name))
+        .collect();
+    lines.extend(
+        bools
+            .filter(|item| values.get(&item.name).and_then(|v| v.as_bool()) == Some(true))
+            .map(|item| format!("cargo:rustc-cfg={}", item.name)),
+    );
+    lines
+}
+
+/// `cargo:rustc-env` instructions exposing every scalar value to `env!` as
+/// `<prefix>NAME`, sorted by name.
+fn env_instructions(
+    configs: &[ConfigItem],
+    values: &HashMap<String, toml::Value>,
+    prefix: &str,
+) -> Vec<String> {
+    let mut sorted: Vec<&ConfigItem> = configs.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    sorted
+        .into_iter()
+        .filter_map(|item| {
+            let v = codegen::env::value_string(item, values.get(&item.name)?)?;
+            Some(format!(
+                "cargo:rustc-env={}={}",
+                codegen::env::var_name(prefix, &item.name),
+                v
+            ))
+        })
+        .collect()
+}
+
+/// Hashes the inputs the generated files are built from, including the crate
+/// version so an upgraded generator always rewrites the file. Values are
+/// sorted so the hash does not depend on `HashMap` iteration order.
+pub fn inputs_hash(
+    configs: &[ConfigItem],
+    values: &HashMap<String, toml::Value>,
+) -> Result<String> {
+    let sorted: BTreeMap<_, _> = values.iter().collect();
+    let serialized = serde_json::to_string(&(env!("CARGO_PKG_VERSION"), configs, sorted))?;
+    Ok(config_io::stable_hash(serialized.as_bytes()))
+}
+
+fn hash_path(out_path: &Path) -> PathBuf {
+    let mut name = out_path.as_os_str().to_owned();
+    name.push(".hash");
+    PathBuf::from(name)
+}
+
+/// Writes the output of `generate` to `out_path` unless the file exists and
+/// was generated from inputs hashing to `hash`. Cargo instructions are still
+/// emitted on every run, since cargo only keeps the ones printed by the latest
+/// build script invocation.
+fn write_if_changed<F>(out_path: &Path, hash: &str, generate: F) -> Result<()>
+where
+    F: FnOnce() -> Result<String>,
+{
+    let hash_path = hash_path(out_path);
+    if out_path.exists() && fs::read_to_string(&hash_path).is_ok_and(|h| h == hash) {
+        return Ok(());
+    }
+    fs::write(out_path, generate()?)
+        .with_context(|| format!("Failed to write to {:?}", out_path))?;
+    fs::write(&hash_path, hash).with_context(|| format!("Failed to write to {:?}", hash_path))
+}
+
 fn emit_rerun_if_changed(dir: &Path) -> Result<()> {
     use walkdir::WalkDir;
     for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
-        if entry.file_name() == "Kconfig.toml" {
+        if parser::is_kconfig_file_name(entry.file_name()) {
             println!("cargo:rerun-if-changed={}", entry.path().display());
         }
     }
@@ -157,4 +318,195 @@ desc = "Test"
 
         Ok(())
     }
+
+    #[test]
+    fn test_build_skips_unchanged_output() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "TEST_INT"
+type = "int"
+default = 1
+desc = "Test"
+"#,
+        )?;
+        let config_file = dir.path().join(".config");
+        fs::write(&config_file, "TEST_INT = 2\n")?;
+        let out_dir = tempdir()?;
+
+        let helper = || {
+            BuildHelper::with_out_dir(out_dir.path().to_path_buf())
+                .with_kconfig_dir(dir.path())
+                .with_config_file(&config_file)
+        };
+
+        let config_rs = out_dir.path().join("config.rs");
+        helper().build()?;
+        let first = fs::metadata(&config_rs)?.modified()?;
+        assert!(out_dir.path().join("config.rs.hash").exists());
+
+        helper().build()?;
+        assert_eq!(fs::metadata(&config_rs)?.modified()?, first);
+
+        fs::write(&config_file, "TEST_INT = 3\n")?;
+        helper().build()?;
+        let content = fs::read_to_string(&config_rs)?;
+        assert!(content.contains("pub const TEST_INT: i64 = 3;"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_emit_toggles() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "TEST_BOOL"
+type = "bool"
+default = true
+desc = "Test"
+"#,
+        )?;
+        let config_file = dir.path().join(".config");
+        let helper = |out_dir: &Path| {
+            BuildHelper::with_out_dir(out_dir.to_path_buf())
+                .with_kconfig_dir(dir.path())
+                .with_config_file(&config_file)
+        };
+
+        let out_dir = tempdir()?;
+        helper(out_dir.path()).build()?;
+        assert!(out_dir.path().join("config.rs").exists());
+        assert!(!out_dir.path().join("autoconf.h").exists());
+        assert!(!out_dir.path().join("depends.dot").exists());
+
+        let out_dir = tempdir()?;
+        helper(out_dir.path())
+            .emit_rust(false)
+            .emit_c(true)
+            .emit_dot(true)
+            .build()?;
+        assert!(!out_dir.path().join("config.rs").exists());
+        let header = fs::read_to_string(out_dir.path().join("autoconf.h"))?;
+        assert!(header.contains("#define CONFIG_TEST_BOOL 1"));
+        assert!(out_dir.path().join("depends.dot").exists());
+
+        let out_dir = tempdir()?;
+        helper(out_dir.path())
+            .emit_c(true)
+            .with_rust_file("anaxa.rs")
+            .with_c_file("anaxa.h")
+            .build()?;
+        assert!(out_dir.path().join("anaxa.rs").exists());
+        assert!(out_dir.path().join("anaxa.h").exists());
+        assert!(!out_dir.path().join("config.rs").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_config_layers() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "UART_BAUD"
+type = "int"
+default = 9600
+desc = "Baud rate"
+
+[[config]]
+name = "ENABLE_LOG"
+type = "bool"
+default = false
+desc = "Logging"
+"#,
+        )?;
+        let base = dir.path().join("board.config");
+        fs::write(&base, "UART_BAUD = 115200\nENABLE_LOG = true\n")?;
+        let local = dir.path().join("local.config");
+        fs::write(&local, "UART_BAUD = 921600\n")?;
+        let out_dir = tempdir()?;
+
+        BuildHelper::with_out_dir(out_dir.path().to_path_buf())
+            .with_kconfig_dir(dir.path())
+            .with_config_layers(vec![base, dir.path().join("missing.config"), local])
+            .build()?;
+
+        let content = fs::read_to_string(out_dir.path().join("config.rs"))?;
+        assert!(content.contains("pub const UART_BAUD: i64 = 921600;"));
+        assert!(content.contains("pub const ENABLE_LOG: bool = true;"));
+        assert!(!dir.path().join("missing.config").exists());
+        assert!(!dir.path().join(".config").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cfg_instructions_declare_check_cfg() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "ENABLE_A"
+type = "bool"
+default = true
+desc = "A"
+
+[[config]]
+name = "ENABLE_B"
+type = "bool"
+default = false
+desc = "B"
+
+[[config]]
+name = "COUNT"
+type = "int"
+default = 1
+desc = "Count"
+"#,
+        )?;
+        let configs = parser::flatten_configs(&parser::build_config_tree(dir.path())?);
+        let values = crate::evaluator::collect_defaults(&configs);
+
+        let lines = cfg_instructions(&configs, &values);
+        assert!(lines.contains(&"cargo:rustc-check-cfg=cfg(ENABLE_A)".to_string()));
+        assert!(lines.contains(&"cargo:rustc-check-cfg=cfg(ENABLE_B)".to_string()));
+        assert!(lines.contains(&"cargo:rustc-cfg=ENABLE_A".to_string()));
+        assert!(!lines.contains(&"cargo:rustc-cfg=ENABLE_B".to_string()));
+        assert!(!lines.iter().any(|l| l.contains("COUNT")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_env_instructions_prefix() {
+        let configs = vec![
+            ConfigItem::test_item("MAX", crate::schema::ConfigType::Int),
+            ConfigItem::test_item("ENABLE", crate::schema::ConfigType::Bool),
+            ConfigItem::test_item("BASE", crate::schema::ConfigType::Hex),
+        ];
+        let mut values = HashMap::new();
+        values.insert("MAX".to_string(), toml::Value::Integer(16));
+        values.insert("ENABLE".to_string(), toml::Value::Boolean(true));
+        values.insert("BASE".to_string(), toml::Value::Integer(-1));
+
+        let helper = BuildHelper::with_out_dir(PathBuf::new()).env_prefix("MYAPP_");
+        assert_eq!(
+            env_instructions(&configs, &values, &helper.env_prefix),
+            vec![
+                "cargo:rustc-env=MYAPP_BASE=18446744073709551615",
+                "cargo:rustc-env=MYAPP_ENABLE=true",
+                "cargo:rustc-env=MYAPP_MAX=16",
+            ]
+        );
+        assert_eq!(
+            env_instructions(&configs, &values, codegen::env::DEFAULT_PREFIX)[1],
+            "cargo:rustc-env=ANAXA_ENABLE=true"
+        );
+    }
 }
@@ -6,9 +6,9 @@ use std::fmt::Write;
 pub fn generate(items: &[ConfigItem], values: &HashMap<String, toml::Value>) -> Result<String> {
     let mut buffer = String::new();
 
-    writeln!(buffer, "/* Generated by anaxa-config */")?;
-    writeln!(buffer, "#ifndef _AUTOCONF_H_")?;
-    writeln!(buffer, "#define _AUTOCONF_H_")?;
+    writeln!(buffer, "/* auto-generated by anaxa-builder, do not edit */")?;
+    writeln!(buffer, "#ifndef ANAXA_AUTOCONF_H")?;
+    writeln!(buffer, "#define ANAXA_AUTOCONF_H")?;
     writeln!(buffer)?;
 
     for item in items {
@@ -18,7 +18,7 @@ pub fn generate(items: &[ConfigItem], values: &HashMap<String, toml::Value>) ->
     }
 
     writeln!(buffer)?;
-    writeln!(buffer, "#endif /* _AUTOCONF_H_ */")?;
+    writeln!(buffer, "#endif /* ANAXA_AUTOCONF_H */")?;
 
     Ok(buffer)
 }
@@ -26,9 +26,15 @@ pub fn generate(items: &[ConfigItem], values: &HashMap<String, toml::Value>) ->
 fn write_item_define(buffer: &mut String, item: &ConfigItem, val: &toml::Value) -> Result<()> {
     let name = format!("CONFIG_{}", item.name);
 
-    if item.config_type == ConfigType::Bool && val.as_bool() == Some(false) {
+    if !item.desc.is_empty() {
+        writeln!(buffer, "/* {} */", item.desc.replace("*/", "* /"))?;
+    }
+
+    if (item.config_type == ConfigType::Bool && val.as_bool() == Some(false))
+        || item.is_unselected(val)
+    {
         writeln!(buffer, "/* {} is not set */", name)?;
-    } else if let Some(formatted) = item.config_type.format_value_c(val) {
+    } else if let Some(formatted) = item.config_type.format_value_c(item.mapped_value(val)) {
         writeln!(buffer, "#define {} {}", name, formatted)?;
     }
     Ok(())
@@ -43,28 +49,12 @@ mod tests {
     fn test_generate_c() -> Result<()> {
         let items = vec![
             ConfigItem {
-                name: "ENABLE_A".to_string(),
-                config_type: ConfigType::Bool,
-                default: None,
                 desc: "A".to_string(),
-                depends_on: None,
-                help: None,
-                options: None,
-                feature: None,
-                range: None,
-                regex: None,
+                ..ConfigItem::test_item("ENABLE_A", ConfigType::Bool)
             },
             ConfigItem {
-                name: "MAX_B".to_string(),
-                config_type: ConfigType::Int,
-                default: None,
                 desc: "B".to_string(),
-                depends_on: None,
-                help: None,
-                options: None,
-                feature: None,
-                range: None,
-                regex: None,
+                ..ConfigItem::test_item("MAX_B", ConfigType::Int)
             },
         ];
 
@@ -77,4 +67,92 @@ mod tests {
         assert!(code.contains("#define CONFIG_MAX_B 42"));
         Ok(())
     }
+
+    #[test]
+    fn test_generate_c_guard_and_comments() -> Result<()> {
+        let items = vec![ConfigItem {
+            desc: "Enable A".to_string(),
+            ..ConfigItem::test_item("ENABLE_A", ConfigType::Bool)
+        }];
+
+        let mut values = HashMap::new();
+        values.insert("ENABLE_A".to_string(), toml::Value::Boolean(false));
+
+        let code = generate(&items, &values)?;
+        assert!(code.starts_with("/* auto-generated"));
+        assert!(code.contains("#ifndef ANAXA_AUTOCONF_H\n#define ANAXA_AUTOCONF_H\n"));
+        assert!(code.contains("/* Enable A */\n/* CONFIG_ENABLE_A is not set */\n"));
+        assert!(!code.contains("#define CONFIG_ENABLE_A"));
+        assert!(code.trim_end().ends_with("#endif /* ANAXA_AUTOCONF_H */"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_c_optional_choice_unselected() -> Result<()> {
+        let items = vec![ConfigItem {
+            options: Some(vec!["uart".into(), "rtt".into()]),
+            optional: true,
+            ..ConfigItem::test_item("LOGGER", ConfigType::Choice)
+        }];
+
+        let mut values = HashMap::new();
+        values.insert("LOGGER".to_string(), toml::Value::String(String::new()));
+
+        let code = generate(&items, &values)?;
+        assert!(code.contains("/* CONFIG_LOGGER is not set */"));
+        assert!(!code.contains("#define CONFIG_LOGGER"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_c_choice_option_values() -> Result<()> {
+        let choice = |name: &str, options: Vec<&str>, option_values: Vec<toml::Value>| ConfigItem {
+            options: Some(options.into_iter().map(Into::into).collect()),
+            option_values: Some(option_values),
+            ..ConfigItem::test_item(name, ConfigType::Choice)
+        };
+        let items = vec![
+            choice(
+                "CPU_FREQ",
+                vec!["slow", "fast"],
+                vec![
+                    toml::Value::Integer(48_000_000),
+                    toml::Value::Integer(96_000_000),
+                ],
+            ),
+            choice(
+                "RATIO",
+                vec!["half", "full"],
+                vec![toml::Value::Float(0.5), toml::Value::Float(1.0)],
+            ),
+            choice(
+                "BANNER",
+                vec!["quoted"],
+                vec![toml::Value::String("say \"hi\"".to_string())],
+            ),
+        ];
+
+        let mut values = HashMap::new();
+        for (name, option) in [
+            ("CPU_FREQ", "fast"),
+            ("RATIO", "half"),
+            ("BANNER", "quoted"),
+        ] {
+            values.insert(name.to_string(), toml::Value::String(option.to_string()));
+        }
+
+        let code = generate(&items, &values)?;
+        assert!(
+            code.contains("#define CONFIG_CPU_FREQ 96000000"),
+            "{}",
+            code
+        );
+        assert!(code.contains("#define CONFIG_RATIO 0.5"), "{}", code);
+        assert!(
+            code.contains(r#"#define CONFIG_BANNER "say \"hi\"""#),
+            "{}",
+            code
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,106 @@
+use crate::schema::ConfigItem;
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// Generates a `[features]` table declaring every feature referenced by
+/// `ConfigItem.feature`, ready to paste into `Cargo.toml`.
+///
+/// Configs mapping to more than one feature also get a grouping feature named
+/// after the symbol (lowercased) that enables all of them, unless that name is
+/// already taken by a referenced feature.
+pub fn generate_features(items: &[ConfigItem]) -> Result<String> {
+    let features: BTreeSet<&str> = items
+        .iter()
+        .flat_map(|i| i.feature.iter().flatten())
+        .map(|f| f.as_str())
+        .collect();
+
+    let mut buffer = String::new();
+    writeln!(buffer, "# auto-generated by anaxa-builder, do not edit")?;
+    writeln!(buffer, "[features]")?;
+    for feature in &features {
+        writeln!(buffer, "{} = []", toml_key(feature))?;
+    }
+
+    for item in items {
+        let Some(item_features) = &item.feature else {
+            continue;
+        };
+        let group = item.name.to_lowercase();
+        if item_features.len() < 2 || features.contains(group.as_str()) {
+            continue;
+        }
+        let deps: Vec<String> = item_features
+            .iter()
+            .map(|f| toml::Value::String(f.clone()).to_string())
+            .collect();
+        writeln!(buffer, "{} = [{}]", toml_key(&group), deps.join(", "))?;
+    }
+
+    Ok(buffer)
+}
+
+/// Quotes `name` unless it is a valid bare TOML key, as feature names like
+/// `serde/std` or `dep:serde` aren't.
+fn toml_key(name: &str) -> String {
+    let bare = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        name.to_string()
+    } else {
+        toml::Value::String(name.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_features() -> anyhow::Result<()> {
+        let items: Vec<ConfigItem> = [
+            ("ENABLE_NET", vec!["net", "tls"]),
+            ("ENABLE_TLS", vec!["tls"]),
+            ("ENABLE_LOG", vec!["log"]),
+            ("ENABLE_SERDE", vec!["serde/std", "dep:serde"]),
+            ("ENABLE_ODD", vec!["a\u{7f}b", "c"]),
+        ]
+        .into_iter()
+        .map(|(name, features)| ConfigItem {
+            feature: Some(features.iter().map(|f| f.to_string()).collect()),
+            ..ConfigItem::test_bool(name, None)
+        })
+        .collect();
+
+        let output = generate_features(&items)?;
+        let table: toml::Table = toml::from_str(&output)?;
+        let features = table["features"].as_table().unwrap();
+
+        for name in ["net", "tls", "log"] {
+            assert_eq!(output.matches(&format!("\n{} = ", name)).count(), 1);
+            assert!(features.contains_key(name));
+        }
+        assert_eq!(
+            features["enable_net"],
+            toml::Value::Array(vec!["net".into(), "tls".into()])
+        );
+        assert!(!features.contains_key("enable_tls"));
+
+        assert!(output.contains("\n\"serde/std\" = []\n"));
+        assert!(output.contains("\n\"dep:serde\" = []\n"));
+        assert_eq!(
+            features["enable_serde"],
+            toml::Value::Array(vec!["serde/std".into(), "dep:serde".into()])
+        );
+        // Rust's Debug escapes aren't all valid TOML, e.g. `\u{7f}`.
+        assert_eq!(
+            features["enable_odd"],
+            toml::Value::Array(vec!["a\u{7f}b".into(), "c".into()])
+        );
+        assert!(output.starts_with("# auto-generated by anaxa-builder"));
+        Ok(())
+    }
+}
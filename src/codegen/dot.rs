@@ -1,6 +1,7 @@
 use crate::graph::ConfigGraph;
 use anyhow::Result;
 use petgraph::dot::{Config, Dot};
+use std::collections::HashMap;
 
 pub fn generate(graph: &ConfigGraph) -> Result<String> {
     let output = format!(
@@ -9,3 +10,62 @@ pub fn generate(graph: &ConfigGraph) -> Result<String> {
     );
     Ok(output)
 }
+
+/// Like [`generate`], but labels each node with its current value and colors
+/// bool nodes green when enabled and grey when disabled.
+pub fn generate_with_values(
+    graph: &ConfigGraph,
+    values: &HashMap<String, toml::Value>,
+) -> Result<String> {
+    let node_attrs = |_, (name, _): (&str, &&str)| {
+        let Some(value) = values.get(name) else {
+            return format!("label = \"{}\"", name);
+        };
+        let label = format!("{}\\n= {}", name, value.to_string().replace('"', "\\\""));
+        match value.as_bool() {
+            Some(true) => format!(
+                "label = \"{}\", style = filled, fillcolor = palegreen, color = green",
+                label
+            ),
+            Some(false) => format!(
+                "label = \"{}\", style = filled, fillcolor = lightgrey, color = grey, fontcolor = grey40",
+                label
+            ),
+            None => format!("label = \"{}\"", label),
+        }
+    };
+    let output = format!(
+        "{:?}",
+        Dot::with_attr_getters(
+            &graph.graph,
+            &[Config::EdgeNoLabel, Config::NodeNoLabel],
+            &|_, _| String::new(),
+            &node_attrs,
+        )
+    );
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::ConfigItem;
+
+    #[test]
+    fn test_generate_with_values() -> Result<()> {
+        let items = vec![
+            ConfigItem::test_bool("ON", None),
+            ConfigItem::test_bool("OFF", Some("ON")),
+        ];
+        let graph = ConfigGraph::build(&items)?;
+
+        let mut values = HashMap::new();
+        values.insert("ON".to_string(), toml::Value::Boolean(true));
+        values.insert("OFF".to_string(), toml::Value::Boolean(false));
+
+        let dot = generate_with_values(&graph, &values)?;
+        assert!(dot.contains("label = \"ON\\n= true\", style = filled, fillcolor = palegreen"));
+        assert!(dot.contains("label = \"OFF\\n= false\", style = filled, fillcolor = lightgrey"));
+        Ok(())
+    }
+}
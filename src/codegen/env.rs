@@ -0,0 +1,173 @@
+use crate::schema::{ConfigItem, ConfigType};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Prefix of the environment variables unless configured otherwise.
+pub const DEFAULT_PREFIX: &str = "ANAXA_";
+
+/// Generates `<prefix>KEY=value` lines that can be sourced by a POSIX shell.
+/// Values are quoted with shell escapes, so docker-compose, which doesn't
+/// unescape `\$`, reads them differently. Lines are sorted by variable name
+/// for stable output.
+pub fn generate(
+    items: &[ConfigItem],
+    values: &HashMap<String, toml::Value>,
+    prefix: &str,
+) -> Result<String> {
+    let mut buffer = String::new();
+
+    writeln!(buffer, "# auto-generated by anaxa-builder, do not edit")?;
+
+    let mut lines = Vec::new();
+    for item in items {
+        let Some(val) = values.get(&item.name) else {
+            continue;
+        };
+        // Unlike `value_string`, keep hex readable in the file.
+        let val = if item.config_type == ConfigType::Hex {
+            crate::schema::hex_value(val).map(|u| format!("0x{:x}", u))
+        } else {
+            value_string(item, val)
+        };
+        if let Some(val) = val {
+            lines.push((var_name(prefix, &item.name), quote(&val)));
+        }
+    }
+    lines.sort();
+    for (name, val) in lines {
+        writeln!(buffer, "{}={}", name, val)?;
+    }
+
+    Ok(buffer)
+}
+
+/// Name of the environment variable carrying `key`.
+pub fn var_name(prefix: &str, key: &str) -> String {
+    format!("{}{}", prefix, key.to_uppercase())
+}
+
+/// Renders a config value as an environment variable value, or `None` for
+/// values that have no scalar representation. Hex values are written as
+/// unsigned decimals, so they parse as `u64`, and string lists are joined
+/// with commas.
+pub fn value_string(item: &ConfigItem, val: &toml::Value) -> Option<String> {
+    match val {
+        toml::Value::Integer(_) if item.config_type == ConfigType::Hex => {
+            crate::schema::hex_value(val).map(|u| u.to_string())
+        }
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        toml::Value::Array(_) => crate::schema::string_list(val).map(|items| items.join(",")),
+        _ => None,
+    }
+}
+
+/// Double-quotes `s` unless it only contains characters that are safe bare.
+fn quote(s: &str) -> String {
+    let is_bare = |c: char| c.is_ascii_alphanumeric() || "_-.,:/+@%".contains(c);
+    if !s.is_empty() && s.chars().all(is_bare) {
+        return s.to_string();
+    }
+
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' | '"' | '$' | '`' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_env() -> Result<()> {
+        let items = vec![
+            ConfigItem::test_item("device_name", ConfigType::String),
+            ConfigItem::test_item("MAX", ConfigType::Int),
+            ConfigItem::test_item("BASE", ConfigType::Hex),
+            ConfigItem::test_item("ENABLE_NET", ConfigType::Bool),
+            ConfigItem::test_item("EMPTY", ConfigType::String),
+        ];
+        let mut values = HashMap::new();
+        values.insert(
+            "device_name".to_string(),
+            toml::Value::String("my \"node\" $HOME".to_string()),
+        );
+        values.insert("MAX".to_string(), toml::Value::Integer(16));
+        values.insert("BASE".to_string(), toml::Value::Integer(-1));
+        values.insert("ENABLE_NET".to_string(), toml::Value::Boolean(true));
+        values.insert("EMPTY".to_string(), toml::Value::String(String::new()));
+
+        let env = generate(&items, &values, DEFAULT_PREFIX)?;
+        let lines: Vec<&str> = env.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "ANAXA_BASE=0xffffffffffffffff",
+                "ANAXA_DEVICE_NAME=\"my \\\"node\\\" \\$HOME\"",
+                "ANAXA_EMPTY=\"\"",
+                "ANAXA_ENABLE_NET=true",
+                "ANAXA_MAX=16",
+            ]
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_generate_env_sources_in_shell() -> Result<()> {
+        let value = "cost: $5 `date` \"q\" \\ done";
+        let items = vec![ConfigItem::test_item("NOTE", ConfigType::String)];
+        let mut values = HashMap::new();
+        values.insert("NOTE".to_string(), toml::Value::String(value.to_string()));
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("config.env");
+        std::fs::write(&path, generate(&items, &values, DEFAULT_PREFIX)?)?;
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!(". {:?}; printf %s \"$ANAXA_NOTE\"", path))
+            .output()?;
+        assert_eq!(String::from_utf8(output.stdout)?, value);
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_env_custom_prefix() -> Result<()> {
+        let items = vec![ConfigItem::test_item("max", ConfigType::Int)];
+        let mut values = HashMap::new();
+        values.insert("max".to_string(), toml::Value::Integer(16));
+
+        let env = generate(&items, &values, "MYAPP_")?;
+        assert!(env.contains("\nMYAPP_MAX=16\n"));
+        assert!(!env.contains("ANAXA_"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_string_hex_is_unsigned_decimal() {
+        let base = ConfigItem::test_item("BASE", ConfigType::Hex);
+        assert_eq!(
+            value_string(&base, &toml::Value::Integer(-1)).as_deref(),
+            Some("18446744073709551615")
+        );
+        assert_eq!(
+            value_string(&base, &toml::Value::Integer(0x8000)).as_deref(),
+            Some("32768")
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use crate::schema::{ConfigItem, ConfigType};
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+
+/// Generates a JSON Schema describing valid `.config` files for the given items.
+/// Aliases are accepted as deprecated properties referring to the symbol's
+/// own schema.
+pub fn generate(items: &[ConfigItem]) -> Result<String> {
+    let mut properties = Map::new();
+
+    for item in items {
+        properties.insert(item.name.clone(), item_schema(item)?);
+    }
+    for item in items {
+        for alias in item.alias.iter().flatten() {
+            // Siblings of `$ref` are ignored in draft-07, hence `allOf`.
+            properties.insert(
+                alias.clone(),
+                json!({
+                    "allOf": [{ "$ref": format!("#/properties/{}", item.name) }],
+                    "deprecated": true,
+                    "description": format!("Renamed to {}", item.name),
+                }),
+            );
+        }
+    }
+
+    let schema = json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Anaxa configuration",
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    });
+
+    Ok(serde_json::to_string_pretty(&schema)?)
+}
+
+fn item_schema(item: &ConfigItem) -> Result<Value> {
+    let mut schema = Map::new();
+
+    match item.config_type {
+        ConfigType::Bool => {
+            schema.insert("type".into(), json!("boolean"));
+        }
+        ConfigType::Int | ConfigType::Hex => {
+            if item.config_type == ConfigType::Hex {
+                // Hex values may also be written as strings like "0x1000";
+                // `pattern` only constrains the string form.
+                schema.insert("type".into(), json!(["integer", "string"]));
+                schema.insert("pattern".into(), json!("^(0[xX])?[0-9a-fA-F]+$"));
+            } else {
+                schema.insert("type".into(), json!("integer"));
+            }
+            if let Some((min, max)) = item.range {
+                schema.insert("minimum".into(), json!(min));
+                schema.insert("maximum".into(), json!(max));
+            }
+        }
+        ConfigType::Float => {
+            schema.insert("type".into(), json!("number"));
+            if let Some((min, max)) = item.float_range {
+                schema.insert("minimum".into(), json!(min));
+                schema.insert("maximum".into(), json!(max));
+            }
+        }
+        ConfigType::String => {
+            schema.insert("type".into(), json!("string"));
+            if let Some(regex) = &item.regex {
+                schema.insert("pattern".into(), json!(regex));
+            }
+        }
+        ConfigType::StringList => {
+            schema.insert("type".into(), json!("array"));
+            let mut element = Map::new();
+            element.insert("type".into(), json!("string"));
+            if let Some(regex) = &item.regex {
+                element.insert("pattern".into(), json!(regex));
+            }
+            schema.insert("items".into(), Value::Object(element));
+        }
+        ConfigType::Choice => {
+            schema.insert("type".into(), json!("string"));
+            if item.options.is_some() {
+                let mut values = item.option_values();
+                if item.optional {
+                    values.push("");
+                }
+                schema.insert("enum".into(), json!(values));
+            }
+        }
+    }
+
+    if !item.desc.is_empty() {
+        schema.insert("description".into(), json!(item.desc));
+    }
+    if let Some(default) = &item.default {
+        schema.insert("default".into(), serde_json::to_value(default)?);
+    }
+
+    Ok(Value::Object(schema))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_json_schema() -> Result<()> {
+        let items = vec![
+            ConfigItem {
+                default: Some(toml::Value::Integer(80)),
+                desc: "Port".to_string(),
+                range: Some((1, 65535)),
+                alias: Some(vec!["OLD_PORT".to_string()]),
+                ..ConfigItem::test_item("PORT", ConfigType::Int)
+            },
+            ConfigItem {
+                desc: "Name".to_string(),
+                regex: Some(r"^[a-z]+$".to_string()),
+                ..ConfigItem::test_item("NAME", ConfigType::String)
+            },
+            ConfigItem {
+                desc: "Mode".to_string(),
+                options: Some(vec!["A".into(), "B".into()]),
+                ..ConfigItem::test_item("MODE", ConfigType::Choice)
+            },
+        ];
+
+        let schema: Value = serde_json::from_str(&generate(&items)?)?;
+        let props = &schema["properties"];
+
+        assert_eq!(props["PORT"]["type"], "integer");
+        assert_eq!(props["PORT"]["minimum"], 1);
+        assert_eq!(props["PORT"]["maximum"], 65535);
+        assert_eq!(props["PORT"]["default"], 80);
+        assert_eq!(props["NAME"]["type"], "string");
+        assert_eq!(props["NAME"]["pattern"], "^[a-z]+$");
+        assert_eq!(props["MODE"]["enum"], json!(["A", "B"]));
+        assert_eq!(props["OLD_PORT"]["allOf"][0]["$ref"], "#/properties/PORT");
+        assert_eq!(props["OLD_PORT"]["deprecated"], true);
+        assert_eq!(schema["additionalProperties"], false);
+        Ok(())
+    }
+}
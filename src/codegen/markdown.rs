@@ -0,0 +1,135 @@
+use crate::schema::{ConfigItem, ConfigNode};
+use anyhow::Result;
+use std::fmt::Write;
+
+/// Generates Markdown documentation for the configuration tree.
+///
+/// Each menu becomes a heading whose level follows its depth in the tree.
+pub fn generate(tree: &ConfigNode) -> Result<String> {
+    let mut buffer = String::new();
+    write_node(&mut buffer, tree, 1)?;
+    Ok(buffer)
+}
+
+fn write_node(buffer: &mut String, node: &ConfigNode, depth: usize) -> Result<()> {
+    writeln!(buffer, "{} {}", "#".repeat(depth.min(6)), node.desc)?;
+    writeln!(buffer)?;
+
+    if let Some(dep) = &node.depends_on {
+        writeln!(buffer, "Depends on: `{}`", dep)?;
+        writeln!(buffer)?;
+    }
+
+    for item in &node.configs {
+        write_item(buffer, item)?;
+    }
+
+    for child in &node.children {
+        write_node(buffer, child, depth + 1)?;
+    }
+
+    Ok(())
+}
+
+fn write_item(buffer: &mut String, item: &ConfigItem) -> Result<()> {
+    if item.desc.is_empty() {
+        writeln!(buffer, "**`{}`**", item.name)?;
+    } else {
+        writeln!(buffer, "**`{}`** — {}", item.name, item.desc)?;
+    }
+    writeln!(buffer)?;
+    writeln!(buffer, "| Field | Value |")?;
+    writeln!(buffer, "|-------|-------|")?;
+    write_row(buffer, "Type", &item.config_type.to_string())?;
+    if let Some(default) = &item.default {
+        write_row(buffer, "Default", &default.to_string())?;
+    }
+    for conditional in item.default_if.iter().flatten() {
+        write_row(
+            buffer,
+            &format!("Default if {}", conditional.depends_on.replace('|', "\\|")),
+            &conditional.value.to_string(),
+        )?;
+    }
+    if let Some(dep) = &item.depends_on {
+        write_row(buffer, "Depends on", dep)?;
+    }
+    if let Some((min, max)) = item.range {
+        write_row(buffer, "Range", &format!("[{}, {}]", min, max))?;
+    }
+    if let Some(regex) = &item.regex {
+        write_row(buffer, "Regex", regex)?;
+    }
+    if let Some(options) = &item.options {
+        let options: Vec<String> = options
+            .iter()
+            .map(|o| match &o.depends_on {
+                Some(dep) => format!("{} (if {})", o.value, dep),
+                None => o.value.clone(),
+            })
+            .collect();
+        write_row(buffer, "Options", &options.join(", "))?;
+    }
+    if let Some(features) = &item.feature {
+        write_row(buffer, "Features", &features.join(", "))?;
+    }
+    writeln!(buffer)?;
+
+    if let Some(help) = &item.help {
+        writeln!(buffer, "{}", help.trim())?;
+        writeln!(buffer)?;
+    }
+
+    Ok(())
+}
+
+fn write_row(buffer: &mut String, field: &str, value: &str) -> Result<()> {
+    writeln!(buffer, "| {} | `{}` |", field, value.replace('|', "\\|"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::ConfigType;
+
+    #[test]
+    fn test_generate_markdown() -> Result<()> {
+        let tree = ConfigNode {
+            desc: "Root".to_string(),
+            configs: vec![],
+            children: vec![ConfigNode {
+                desc: "Networking".to_string(),
+                configs: vec![ConfigItem {
+                    default: Some(toml::Value::Integer(16)),
+                    desc: "Maximum sockets".to_string(),
+                    depends_on: Some("ENABLE_NET".to_string()),
+                    help: Some("Upper bound on open sockets.".to_string()),
+                    range: Some((1, 1024)),
+                    ..ConfigItem::test_item("MAX_SOCKETS", ConfigType::Int)
+                }],
+                children: vec![],
+                path: "net".to_string(),
+                depends_on: None,
+                help: None,
+                exclusive_group: None,
+                file: String::new(),
+            }],
+            path: "".to_string(),
+            depends_on: None,
+            help: None,
+            exclusive_group: None,
+            file: String::new(),
+        };
+
+        let md = generate(&tree)?;
+        assert!(md.contains("# Root\n"));
+        assert!(md.contains("## Networking\n"));
+        assert!(md.contains("**`MAX_SOCKETS`** — Maximum sockets"));
+        assert!(md.contains("| Default | `16` |"));
+        assert!(md.contains("| Depends on | `ENABLE_NET` |"));
+        assert!(md.contains("| Range | `[1, 1024]` |"));
+        assert!(md.contains("Upper bound on open sockets."));
+        Ok(())
+    }
+}
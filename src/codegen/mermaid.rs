@@ -0,0 +1,40 @@
+use crate::graph::ConfigGraph;
+use anyhow::Result;
+use std::fmt::Write;
+
+/// Renders the dependency graph as a Mermaid flowchart. Edges point from a
+/// symbol to the symbols that depend on it, matching the DOT output.
+pub fn generate(graph: &ConfigGraph) -> Result<String> {
+    let mut buffer = String::new();
+
+    writeln!(buffer, "graph TD")?;
+    for node in graph.graph.nodes() {
+        writeln!(buffer, "    {}", node)?;
+    }
+    for (from, to, _) in graph.graph.all_edges() {
+        writeln!(buffer, "    {} --> {}", from, to)?;
+    }
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::ConfigItem;
+
+    #[test]
+    fn test_generate_mermaid() -> Result<()> {
+        let items = vec![
+            ConfigItem::test_bool("ENABLE_NET", None),
+            ConfigItem::test_bool("MAX_SOCKETS", Some("ENABLE_NET")),
+        ];
+        let graph = ConfigGraph::build(&items)?;
+
+        let mermaid = generate(&graph)?;
+        assert!(mermaid.starts_with("graph TD\n"));
+        assert!(mermaid.contains("    ENABLE_NET\n"));
+        assert!(mermaid.contains("    ENABLE_NET --> MAX_SOCKETS\n"));
+        Ok(())
+    }
+}
@@ -1,3 +1,50 @@
 pub mod c;
+pub mod cargo;
 pub mod dot;
+pub mod env;
+pub mod json_schema;
+pub mod markdown;
+pub mod mermaid;
+pub mod python;
 pub mod rust;
+pub mod template;
+
+/// Converts `SOME_NAME` or `some-name` into `SomeName`.
+pub(crate) fn to_camel_case(s: &str) -> String {
+    s.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first)
+                .chain(chars.map(|c| c.to_ascii_lowercase()))
+                .collect::<String>()
+        })
+        .collect()
+}
+
+/// Turns choice options into unique identifiers: each option is converted
+/// by `sanitize`, gets `prefix` if it doesn't start with a letter, and is
+/// numbered after `separator` if it clashes with an earlier one.
+pub(crate) fn unique_identifiers(
+    options: &[&str],
+    sanitize: impl Fn(&str) -> String,
+    prefix: &str,
+    separator: &str,
+) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for option in options {
+        let mut name = sanitize(option);
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            name = format!("{}{}", prefix, name);
+        }
+        let base = name.clone();
+        let mut suffix = 2;
+        while names.contains(&name) {
+            name = format!("{}{}{}", base, separator, suffix);
+            suffix += 1;
+        }
+        names.push(name);
+    }
+    names
+}
@@ -0,0 +1,203 @@
+use crate::codegen::{to_camel_case, unique_identifiers};
+use crate::schema::{ConfigItem, ConfigType};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+pub fn generate(items: &[ConfigItem], values: &HashMap<String, toml::Value>) -> Result<String> {
+    let mut buffer = String::new();
+
+    writeln!(buffer, "# auto-generated by anaxa-builder, do not edit")?;
+    if items.iter().any(has_enum) {
+        writeln!(buffer)?;
+        writeln!(buffer, "from enum import Enum")?;
+    }
+    writeln!(buffer)?;
+
+    for item in items {
+        let val = values.get(&item.name);
+        if has_enum(item) {
+            write_choice_enum(&mut buffer, item, val)?;
+        }
+        let Some(val) = val else {
+            continue;
+        };
+
+        if !item.desc.is_empty() {
+            writeln!(buffer, "# {}", item.desc.replace('\n', " "))?;
+        }
+        if item.is_unselected(val) {
+            writeln!(buffer, "{} = None", item.name)?;
+        } else if let Some(formatted) = format_value(item, item.mapped_value(val)) {
+            writeln!(buffer, "{} = {}", item.name, formatted)?;
+        }
+    }
+
+    Ok(buffer)
+}
+
+fn has_enum(item: &ConfigItem) -> bool {
+    item.config_type == ConfigType::Choice && item.options.is_some()
+}
+
+fn format_value(item: &ConfigItem, val: &toml::Value) -> Option<String> {
+    match val {
+        toml::Value::Boolean(b) => Some(if *b { "True" } else { "False" }.to_string()),
+        toml::Value::Integer(_) if item.config_type == ConfigType::Hex => {
+            crate::schema::hex_value(val).map(|u| format!("0x{:x}", u))
+        }
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) if f.is_finite() => Some(format!("{:?}", f)),
+        toml::Value::Float(f) => Some(format!("float(\"{}\")", f)),
+        toml::Value::String(s) => Some(quote(s)),
+        toml::Value::Array(_) => crate::schema::string_list(val).map(|items| {
+            let quoted: Vec<String> = items.iter().map(|s| quote(s)).collect();
+            format!("[{}]", quoted.join(", "))
+        }),
+        _ => None,
+    }
+}
+
+/// Quotes `s` as a Python string literal.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Emits an `Enum` class for a choice config, plus a `<NAME>_CHOICE` constant
+/// holding the selected member (or `None` for an unselected optional choice).
+fn write_choice_enum(
+    buffer: &mut String,
+    item: &ConfigItem,
+    val: Option<&toml::Value>,
+) -> Result<()> {
+    let class_name = to_camel_case(&item.name);
+    let options = item.option_values();
+    let members = member_names(&options);
+
+    writeln!(buffer, "class {}(Enum):", class_name)?;
+    for (member, option) in members.iter().zip(&options) {
+        writeln!(buffer, "    {} = {}", member, quote(option))?;
+    }
+    writeln!(buffer)?;
+
+    let selected = val
+        .and_then(|v| v.as_str())
+        .and_then(|v| options.iter().position(|o| *o == v));
+    match selected {
+        Some(index) => writeln!(
+            buffer,
+            "{}_CHOICE = {}.{}",
+            item.name, class_name, members[index]
+        )?,
+        None if item.optional => writeln!(buffer, "{}_CHOICE = None", item.name)?,
+        None => {}
+    }
+    Ok(())
+}
+
+/// Sanitizes choice options into unique, upper-case Python identifiers.
+fn member_names(options: &[&str]) -> Vec<String> {
+    let sanitize = |option: &str| {
+        option
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    };
+    unique_identifiers(options, sanitize, "V_", "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_python() -> Result<()> {
+        let items = vec![
+            ConfigItem::test_item("ENABLE_A", ConfigType::Bool),
+            ConfigItem::test_item("MAX_B", ConfigType::Int),
+            ConfigItem {
+                desc: "Load address".to_string(),
+                ..ConfigItem::test_item("BASE", ConfigType::Hex)
+            },
+            ConfigItem::test_item("NAME", ConfigType::String),
+            ConfigItem::test_item("ORIGINS", ConfigType::StringList),
+        ];
+
+        let mut values = HashMap::new();
+        values.insert("ENABLE_A".to_string(), toml::Value::Boolean(true));
+        values.insert("MAX_B".to_string(), toml::Value::Integer(42));
+        values.insert("BASE".to_string(), toml::Value::Integer(0x8000_abcd));
+        values.insert(
+            "NAME".to_string(),
+            toml::Value::String("say \"hi\"".to_string()),
+        );
+
+        values.insert(
+            "ORIGINS".to_string(),
+            toml::Value::Array(vec!["a.com".into(), "b.com".into()]),
+        );
+
+        let code = generate(&items, &values)?;
+        assert!(code.starts_with("# auto-generated"));
+        assert!(!code.contains("from enum import Enum"));
+        assert!(code.contains("ENABLE_A = True\n"));
+        assert!(code.contains("MAX_B = 42\n"));
+        assert!(code.contains("# Load address\nBASE = 0x8000abcd\n"));
+        assert!(code.contains("NAME = \"say \\\"hi\\\"\"\n"));
+        assert!(code.contains("ORIGINS = [\"a.com\", \"b.com\"]\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_python_choice_enum() -> Result<()> {
+        let sched = ConfigItem {
+            options: Some(vec!["round-robin".into(), "fifo".into()]),
+            ..ConfigItem::test_item("SCHEDULER", ConfigType::Choice)
+        };
+        let logger = ConfigItem {
+            options: Some(vec!["uart".into()]),
+            optional: true,
+            ..ConfigItem::test_item("LOGGER", ConfigType::Choice)
+        };
+
+        let mut values = HashMap::new();
+        values.insert(
+            "SCHEDULER".to_string(),
+            toml::Value::String("fifo".to_string()),
+        );
+        values.insert("LOGGER".to_string(), toml::Value::String(String::new()));
+
+        let code = generate(&[sched, logger], &values)?;
+        assert!(code.contains("from enum import Enum\n"));
+        assert!(code.contains(
+            "class Scheduler(Enum):\n    ROUND_ROBIN = \"round-robin\"\n    FIFO = \"fifo\"\n"
+        ));
+        assert!(code.contains("SCHEDULER_CHOICE = Scheduler.FIFO\n"));
+        assert!(code.contains("SCHEDULER = \"fifo\"\n"));
+        assert!(code.contains("LOGGER_CHOICE = None\n"));
+        assert!(code.contains("LOGGER = None\n"));
+        Ok(())
+    }
+}
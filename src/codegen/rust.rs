@@ -1,3 +1,4 @@
+use crate::codegen::{to_camel_case, unique_identifiers};
 use crate::schema::{ConfigItem, ConfigType};
 use anyhow::Result;
 use std::collections::HashMap;
@@ -13,22 +14,107 @@ pub fn generate_consts(
     writeln!(buffer)?;
 
     for item in items {
-        if let Some(val) = values.get(&item.name) {
-            if let Some(formatted) = item.config_type.format_value_rust(val) {
+        if let Some(val) = values.get(&item.name).filter(|v| !item.is_unselected(v)) {
+            if let Some(formatted) = item.config_type.format_value_rust(item.mapped_value(val)) {
+                write_doc_comment(&mut buffer, item)?;
                 writeln!(
                     buffer,
                     "#[allow(dead_code)]\npub const {}: {} = {};",
                     item.name,
-                    item.config_type.rust_type(),
+                    item.rust_type(),
                     formatted
                 )?;
             }
         }
+        if item.config_type == ConfigType::Choice && item.options.is_some() {
+            write_choice_enum(&mut buffer, item, values.get(&item.name))?;
+        }
     }
 
     Ok(buffer)
 }
 
+/// Emits the config's `desc` and `help` as `///` doc comments.
+fn write_doc_comment(buffer: &mut String, item: &ConfigItem) -> Result<()> {
+    let help = item.help.as_deref().unwrap_or_default().trim();
+    for line in item.desc.lines() {
+        writeln!(buffer, "/// {}", line.trim_end())?;
+    }
+    if !item.desc.is_empty() && !help.is_empty() {
+        writeln!(buffer, "///")?;
+    }
+    for line in help.lines() {
+        if line.trim().is_empty() {
+            writeln!(buffer, "///")?;
+        } else {
+            writeln!(buffer, "/// {}", line.trim_end())?;
+        }
+    }
+    Ok(())
+}
+
+/// Emits a Rust enum for a choice config, plus a `<NAME>_CHOICE` const holding
+/// the selected variant so downstream code can `match` exhaustively.
+fn write_choice_enum(
+    buffer: &mut String,
+    item: &ConfigItem,
+    val: Option<&toml::Value>,
+) -> Result<()> {
+    let enum_name = to_camel_case(&item.name);
+    let options = item.option_values();
+    let variants = variant_names(&options);
+
+    writeln!(buffer, "#[allow(dead_code)]")?;
+    writeln!(buffer, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]")?;
+    writeln!(buffer, "pub enum {} {{", enum_name)?;
+    for variant in &variants {
+        writeln!(buffer, "    {},", variant)?;
+    }
+    writeln!(buffer, "}}")?;
+
+    writeln!(buffer, "#[allow(dead_code)]")?;
+    writeln!(buffer, "impl {} {{", enum_name)?;
+    writeln!(buffer, "    pub const fn as_str(&self) -> &'static str {{")?;
+    writeln!(buffer, "        match self {{")?;
+    for (variant, option) in variants.iter().zip(&options) {
+        writeln!(
+            buffer,
+            "            {}::{} => {:?},",
+            enum_name, variant, option
+        )?;
+    }
+    writeln!(buffer, "        }}")?;
+    writeln!(buffer, "    }}")?;
+    writeln!(buffer, "}}")?;
+
+    let selected = val
+        .and_then(|v| v.as_str())
+        .and_then(|v| options.iter().position(|o| *o == v));
+    if item.optional {
+        let selected = match selected {
+            Some(index) => format!("Some({}::{})", enum_name, variants[index]),
+            None => "None".to_string(),
+        };
+        writeln!(
+            buffer,
+            "#[allow(dead_code)]\npub const {}_CHOICE: Option<{}> = {};",
+            item.name, enum_name, selected
+        )?;
+    } else if let Some(index) = selected {
+        writeln!(
+            buffer,
+            "#[allow(dead_code)]\npub const {}_CHOICE: {} = {}::{};",
+            item.name, enum_name, enum_name, variants[index]
+        )?;
+    }
+    Ok(())
+}
+
+/// Sanitizes choice options into unique, valid enum variant identifiers.
+fn variant_names(options: &[&str]) -> Vec<String> {
+    unique_identifiers(options, to_camel_case, "V", "")
+}
+
 /// Generates a vector of strings suitable for `--cfg` flags.
 pub fn generate_rust_cfgs(
     items: &[ConfigItem],
@@ -47,6 +133,31 @@ pub fn generate_rust_cfgs(
     Ok(cfgs)
 }
 
+/// Generates `name="value"` strings suitable for `--cfg` flags from string
+/// and choice configs, so downstream code can use `#[cfg(NAME = "value")]`.
+///
+/// Values containing whitespace are skipped since `RUSTFLAGS` is split on it.
+pub fn generate_rust_value_cfgs(
+    items: &[ConfigItem],
+    values: &HashMap<String, toml::Value>,
+) -> Result<Vec<String>> {
+    let mut cfgs = Vec::new();
+
+    for item in items {
+        if !matches!(item.config_type, ConfigType::String | ConfigType::Choice) {
+            continue;
+        }
+        if let Some(s) = values.get(&item.name).and_then(|v| v.as_str()) {
+            if s.is_empty() || s.chars().any(char::is_whitespace) {
+                continue;
+            }
+            cfgs.push(format!("{}={:?}", item.name, s));
+        }
+    }
+
+    Ok(cfgs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -56,28 +167,12 @@ mod tests {
     fn test_generate_rust_consts() -> Result<()> {
         let items = vec![
             ConfigItem {
-                name: "ENABLE_A".to_string(),
-                config_type: ConfigType::Bool,
-                default: None,
                 desc: "A".to_string(),
-                depends_on: None,
-                help: None,
-                options: None,
-                feature: None,
-                range: None,
-                regex: None,
+                ..ConfigItem::test_item("ENABLE_A", ConfigType::Bool)
             },
             ConfigItem {
-                name: "STR_VAL".to_string(),
-                config_type: ConfigType::String,
-                default: None,
                 desc: "S".to_string(),
-                depends_on: None,
-                help: None,
-                options: None,
-                feature: None,
-                range: None,
-                regex: None,
+                ..ConfigItem::test_item("STR_VAL", ConfigType::String)
             },
         ];
 
@@ -94,19 +189,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_generate_consts_doc_comments() -> Result<()> {
+        let items = vec![ConfigItem {
+            desc: "Maximum number of sockets".to_string(),
+            help: Some("Each socket costs 2 KiB.\n\nRaise for servers.\n".to_string()),
+            ..ConfigItem::test_item("MAX_SOCKETS", ConfigType::Int)
+        }];
+
+        let mut values = HashMap::new();
+        values.insert("MAX_SOCKETS".to_string(), toml::Value::Integer(16));
+
+        let code = generate_consts(&items, &values)?;
+        assert!(code.contains(
+            "/// Maximum number of sockets\n\
+             ///\n\
+             /// Each socket costs 2 KiB.\n\
+             ///\n\
+             /// Raise for servers.\n\
+             #[allow(dead_code)]\n\
+             pub const MAX_SOCKETS: i64 = 16;"
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_generate_rust_cfgs() -> Result<()> {
         let items = vec![ConfigItem {
-            name: "ENABLE_A".to_string(),
-            config_type: ConfigType::Bool,
-            default: None,
             desc: "A".to_string(),
-            depends_on: None,
-            help: None,
-            options: None,
-            feature: None,
-            range: None,
-            regex: None,
+            ..ConfigItem::test_item("ENABLE_A", ConfigType::Bool)
         }];
 
         let mut values = HashMap::new();
@@ -116,4 +227,145 @@ mod tests {
         assert_eq!(cfgs, vec!["ENABLE_A".to_string()]);
         Ok(())
     }
+
+    #[test]
+    fn test_generate_choice_enum() -> Result<()> {
+        let items = vec![ConfigItem {
+            desc: "Backend".to_string(),
+            options: Some(vec!["poll".into(), "io-uring".into(), "9p".into()]),
+            ..ConfigItem::test_item("NET_BACKEND", ConfigType::Choice)
+        }];
+
+        let mut values = HashMap::new();
+        values.insert(
+            "NET_BACKEND".to_string(),
+            toml::Value::String("io-uring".to_string()),
+        );
+
+        let code = generate_consts(&items, &values)?;
+        assert!(code.contains("pub const NET_BACKEND: &str = \"io-uring\";"));
+        assert!(code.contains("pub enum NetBackend {\n    Poll,\n    IoUring,\n    V9p,\n}"));
+        assert!(code.contains("NetBackend::IoUring => \"io-uring\","));
+        assert!(code.contains("pub const NET_BACKEND_CHOICE: NetBackend = NetBackend::IoUring;"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_optional_choice() -> Result<()> {
+        let items = vec![ConfigItem {
+            desc: "Logger".to_string(),
+            options: Some(vec!["uart".into(), "rtt".into()]),
+            optional: true,
+            ..ConfigItem::test_item("LOGGER", ConfigType::Choice)
+        }];
+
+        let mut values = HashMap::new();
+        values.insert("LOGGER".to_string(), toml::Value::String(String::new()));
+        let code = generate_consts(&items, &values)?;
+        assert!(!code.contains("pub const LOGGER:"));
+        assert!(code.contains("pub const LOGGER_CHOICE: Option<Logger> = None;"));
+
+        values.insert("LOGGER".to_string(), toml::Value::String("rtt".to_string()));
+        let code = generate_consts(&items, &values)?;
+        assert!(code.contains("pub const LOGGER: &str = \"rtt\";"));
+        assert!(code.contains("pub const LOGGER_CHOICE: Option<Logger> = Some(Logger::Rtt);"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_choice_option_values() -> Result<()> {
+        let items = vec![ConfigItem {
+            desc: "Clock".to_string(),
+            options: Some(vec!["slow".into(), "fast".into()]),
+            option_values: Some(vec![
+                toml::Value::Integer(48_000_000),
+                toml::Value::Integer(96_000_000),
+            ]),
+            ..ConfigItem::test_item("CPU_FREQ", ConfigType::Choice)
+        }];
+
+        let mut values = HashMap::new();
+        values.insert(
+            "CPU_FREQ".to_string(),
+            toml::Value::String("fast".to_string()),
+        );
+
+        let code = generate_consts(&items, &values)?;
+        assert!(code.contains("pub const CPU_FREQ: i64 = 96000000;"));
+        assert!(code.contains("CpuFreq::Fast => \"fast\","));
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_choice_float_and_string_option_values() -> Result<()> {
+        let items = vec![
+            ConfigItem {
+                desc: "Ratio".to_string(),
+                options: Some(vec!["half".into(), "full".into()]),
+                option_values: Some(vec![toml::Value::Float(0.5), toml::Value::Float(1.0)]),
+                ..ConfigItem::test_item("RATIO", ConfigType::Choice)
+            },
+            ConfigItem {
+                desc: "Banner".to_string(),
+                options: Some(vec!["quoted".into()]),
+                option_values: Some(vec![toml::Value::String("say \"hi\"".to_string())]),
+                ..ConfigItem::test_item("BANNER", ConfigType::Choice)
+            },
+        ];
+
+        let mut values = HashMap::new();
+        values.insert("RATIO".to_string(), toml::Value::String("half".to_string()));
+        values.insert(
+            "BANNER".to_string(),
+            toml::Value::String("quoted".to_string()),
+        );
+
+        let code = generate_consts(&items, &values)?;
+        assert!(code.contains("pub const RATIO: f64 = 0.5;"), "{}", code);
+        assert!(
+            code.contains(r#"pub const BANNER: &str = "say \"hi\"";"#),
+            "{}",
+            code
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_variant_names() {
+        assert_eq!(
+            variant_names(&["RR", "a-b", "a_b", "1st"]),
+            vec!["Rr", "AB", "AB2", "V1st"]
+        );
+    }
+
+    #[test]
+    fn test_generate_rust_value_cfgs() -> Result<()> {
+        let items = vec![
+            ConfigItem {
+                desc: "Level".to_string(),
+                options: Some(vec!["high".into(), "low".into()]),
+                ..ConfigItem::test_item("LEVEL", ConfigType::Choice)
+            },
+            ConfigItem {
+                desc: "Greeting".to_string(),
+                ..ConfigItem::test_item("GREETING", ConfigType::String)
+            },
+            ConfigItem {
+                desc: "Port".to_string(),
+                ..ConfigItem::test_item("PORT", ConfigType::Int)
+            },
+        ];
+
+        let mut values = HashMap::new();
+        values.insert("LEVEL".to_string(), toml::Value::String("high".to_string()));
+        values.insert(
+            "GREETING".to_string(),
+            toml::Value::String("hello world".to_string()),
+        );
+        values.insert("PORT".to_string(), toml::Value::Integer(80));
+
+        let cfgs = generate_rust_value_cfgs(&items, &values)?;
+        assert_eq!(cfgs, vec!["LEVEL=\"high\"".to_string()]);
+        Ok(())
+    }
 }
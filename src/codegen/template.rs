@@ -0,0 +1,170 @@
+use crate::schema::{ConfigItem, ConfigType};
+use anyhow::Result;
+use std::fmt::Write;
+
+/// Generates a commented `.config` scaffold from the schema alone: every
+/// symbol is assigned its default and preceded by comments describing its
+/// type, constraints and help. Symbols without a default are left commented
+/// out.
+pub fn generate(items: &[ConfigItem]) -> Result<String> {
+    let mut buffer = String::new();
+
+    writeln!(
+        buffer,
+        "# Config template generated by anaxa-builder from the schema."
+    )?;
+    writeln!(
+        buffer,
+        "# Every symbol is set to its default; edit the values and save as .config."
+    )?;
+
+    for item in items {
+        writeln!(buffer)?;
+        write_item(&mut buffer, item)?;
+    }
+
+    Ok(buffer)
+}
+
+fn write_item(buffer: &mut String, item: &ConfigItem) -> Result<()> {
+    let config_type = serde_json::to_value(&item.config_type)?;
+    let config_type = config_type.as_str().unwrap_or_default();
+    if item.desc.is_empty() {
+        writeln!(buffer, "# {} ({})", item.name, config_type)?;
+    } else {
+        writeln!(
+            buffer,
+            "# {} ({}): {}",
+            item.name,
+            config_type,
+            item.desc.replace('\n', " ")
+        )?;
+    }
+
+    if let Some(replacement) = &item.deprecated {
+        match replacement.as_str() {
+            "" => writeln!(buffer, "#   deprecated")?,
+            name => writeln!(buffer, "#   deprecated, use {} instead", name)?,
+        }
+    }
+    if let Some(dep) = &item.depends_on {
+        writeln!(buffer, "#   depends on: {}", dep)?;
+    }
+    if let Some(options) = &item.options {
+        let options: Vec<String> = options
+            .iter()
+            .map(|o| {
+                let value = toml::Value::String(o.value.clone()).to_string();
+                match (&o.label, &o.depends_on) {
+                    (Some(label), _) => format!("{} ({})", value, label),
+                    (None, Some(dep)) => format!("{} (if {})", value, dep),
+                    (None, None) => value,
+                }
+            })
+            .collect();
+        writeln!(buffer, "#   options: {}", options.join(", "))?;
+        if item.optional {
+            writeln!(buffer, "#   optional: \"\" selects none")?;
+        }
+    }
+    if let Some((min, max)) = item.range {
+        writeln!(
+            buffer,
+            "#   range: [{}, {}]",
+            format_value(item, &toml::Value::Integer(min)),
+            format_value(item, &toml::Value::Integer(max))
+        )?;
+    }
+    if let Some((min, max)) = item.float_range {
+        writeln!(buffer, "#   range: [{:?}, {:?}]", min, max)?;
+    }
+    if let Some(regex) = &item.regex {
+        writeln!(buffer, "#   regex: {}", regex)?;
+    }
+    for conditional in item.default_if.iter().flatten() {
+        writeln!(
+            buffer,
+            "#   default if {}: {}",
+            conditional.depends_on,
+            format_value(item, &conditional.value)
+        )?;
+    }
+    if let Some(var) = &item.default_env {
+        writeln!(buffer, "#   default from ${} when set", var)?;
+    }
+    for line in item.help.iter().flat_map(|help| help.trim().lines()) {
+        writeln!(buffer, "#   {}", line.trim())?;
+    }
+
+    match &item.default {
+        Some(default) => writeln!(buffer, "{} = {}", item.name, format_value(item, default))?,
+        None => writeln!(buffer, "# {} =", item.name)?,
+    }
+    Ok(())
+}
+
+/// Renders a value as TOML, writing hex symbols as hex literals. Hex values
+/// above `i64::MAX` don't fit a TOML integer and are quoted instead.
+fn format_value(item: &ConfigItem, val: &toml::Value) -> String {
+    match crate::schema::hex_value(val) {
+        Some(u) if item.config_type == ConfigType::Hex && u > i64::MAX as u64 => {
+            format!("\"0x{:x}\"", u)
+        }
+        Some(u) if item.config_type == ConfigType::Hex => format!("0x{:x}", u),
+        _ => val.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::KconfigFile;
+
+    #[test]
+    fn test_generate_template() -> Result<()> {
+        let parsed: KconfigFile = toml::from_str(
+            r#"
+            [[config]]
+            name = "SCHED"
+            type = "choice"
+            desc = "Scheduler"
+            default = "fifo"
+            options = ["rr", "fifo"]
+            help = """
+            Task scheduling policy.
+            FIFO has the least overhead."""
+
+            [[config]]
+            name = "BASE_ADDR"
+            type = "hex"
+            desc = "Load address"
+            default = 0x8000
+            range = [0x1000, 0xFFFF]
+
+            [[config]]
+            name = "BOARD"
+            type = "choice"
+            desc = "Board"
+            options = ["devkit", "prod"]
+        "#,
+        )?;
+        let template = generate(&parsed.configs.unwrap())?;
+
+        assert!(template.contains(
+            "# SCHED (choice): Scheduler\n\
+             #   options: \"rr\", \"fifo\"\n\
+             #   Task scheduling policy.\n\
+             #   FIFO has the least overhead.\n\
+             SCHED = \"fifo\"\n"
+        ));
+        assert!(template.contains("#   range: [0x1000, 0xffff]\nBASE_ADDR = 0x8000\n"));
+        assert!(template.contains("# BOARD =\n"));
+
+        // The scaffold is a loadable config.
+        let table: toml::Table = toml::from_str(&template)?;
+        assert_eq!(table["SCHED"].as_str(), Some("fifo"));
+        assert_eq!(table["BASE_ADDR"].as_integer(), Some(0x8000));
+        assert!(!table.contains_key("BOARD"));
+        Ok(())
+    }
+}
@@ -1,64 +1,860 @@
+use crate::error::AnaxaError;
 use crate::evaluator;
-use crate::schema::ConfigItem;
-use anyhow::{Context, Result};
-use std::collections::HashMap;
+use crate::schema::{ConfigItem, ConfigNode};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Write};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use toml::{Table, Value};
+use toml_edit::DocumentMut;
 
-pub fn load_config(path: &Path, items: &[ConfigItem]) -> Result<HashMap<String, Value>> {
+/// Loads `.config` values on top of schema defaults. Values that fail
+/// validation are reported as warnings and skipped.
+pub fn load_config(
+    path: &Path,
+    items: &[ConfigItem],
+) -> Result<HashMap<String, Value>, AnaxaError> {
+    let loaded = load_config_checked(path, items, false)?;
+    warn_unknown_keys(path, &loaded.unknown);
+    warn_deprecated(&loaded.deprecated);
+    Ok(loaded.values)
+}
+
+/// Like [`load_config`], but fails on the first value that doesn't validate.
+pub fn load_config_strict(
+    path: &Path,
+    items: &[ConfigItem],
+) -> Result<HashMap<String, Value>, AnaxaError> {
+    let loaded = load_config_checked(path, items, true)?;
+    warn_unknown_keys(path, &loaded.unknown);
+    warn_deprecated(&loaded.deprecated);
+    Ok(loaded.values)
+}
+
+/// Like [`load_config`], or [`load_config_strict`] if `strict`, but doesn't
+/// enforce `exclusive_group`s, so edits that resolve a conflict can be
+/// applied before calling [`check_exclusive_groups`].
+pub fn load_config_unchecked(
+    path: &Path,
+    items: &[ConfigItem],
+    strict: bool,
+) -> Result<HashMap<String, Value>, AnaxaError> {
+    let loaded = load_config_impl(path, items, strict)?;
+    warn_unknown_keys(path, &loaded.unknown);
+    warn_deprecated(&loaded.deprecated);
+    Ok(loaded.values)
+}
+
+/// Like [`load_config`], but also returns the keys in the file that match
+/// no known config item, so callers can surface renamed or removed symbols.
+pub fn load_config_reporting(
+    path: &Path,
+    items: &[ConfigItem],
+) -> Result<(HashMap<String, Value>, Vec<String>), AnaxaError> {
+    let loaded = load_config_checked(path, items, false)?;
+    warn_deprecated(&loaded.deprecated);
+    Ok((loaded.values, loaded.unknown))
+}
+
+/// Resolves `default_if` against the loaded values for every symbol that is
+/// neither set in the file (`explicit`) nor overridden from the environment.
+fn resolve_conditional_defaults(
+    items: &[ConfigItem],
+    values: &mut HashMap<String, Value>,
+    mut explicit: HashSet<String>,
+) {
+    explicit.extend(
+        items
+            .iter()
+            .filter(|i| {
+                i.default_env
+                    .as_ref()
+                    .is_some_and(|var| std::env::var_os(var).is_some())
+            })
+            .map(|i| i.name.clone()),
+    );
+    evaluator::apply_conditional_defaults(items, values, &explicit);
+}
+
+fn warn_unknown_keys(path: &Path, unknown: &[String]) {
+    if !unknown.is_empty() {
+        eprintln!(
+            "{} unknown config keys in {:?}: {}",
+            crate::term::Styler::stderr().warning(),
+            path,
+            unknown.join(", ")
+        );
+    }
+}
+
+fn warn_deprecated(messages: &[String]) {
+    for message in messages {
+        eprintln!("{} {}", crate::term::Styler::stderr().warning(), message);
+    }
+}
+
+struct Loaded {
+    values: HashMap<String, Value>,
+    /// Keys in the file that match no config item.
+    unknown: Vec<String>,
+    /// A warning for every deprecated symbol the file sets.
+    deprecated: Vec<String>,
+}
+
+/// [`load_config_impl`], failing if the values enable more than one member
+/// of an `exclusive_group`.
+fn load_config_checked(
+    path: &Path,
+    items: &[ConfigItem],
+    strict: bool,
+) -> Result<Loaded, AnaxaError> {
+    let loaded = load_config_impl(path, items, strict)?;
+    check_exclusive_groups(items, &loaded.values)
+        .map_err(|e| e.in_file("Invalid configuration", path))?;
+    Ok(loaded)
+}
+
+fn load_config_impl(path: &Path, items: &[ConfigItem], strict: bool) -> Result<Loaded, AnaxaError> {
     let mut values = evaluator::collect_defaults(items);
+    let mut unknown = Vec::new();
+    let mut deprecated = Vec::new();
 
     if path.exists() {
-        let content = fs::read_to_string(path)
-            .with_context(|| format!("Failed to read config file: {:?}", path))?;
+        let mut explicit = HashSet::new();
+        (unknown, deprecated) = apply_file(path, items, strict, &mut values, &mut explicit)?;
+        resolve_conditional_defaults(items, &mut values, explicit);
+    } else {
+        // 生成默认配置文件
+        write_sections(path, &[("", items)], &values)?;
+    }
 
-        let parsed: Table = toml::from_str(&content)
-            .with_context(|| format!("Failed to parse config file: {:?}", path))?;
+    if strict {
+        // Templates stay in `values`; only make sure they can be expanded.
+        if let Some(e) = interpolate(&mut values.clone()).into_iter().next() {
+            return Err(e.in_file("Invalid interpolation", path));
+        }
+    }
+    Ok(Loaded {
+        values,
+        unknown,
+        deprecated,
+    })
+}
+
+/// Loads a stack of config fragments, e.g. a board's base config followed
+/// by local overrides, on top of schema defaults. Later layers win; layers
+/// that don't exist are skipped and, unlike [`load_config`], never created.
+pub fn load_config_layers(
+    layers: &[PathBuf],
+    items: &[ConfigItem],
+) -> Result<HashMap<String, Value>, AnaxaError> {
+    let mut values = evaluator::collect_defaults(items);
+    let mut explicit = HashSet::new();
+    for path in layers.iter().filter(|p| p.exists()) {
+        let (unknown, deprecated) = apply_file(path, items, false, &mut values, &mut explicit)?;
+        warn_unknown_keys(path, &unknown);
+        warn_deprecated(&deprecated);
+    }
+    resolve_conditional_defaults(items, &mut values, explicit);
+    check_exclusive_groups(items, &values)?;
+    Ok(values)
+}
 
-        for (key, val) in parsed {
-            if let Some(item) = items.iter().find(|i| i.name == key) {
-                if let Err(e) = item.validate(&val) {
-                    eprintln!("Warning: {}", e);
-                    continue;
+/// Resolves `table`, e.g. the result of [`merge_configs`], on top of schema
+/// defaults the way [`load_config`] resolves a file: aliases are mapped to
+/// current names and values are normalized and validated. `origin` names
+/// the source of the values in messages.
+pub fn load_table(
+    origin: &Path,
+    table: &Table,
+    items: &[ConfigItem],
+    strict: bool,
+) -> Result<HashMap<String, Value>, AnaxaError> {
+    let mut values = evaluator::collect_defaults(items);
+    let mut explicit = HashSet::new();
+    let (unknown, deprecated) =
+        apply_table(origin, table, items, strict, &mut values, &mut explicit)?;
+    warn_unknown_keys(origin, &unknown);
+    warn_deprecated(&deprecated);
+    resolve_conditional_defaults(items, &mut values, explicit);
+    check_exclusive_groups(items, &values)
+        .map_err(|e| e.in_file("Invalid configuration", origin))?;
+    Ok(values)
+}
+
+/// Applies the values set in the config file at `path` on top of `values`
+/// and records the symbols it sets in `explicit`. Returns the unknown keys
+/// and deprecation warnings found in the file.
+fn apply_file(
+    path: &Path,
+    items: &[ConfigItem],
+    strict: bool,
+    values: &mut HashMap<String, Value>,
+    explicit: &mut HashSet<String>,
+) -> Result<(Vec<String>, Vec<String>), AnaxaError> {
+    apply_table(path, &read_table(path)?, items, strict, values, explicit)
+}
+
+/// [`apply_file`] for an already parsed `table` read from `path`.
+fn apply_table(
+    path: &Path,
+    table: &Table,
+    items: &[ConfigItem],
+    strict: bool,
+    values: &mut HashMap<String, Value>,
+    explicit: &mut HashSet<String>,
+) -> Result<(Vec<String>, Vec<String>), AnaxaError> {
+    let mut unknown = Vec::new();
+    let mut deprecated = Vec::new();
+    for (key, val) in table {
+        let Some(item) = find_item(items, key) else {
+            unknown.push(key.clone());
+            continue;
+        };
+        if item.name != *key {
+            // The canonical name wins over any of its aliases.
+            if table.contains_key(&item.name) {
+                continue;
+            }
+            eprintln!(
+                "Note: '{}' in {:?} has been renamed to '{}'",
+                key, path, item.name
+            );
+        }
+        deprecated.extend(item.deprecation_message());
+        if let Err(e) = item.validate(val) {
+            if strict {
+                return Err(AnaxaError::validation(
+                    &item.name,
+                    format!("Invalid value for '{}' in {:?}: {}", key, path, e),
+                ));
+            }
+            eprintln!("{} {}", crate::term::Styler::stderr().warning(), e);
+            continue;
+        }
+        explicit.insert(item.name.clone());
+        values.insert(item.name.clone(), item.normalize(val.clone()));
+    }
+    Ok((unknown, deprecated))
+}
+
+/// Returns `values` with `${NAME}` references expanded, as seen by code
+/// generation and builds. Loaded values keep the templates so that saving
+/// them doesn't bake in the expansion. Values that can't be expanded are
+/// kept as they are, with a warning.
+pub fn expand_templates(values: &HashMap<String, Value>) -> HashMap<String, Value> {
+    let mut expanded = values.clone();
+    for e in interpolate(&mut expanded) {
+        eprintln!("{} {}", crate::term::Styler::stderr().warning(), e);
+    }
+    expanded
+}
+
+/// Expands `${NAME}` references in string values, e.g. `"build-${VERSION}"`,
+/// using the other values. Values with undefined or cyclic references are
+/// left untouched and reported in the returned errors.
+fn interpolate(values: &mut HashMap<String, Value>) -> Vec<AnaxaError> {
+    let mut names: Vec<String> = values
+        .iter()
+        .filter(|(_, v)| v.as_str().is_some_and(|s| s.contains("${")))
+        .map(|(k, _)| k.clone())
+        .collect();
+    names.sort();
+
+    let mut errors = Vec::new();
+    let mut resolved = HashMap::new();
+    for name in names {
+        if let Err(e) = expand(&name, values, &mut resolved, &mut Vec::new()) {
+            errors.push(AnaxaError::validation(&name, e));
+        }
+    }
+    for (name, s) in resolved {
+        values.insert(name, Value::String(s));
+    }
+    errors
+}
+
+fn expand(
+    name: &str,
+    values: &HashMap<String, Value>,
+    resolved: &mut HashMap<String, String>,
+    stack: &mut Vec<String>,
+) -> Result<String, String> {
+    if let Some(s) = resolved.get(name) {
+        return Ok(s.clone());
+    }
+    if stack.iter().any(|n| n == name) {
+        stack.push(name.to_string());
+        return Err(format!(
+            "Cyclic reference in '{}': {}",
+            stack[0],
+            stack.join(" -> ")
+        ));
+    }
+    let template = match values.get(name) {
+        Some(Value::String(s)) => s,
+        Some(other) => return Ok(other.to_string()),
+        None => {
+            return Err(format!(
+                "'{}' references undefined symbol '{}'",
+                stack.last().map(String::as_str).unwrap_or_default(),
+                name
+            ))
+        }
+    };
+
+    stack.push(name.to_string());
+    let mut out = String::new();
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        out.push_str(&expand(
+            &rest[start + 2..start + 2 + len],
+            values,
+            resolved,
+            stack,
+        )?);
+        rest = &rest[start + 3 + len..];
+    }
+    out.push_str(rest);
+    stack.pop();
+
+    resolved.insert(name.to_string(), out.clone());
+    Ok(out)
+}
+
+/// Finds the config item for a `.config` key, by name or by alias.
+fn find_item<'a>(items: &'a [ConfigItem], key: &str) -> Option<&'a ConfigItem> {
+    items.iter().find(|i| i.name == key).or_else(|| {
+        items
+            .iter()
+            .find(|i| i.alias.iter().flatten().any(|a| a == key))
+    })
+}
+
+/// Names of the symbols `path` sets explicitly, with aliases resolved to the
+/// current names. A missing file sets nothing.
+pub fn explicit_keys(path: &Path, items: &[ConfigItem]) -> Result<HashSet<String>> {
+    if !path.exists() {
+        return Ok(HashSet::new());
+    }
+    Ok(read_table(path)?
+        .keys()
+        .filter_map(|key| find_item(items, key))
+        .map(|item| item.name.clone())
+        .collect())
+}
+
+/// The symbols `path` sets to a non-default value in `values`, i.e. those
+/// `defined(NAME)` is true for. See [`evaluator::defined_symbols`].
+pub fn defined_keys(
+    path: &Path,
+    items: &[ConfigItem],
+    values: &HashMap<String, Value>,
+) -> Result<HashSet<String>> {
+    let explicit = explicit_keys(path, items)?;
+    Ok(evaluator::defined_symbols(items, values, &explicit))
+}
+
+fn read_table(path: &Path) -> Result<Table, AnaxaError> {
+    let content = fs::read_to_string(path).map_err(|e| AnaxaError::io(path, e))?;
+    toml::from_str(&content).map_err(|e| AnaxaError::parse(path, e))
+}
+
+/// A problem found by [`verify_config`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// The value doesn't validate against its config item.
+    InvalidValue { key: String, error: String },
+    /// An enabled bool symbol whose `depends_on` is not satisfied.
+    UnmetDependency { key: String, depends_on: String },
+    /// A symbol without a default that the file doesn't set.
+    Missing { key: String },
+    /// A key that matches no config item.
+    Unknown { key: String },
+    /// More than one enabled member of an `exclusive_group`.
+    ExclusiveGroup {
+        group: Vec<String>,
+        enabled: Vec<String>,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::InvalidValue { error, .. } => write!(f, "{}", error),
+            Violation::UnmetDependency { key, depends_on } => {
+                write!(
+                    f,
+                    "'{}' is enabled but depends on unmet '{}'",
+                    key, depends_on
+                )
+            }
+            Violation::Missing { key } => write!(f, "'{}' has no default and is not set", key),
+            Violation::Unknown { key } => write!(f, "'{}' is not a known config", key),
+            Violation::ExclusiveGroup { group, enabled } => write!(
+                f,
+                "At most one of {} may be enabled, but {} are",
+                group.join(", "),
+                enabled.join(", ")
+            ),
+        }
+    }
+}
+
+/// Checks that the config file at `path` is complete and valid for
+/// `items`. Unlike [`load_config`], a missing file is an error and nothing
+/// is written.
+pub fn verify_config(path: &Path, items: &[ConfigItem]) -> Result<Vec<Violation>> {
+    let table = read_table(path)?;
+    let mut violations = Vec::new();
+    let mut values = evaluator::collect_defaults(items);
+    let mut explicit = HashSet::new();
+    // Symbols the file sets, under their canonical names.
+    let mut present = HashSet::new();
+
+    let mut keys: Vec<&String> = table.keys().collect();
+    keys.sort();
+    for key in keys {
+        let Some(item) = find_item(items, key) else {
+            violations.push(Violation::Unknown { key: key.clone() });
+            continue;
+        };
+        // The canonical name wins over any of its aliases.
+        if item.name != **key && table.contains_key(&item.name) {
+            continue;
+        }
+        present.insert(item.name.as_str());
+        let val = &table[key.as_str()];
+        match item.validate(val) {
+            Ok(()) => {
+                explicit.insert(item.name.clone());
+                values.insert(item.name.clone(), item.normalize(val.clone()));
+            }
+            Err(error) => violations.push(Violation::InvalidValue {
+                key: key.clone(),
+                error,
+            }),
+        }
+    }
+
+    resolve_conditional_defaults(items, &mut values, explicit.clone());
+
+    let mut evaluator = evaluator::Evaluator::new();
+    for (name, val) in &values {
+        let _ = evaluator.set_variable(name, val);
+    }
+    evaluator.set_explicit(evaluator::defined_symbols(items, &values, &explicit));
+
+    for item in items {
+        // `values` holds the resolved defaults, including `default_if` and
+        // `default_env`, so only symbols nothing assigns end up here.
+        if !item.optional
+            && !present.contains(item.name.as_str())
+            && !values.contains_key(&item.name)
+        {
+            violations.push(Violation::Missing {
+                key: item.name.clone(),
+            });
+        }
+        if let Some(expr) = &item.depends_on {
+            let enabled = values.get(&item.name).and_then(|v| v.as_bool()) == Some(true);
+            if enabled && !evaluator.check_dependency(expr).unwrap_or(true) {
+                violations.push(Violation::UnmetDependency {
+                    key: item.name.clone(),
+                    depends_on: expr.clone(),
+                });
+            }
+        }
+    }
+    violations.extend(exclusive_group_conflicts(items, &values).into_iter().map(
+        |(group, enabled)| Violation::ExclusiveGroup {
+            group: group.to_vec(),
+            enabled: enabled.into_iter().map(String::from).collect(),
+        },
+    ));
+
+    Ok(violations)
+}
+
+/// A symbol that two config fragments set to different values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    pub key: String,
+    pub previous: (PathBuf, Value),
+    pub overridden_by: (PathBuf, Value),
+}
+
+/// Layers `fragments` on top of `base`, later files winning, like the
+/// kernel's `merge_config.sh`. Values are not checked against the schema;
+/// resolve the result with [`load_table`].
+///
+/// Returns the merged values together with every symbol that a fragment
+/// set to a different value than an earlier fragment did.
+pub fn merge_configs(
+    base: &Path,
+    fragments: &[PathBuf],
+) -> Result<(HashMap<String, Value>, Vec<MergeConflict>)> {
+    let mut merged: HashMap<String, Value> = read_table(base)?.into_iter().collect();
+    let mut set_by: HashMap<String, (PathBuf, Value)> = HashMap::new();
+    let mut conflicts = Vec::new();
+
+    for fragment in fragments {
+        let mut entries: Vec<_> = read_table(fragment)?.into_iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        for (key, val) in entries {
+            let current = (fragment.clone(), val.clone());
+            if let Some(previous) = set_by.insert(key.clone(), current.clone()) {
+                if previous.1 != val {
+                    conflicts.push(MergeConflict {
+                        key: key.clone(),
+                        previous,
+                        overridden_by: current,
+                    });
                 }
-                values.insert(key, val);
             }
+            merged.insert(key, val);
         }
+    }
+
+    Ok((merged, conflicts))
+}
+
+/// Writes `values` to `path` in schema order: symbols are grouped by the
+/// menu that defines them, in tree order, each group under a `# <menu>`
+/// comment. Values for unknown symbols go last, sorted by name.
+///
+/// If `path` already holds a TOML document it is updated in place instead,
+/// keeping its comments, blank lines and key order. Keys missing from
+/// `values` are dropped and new ones are appended as above.
+pub fn save_config(path: &Path, tree: &ConfigNode, values: &HashMap<String, Value>) -> Result<()> {
+    let mut sections = Vec::new();
+    collect_sections(tree, &mut sections);
+    Ok(write_sections(path, &sections, values)?)
+}
+
+/// Like [`save_config`], but with `prune_hidden` set first drops the values
+/// of symbols whose dependencies are unmet, see [`prune_hidden`].
+pub fn save_config_with(
+    path: &Path,
+    tree: &ConfigNode,
+    values: &HashMap<String, Value>,
+    prune_hidden: bool,
+) -> Result<()> {
+    if prune_hidden {
+        save_config(path, tree, &self::prune_hidden(tree, values))
     } else {
-        // 生成默认配置文件
-        for item in items {
-            if item.default.is_some() {
-                values.insert(item.name.clone(), item.default.clone().unwrap());
+        save_config(path, tree, values)
+    }
+}
+
+/// Writes every symbol in `items` with its effective value, as a
+/// reproducibility artifact to check into CI. Unlike a defconfig, which keeps
+/// only what differs from the defaults, the snapshot is exhaustive: symbols
+/// without a value are listed under `unset` and symbols whose `depends_on`
+/// is unmet under `inactive`. `schema_hash` changes whenever the schema does.
+pub fn write_resolved_snapshot(
+    path: &Path,
+    items: &[ConfigItem],
+    values: &HashMap<String, Value>,
+    evaluator: &evaluator::Evaluator,
+) -> Result<()> {
+    #[derive(serde::Serialize)]
+    struct Snapshot<'a> {
+        schema_hash: String,
+        inactive: Vec<&'a str>,
+        unset: Vec<&'a str>,
+        values: BTreeMap<&'a str, Value>,
+    }
+
+    let mut snapshot = Snapshot {
+        schema_hash: schema_hash(items)?,
+        inactive: Vec::new(),
+        unset: Vec::new(),
+        values: BTreeMap::new(),
+    };
+    for item in items {
+        let name = item.name.as_str();
+        match values.get(name) {
+            Some(val) => {
+                snapshot.values.insert(name, item.file_value(val));
             }
+            None => snapshot.unset.push(name),
+        }
+        let met = item
+            .depends_on
+            .as_ref()
+            .is_none_or(|expr| evaluator.check_dependency(expr).unwrap_or(true));
+        if !met {
+            snapshot.inactive.push(name);
         }
-        save_config(path, &values)?;
     }
+    snapshot.inactive.sort_unstable();
+    snapshot.unset.sort_unstable();
 
-    Ok(values)
+    let content = format!(
+        "# Resolved configuration generated by anaxa-builder, do not edit\n{}",
+        toml::to_string(&snapshot)?
+    );
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| AnaxaError::io(parent, e))?;
+    }
+    fs::write(path, content).map_err(|e| AnaxaError::io(path, e))?;
+    Ok(())
+}
+
+/// Hashes the schema, so a snapshot records which schema it was resolved
+/// against.
+fn schema_hash(items: &[ConfigItem]) -> Result<String> {
+    Ok(stable_hash(serde_json::to_string(items)?.as_bytes()))
+}
+
+/// Hashes `data` with 64-bit FNV-1a. Unlike `DefaultHasher`, whose algorithm
+/// may change between Rust releases, this keeps hashes written to files
+/// stable across toolchains.
+pub(crate) fn stable_hash(data: &[u8]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    let hash = data.iter().fold(OFFSET_BASIS, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(PRIME)
+    });
+    format!("{:016x}", hash)
+}
+
+fn collect_sections<'a>(node: &'a ConfigNode, sections: &mut Vec<(&'a str, &'a [ConfigItem])>) {
+    sections.push((node.desc.as_str(), node.configs.as_slice()));
+    for child in &node.children {
+        collect_sections(child, sections);
+    }
 }
 
-pub fn save_config(path: &Path, values: &HashMap<String, Value>) -> Result<()> {
-    let mut table = Table::new();
+fn write_sections(
+    path: &Path,
+    sections: &[(&str, &[ConfigItem])],
+    values: &HashMap<String, Value>,
+) -> Result<(), AnaxaError> {
+    let by_name: HashMap<&str, &ConfigItem> = sections
+        .iter()
+        .flat_map(|(_, items)| items.iter())
+        .map(|item| (item.name.as_str(), item))
+        .collect();
+    // Values in the form they are written, see `ConfigItem::file_value`.
+    let encoded: HashMap<String, Value> = values
+        .iter()
+        .map(|(key, val)| {
+            let val = match by_name.get(key.as_str()) {
+                Some(item) => item.file_value(val),
+                None => val.clone(),
+            };
+            (key.clone(), val)
+        })
+        .collect();
+
+    let existing = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| content.parse::<DocumentMut>().ok());
+
+    let mut content = String::new();
+    let mut written = HashSet::new();
+    if let Some(mut doc) = existing {
+        update_document(&mut doc, &encoded, &mut written)?;
+        content = doc.to_string();
+    }
+
+    for (title, items) in sections {
+        let entries: Vec<(&str, &Value)> = items
+            .iter()
+            .filter(|item| written.insert(item.name.clone()))
+            .filter_map(|item| Some((item.name.as_str(), encoded.get(&item.name)?)))
+            .collect();
+        push_section(&mut content, title, &entries)?;
+    }
+
+    let mut rest: Vec<(&str, &Value)> = encoded
+        .iter()
+        .filter(|(k, _)| !written.contains(k.as_str()))
+        .map(|(k, v)| (k.as_str(), v))
+        .collect();
+    rest.sort_by_key(|(k, _)| *k);
+    push_section(&mut content, "", &rest)?;
 
-    for (k, v) in values {
-        table.insert(k.clone(), v.clone());
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| AnaxaError::io(parent, e))?;
     }
+    fs::write(path, content).map_err(|e| AnaxaError::io(path, e))?;
+
+    Ok(())
+}
 
-    let content = toml::to_string_pretty(&table)?;
-    fs::write(path, content).with_context(|| format!("Failed to write config file: {:?}", path))?;
+/// Updates the keys already in `doc` from `values`, keeping their
+/// surrounding comments, and removes the ones `values` no longer has.
+/// Every key left in the document is recorded in `written`.
+fn update_document(
+    doc: &mut DocumentMut,
+    values: &HashMap<String, Value>,
+    written: &mut HashSet<String>,
+) -> Result<(), AnaxaError> {
+    let keys: Vec<String> = doc.iter().map(|(k, _)| k.to_string()).collect();
+    for key in keys {
+        let Some(val) = values.get(&key) else {
+            doc.remove(&key);
+            continue;
+        };
+        let new: toml_edit::Value = val.to_string().parse().map_err(|e| {
+            AnaxaError::validation(&key, format!("Failed to encode value for '{}': {}", key, e))
+        })?;
+        match doc.get_mut(&key).and_then(|item| item.as_value_mut()) {
+            Some(old) => {
+                let decor = old.decor().clone();
+                *old = new;
+                *old.decor_mut() = decor;
+            }
+            None => doc[key.as_str()] = toml_edit::Item::Value(new),
+        }
+        written.insert(key);
+    }
+    Ok(())
+}
 
+/// Appends `entries`, separated from earlier sections by a blank line and
+/// preceded by a `# title` comment when the title is non-empty.
+fn push_section(
+    content: &mut String,
+    title: &str,
+    entries: &[(&str, &Value)],
+) -> Result<(), AnaxaError> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    if !content.is_empty() {
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push('\n');
+    }
+    if !title.is_empty() {
+        let _ = writeln!(content, "# {}", title);
+    }
+    for (key, val) in entries {
+        let mut entry = Table::new();
+        entry.insert(key.to_string(), (*val).clone());
+        let encoded = toml::to_string(&entry).map_err(|e| {
+            AnaxaError::validation(key, format!("Failed to encode value for '{}': {}", key, e))
+        })?;
+        content.push_str(&encoded);
+    }
     Ok(())
 }
 
+/// Ensures at most one member of every `exclusive_group` is enabled in
+/// `values`. Groups are read from `items` as returned by
+/// [`flatten_configs`](crate::parser::flatten_configs).
+pub fn check_exclusive_groups(
+    items: &[ConfigItem],
+    values: &HashMap<String, Value>,
+) -> Result<(), AnaxaError> {
+    match exclusive_group_conflicts(items, values).first() {
+        Some((group, enabled)) => Err(AnaxaError::validation(
+            enabled[1],
+            format!(
+                "At most one of {} may be enabled, but {} are",
+                group.join(", "),
+                enabled.join(", ")
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Every `exclusive_group` with more than one enabled member in `values`,
+/// with those members.
+fn exclusive_group_conflicts<'a>(
+    items: &'a [ConfigItem],
+    values: &HashMap<String, Value>,
+) -> Vec<(&'a [String], Vec<&'a str>)> {
+    let mut seen = HashSet::new();
+    let mut conflicts = Vec::new();
+    for group in items.iter().filter_map(|i| i.exclusive_group.as_deref()) {
+        if !seen.insert(group) {
+            continue;
+        }
+        let enabled: Vec<&str> = group
+            .iter()
+            .filter(|m| values.get(*m).and_then(|v| v.as_bool()) == Some(true))
+            .map(|m| m.as_str())
+            .collect();
+        if enabled.len() > 1 {
+            conflicts.push((group, enabled));
+        }
+    }
+    conflicts
+}
+
+/// Drops the values of symbols that are hidden under `values`, i.e. whose
+/// own `depends_on` or that of an enclosing menu is unmet. Symbols hidden only
+/// by `visible_if` keep their values.
+pub fn prune_hidden(tree: &ConfigNode, values: &HashMap<String, Value>) -> HashMap<String, Value> {
+    let mut evaluator = evaluator::Evaluator::new();
+    for (name, val) in values {
+        let _ = evaluator.set_variable(name, val);
+    }
+
+    let mut hidden = HashSet::new();
+    collect_hidden(tree, &evaluator, false, &mut hidden);
+
+    values
+        .iter()
+        .filter(|(k, _)| !hidden.contains(k.as_str()))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect()
+}
+
+fn collect_hidden<'a>(
+    node: &'a ConfigNode,
+    evaluator: &evaluator::Evaluator,
+    parent_hidden: bool,
+    hidden: &mut HashSet<&'a str>,
+) {
+    let is_met = |expr: &Option<String>| {
+        expr.as_ref()
+            .map(|e| evaluator.check_dependency(e).unwrap_or(true))
+            .unwrap_or(true)
+    };
+    let node_hidden = parent_hidden || !is_met(&node.depends_on);
+    for item in &node.configs {
+        if node_hidden || !is_met(&item.depends_on) {
+            hidden.insert(item.name.as_str());
+        }
+    }
+    for child in &node.children {
+        collect_hidden(child, evaluator, node_hidden, hidden);
+    }
+}
+
+/// Returns the values that differ from the schema defaults, as saved by
+/// `savedefconfig`. Symbols whose `depends_on` is unmet under `evaluator`
+/// are left out, since their values have no effect.
 pub fn get_minimal_config(
     current_values: &HashMap<String, Value>,
     items: &[ConfigItem],
+    evaluator: &evaluator::Evaluator,
 ) -> HashMap<String, Value> {
     let defaults = evaluator::collect_defaults(items);
     let mut minimal = HashMap::new();
 
     for (name, value) in current_values {
+        let hidden = items.iter().find(|i| i.name == *name).is_some_and(|item| {
+            item.depends_on
+                .as_ref()
+                .is_some_and(|expr| !evaluator.check_dependency(expr).unwrap_or(true))
+        });
+        if hidden {
+            continue;
+        }
         if let Some(default_val) = defaults.get(name) {
             if value != default_val {
                 minimal.insert(name.clone(), value.clone());
@@ -74,35 +870,21 @@ pub fn get_minimal_config(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::schema::{ConfigItem, ConfigType};
+    use crate::schema::{ConfigItem, ConfigNode, ConfigType};
     use toml::Value;
 
     #[test]
     fn test_get_minimal_config() {
         let items = vec![
             ConfigItem {
-                name: "A".to_string(),
-                config_type: ConfigType::Bool,
                 default: Some(Value::Boolean(true)),
                 desc: "A".to_string(),
-                depends_on: None,
-                help: None,
-                options: None,
-                feature: None,
-                range: None,
-                regex: None,
+                ..ConfigItem::test_item("A", ConfigType::Bool)
             },
             ConfigItem {
-                name: "B".to_string(),
-                config_type: ConfigType::Int,
                 default: Some(Value::Integer(10)),
                 desc: "B".to_string(),
-                depends_on: None,
-                help: None,
-                options: None,
-                feature: None,
-                range: None,
-                regex: None,
+                ..ConfigItem::test_item("B", ConfigType::Int)
             },
         ];
 
@@ -110,38 +892,733 @@ mod tests {
         current.insert("A".to_string(), Value::Boolean(false));
         current.insert("B".to_string(), Value::Integer(10));
 
-        let minimal = get_minimal_config(&current, &items);
+        let minimal = get_minimal_config(&current, &items, &evaluator::Evaluator::new());
 
         assert_eq!(minimal.len(), 1);
         assert_eq!(minimal.get("A"), Some(&Value::Boolean(false)));
         assert_eq!(minimal.get("B"), None);
     }
 
+    #[test]
+    fn test_get_minimal_config_skips_hidden_symbols() -> Result<()> {
+        let items = vec![
+            ConfigItem {
+                default: Some(Value::Boolean(false)),
+                ..ConfigItem::test_item("ENABLE_NET", ConfigType::Bool)
+            },
+            ConfigItem {
+                default: Some(Value::Integer(80)),
+                depends_on: Some("ENABLE_NET".to_string()),
+                ..ConfigItem::test_item("NET_PORT", ConfigType::Int)
+            },
+            ConfigItem {
+                default: Some(Value::Integer(3)),
+                ..ConfigItem::test_item("LOG_LEVEL", ConfigType::Int)
+            },
+        ];
+
+        let mut current = evaluator::collect_defaults(&items);
+        current.insert("NET_PORT".to_string(), Value::Integer(8080));
+        current.insert("LOG_LEVEL".to_string(), Value::Integer(5));
+        let mut evaluator = evaluator::Evaluator::new();
+        for (name, val) in &current {
+            evaluator.set_variable(name, val)?;
+        }
+
+        let minimal = get_minimal_config(&current, &items, &evaluator);
+        assert_eq!(minimal.len(), 1);
+        assert_eq!(minimal.get("LOG_LEVEL"), Some(&Value::Integer(5)));
+
+        current.insert("ENABLE_NET".to_string(), Value::Boolean(true));
+        evaluator.set_variable("ENABLE_NET", &Value::Boolean(true))?;
+        let minimal = get_minimal_config(&current, &items, &evaluator);
+        assert_eq!(minimal.len(), 3);
+        assert_eq!(minimal.get("NET_PORT"), Some(&Value::Integer(8080)));
+        Ok(())
+    }
+
     #[test]
     fn test_load_save_config() -> Result<()> {
         let dir = tempfile::tempdir()?;
         let config_path = dir.path().join(".config");
 
         let items = vec![ConfigItem {
-            name: "ENABLE_A".to_string(),
-            config_type: ConfigType::Bool,
             default: Some(Value::Boolean(true)),
             desc: "A".to_string(),
-            depends_on: None,
-            help: None,
-            options: None,
-            feature: None,
-            range: None,
-            regex: None,
+            ..ConfigItem::test_item("ENABLE_A", ConfigType::Bool)
         }];
 
         let mut values = HashMap::new();
         values.insert("ENABLE_A".to_string(), Value::Boolean(false));
 
-        save_config(&config_path, &values)?;
+        save_config(&config_path, &node("", items.clone(), vec![]), &values)?;
         let loaded = load_config(&config_path, &items)?;
 
         assert_eq!(loaded.get("ENABLE_A"), Some(&Value::Boolean(false)));
         Ok(())
     }
+
+    #[test]
+    fn test_save_config_writes_hex_unsigned() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let config_path = dir.path().join(".config");
+        let items = vec![ConfigItem::test_item("BASE", ConfigType::Hex)];
+        let tree = node("", items.clone(), vec![]);
+
+        let mut values = HashMap::new();
+        values.insert("BASE".to_string(), Value::Integer(-1));
+        save_config(&config_path, &tree, &values)?;
+        // Once appended, once updated in place.
+        save_config(&config_path, &tree, &values)?;
+
+        let content = fs::read_to_string(&config_path)?;
+        assert_eq!(content.trim(), "BASE = \"0xffffffffffffffff\"");
+        assert_eq!(
+            load_config(&config_path, &items)?.get("BASE"),
+            Some(&Value::Integer(-1))
+        );
+        Ok(())
+    }
+
+    fn node(desc: &str, configs: Vec<ConfigItem>, children: Vec<ConfigNode>) -> ConfigNode {
+        ConfigNode {
+            desc: desc.to_string(),
+            configs,
+            children,
+            path: String::new(),
+            depends_on: None,
+            help: None,
+            exclusive_group: None,
+            file: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_expand_templates() -> Result<()> {
+        let string_item = |name: &str, default: &str| ConfigItem {
+            default: Some(Value::String(default.to_string())),
+            ..ConfigItem::test_item(name, ConfigType::String)
+        };
+        let items = vec![
+            string_item("VERSION", "1.2"),
+            string_item("ARCH", "x86"),
+            string_item("TAG", "build-${VERSION}-${ARCH}"),
+        ];
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(".config");
+        fs::write(&path, "ARCH = \"arm\"\n")?;
+
+        let values = load_config(&path, &items)?;
+        assert_eq!(
+            values.get("TAG"),
+            Some(&Value::String("build-${VERSION}-${ARCH}".to_string()))
+        );
+        assert_eq!(
+            expand_templates(&values).get("TAG"),
+            Some(&Value::String("build-1.2-arm".to_string()))
+        );
+
+        // Saving keeps the template, so it follows later changes to ARCH.
+        let tree = node("Root", items.clone(), vec![]);
+        let mut values = values;
+        values.insert("ARCH".to_string(), Value::String("riscv".to_string()));
+        save_config(&path, &tree, &values)?;
+        assert!(fs::read_to_string(&path)?.contains("TAG = \"build-${VERSION}-${ARCH}\""));
+        let values = load_config(&path, &items)?;
+        assert_eq!(
+            expand_templates(&values).get("TAG"),
+            Some(&Value::String("build-1.2-riscv".to_string()))
+        );
+
+        // A template left at its default is not part of a defconfig.
+        let minimal = get_minimal_config(&values, &items, &evaluator::Evaluator::new());
+        assert_eq!(minimal.keys().collect::<Vec<_>>(), vec!["ARCH"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_interpolate_reports_undefined_and_cycles() {
+        let mut values = HashMap::new();
+        values.insert("A".to_string(), Value::String("${B}".to_string()));
+        values.insert("B".to_string(), Value::String("x${A}".to_string()));
+        values.insert("C".to_string(), Value::String("${MISSING}".to_string()));
+        values.insert("D".to_string(), Value::String("n=${N}".to_string()));
+        values.insert("N".to_string(), Value::Integer(4));
+
+        let errors: Vec<String> = interpolate(&mut values)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "Cyclic reference in 'A': A -> B -> A",
+                "Cyclic reference in 'B': B -> A -> B",
+                "'C' references undefined symbol 'MISSING'",
+            ]
+        );
+        assert_eq!(values.get("A"), Some(&Value::String("${B}".to_string())));
+        assert_eq!(values.get("D"), Some(&Value::String("n=4".to_string())));
+    }
+
+    #[test]
+    fn test_save_config_is_ordered_and_deterministic() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let config_path = dir.path().join(".config");
+
+        let tree = node(
+            "Root",
+            vec![
+                ConfigItem::test_item("Z_ROOT", ConfigType::Int),
+                ConfigItem::test_item("A_ROOT", ConfigType::Int),
+            ],
+            vec![node(
+                "Networking",
+                vec![
+                    ConfigItem::test_item("NET_B", ConfigType::Int),
+                    ConfigItem::test_item("NET_A", ConfigType::Int),
+                ],
+                vec![],
+            )],
+        );
+        let values: HashMap<String, Value> = ["A_ROOT", "Z_ROOT", "NET_A", "NET_B", "STALE"]
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (k.to_string(), Value::Integer(i as i64)))
+            .collect();
+
+        save_config(&config_path, &tree, &values)?;
+        let first = fs::read_to_string(&config_path)?;
+        assert_eq!(
+            first,
+            "# Root\nZ_ROOT = 1\nA_ROOT = 0\n\n# Networking\nNET_B = 3\nNET_A = 2\n\nSTALE = 4\n"
+        );
+
+        // A fresh map has a different iteration order.
+        let values: HashMap<String, Value> = values.into_iter().collect();
+        save_config(&config_path, &tree, &values)?;
+        assert_eq!(fs::read_to_string(&config_path)?, first);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_resolved_snapshot() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("snapshots/resolved.toml");
+
+        let items = vec![
+            ConfigItem {
+                default: Some(Value::Integer(80)),
+                depends_on: Some("ENABLE_NET".to_string()),
+                ..ConfigItem::test_item("NET_PORT", ConfigType::Int)
+            },
+            ConfigItem {
+                default: Some(Value::Boolean(false)),
+                ..ConfigItem::test_item("ENABLE_NET", ConfigType::Bool)
+            },
+            ConfigItem {
+                default: Some(Value::Integer(3)),
+                ..ConfigItem::test_item("LOG_LEVEL", ConfigType::Int)
+            },
+            ConfigItem::test_item("BAUD", ConfigType::Int),
+        ];
+
+        let mut values = evaluator::collect_defaults(&items);
+        values.insert("LOG_LEVEL".to_string(), Value::Integer(5));
+        let mut evaluator = evaluator::Evaluator::new();
+        for (name, val) in &values {
+            evaluator.set_variable(name, val)?;
+        }
+
+        write_resolved_snapshot(&path, &items, &values, &evaluator)?;
+        let snapshot: Table = toml::from_str(&fs::read_to_string(&path)?)?;
+        let resolved = snapshot["values"].as_table().unwrap();
+        for item in &items {
+            let listed = resolved.contains_key(&item.name)
+                || snapshot["unset"]
+                    .as_array()
+                    .unwrap()
+                    .contains(&Value::String(item.name.clone()));
+            assert!(listed, "{} missing from snapshot", item.name);
+        }
+        assert_eq!(resolved["LOG_LEVEL"], Value::Integer(5));
+        assert_eq!(resolved["NET_PORT"], Value::Integer(80));
+        assert_eq!(snapshot["unset"], Value::Array(vec!["BAUD".into()]));
+        assert_eq!(snapshot["inactive"], Value::Array(vec!["NET_PORT".into()]));
+        assert_eq!(snapshot["schema_hash"], Value::String(schema_hash(&items)?));
+        assert_ne!(schema_hash(&items)?, schema_hash(&items[1..])?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stable_hash() {
+        // FNV-1a test vectors, so the hash can't drift between releases.
+        assert_eq!(stable_hash(b""), "cbf29ce484222325");
+        assert_eq!(stable_hash(b"a"), "af63dc4c8601ec8c");
+        assert_eq!(stable_hash(b"foobar"), "85944171f73967e8");
+    }
+
+    #[test]
+    fn test_save_config_creates_parent_dirs() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let config_path = dir.path().join("boards/devkit/.config");
+
+        let tree = node(
+            "Root",
+            vec![ConfigItem::test_item("A_VAL", ConfigType::Int)],
+            vec![],
+        );
+        let mut values = HashMap::new();
+        values.insert("A_VAL".to_string(), Value::Integer(1));
+
+        save_config(&config_path, &tree, &values)?;
+        assert_eq!(fs::read_to_string(&config_path)?, "# Root\nA_VAL = 1\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_config_preserves_comments() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let config_path = dir.path().join(".config");
+        fs::write(
+            &config_path,
+            "# board: devkit\nB_VAL = 1 # tuned by hand\n\nA_VAL = 2\nGONE = 3\n",
+        )?;
+
+        let tree = node(
+            "Root",
+            vec![
+                ConfigItem::test_item("A_VAL", ConfigType::Int),
+                ConfigItem::test_item("B_VAL", ConfigType::Int),
+                ConfigItem::test_item("C_VAL", ConfigType::Int),
+            ],
+            vec![],
+        );
+        let mut values = load_config(&config_path, &crate::parser::flatten_configs(&tree))?;
+        values.insert("B_VAL".to_string(), Value::Integer(10));
+        values.insert("C_VAL".to_string(), Value::Integer(4));
+
+        save_config(&config_path, &tree, &values)?;
+        assert_eq!(
+            fs::read_to_string(&config_path)?,
+            "# board: devkit\nB_VAL = 10 # tuned by hand\n\nA_VAL = 2\n\n# Root\nC_VAL = 4\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_configs() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let base = dir.path().join("base.config");
+        let board = dir.path().join("board.config");
+        let feature = dir.path().join("feature.config");
+        fs::write(&base, "A = 1\nB = 1\nC = 1\n")?;
+        fs::write(&board, "B = 2\nC = 2\n")?;
+        fs::write(&feature, "B = 3\nC = 2\nD = true\n")?;
+
+        let (merged, conflicts) = merge_configs(&base, &[board.clone(), feature.clone()])?;
+        assert_eq!(merged.get("A"), Some(&Value::Integer(1)));
+        assert_eq!(merged.get("B"), Some(&Value::Integer(3)));
+        assert_eq!(merged.get("C"), Some(&Value::Integer(2)));
+        assert_eq!(merged.get("D"), Some(&Value::Boolean(true)));
+
+        assert_eq!(
+            conflicts,
+            vec![MergeConflict {
+                key: "B".to_string(),
+                previous: (board, Value::Integer(2)),
+                overridden_by: (feature, Value::Integer(3)),
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_table_normalizes_and_maps_aliases() -> Result<()> {
+        let items = vec![
+            ConfigItem::test_item("BASE", ConfigType::Hex),
+            ConfigItem {
+                alias: Some(vec!["UART_BAUD".to_string()]),
+                ..ConfigItem::test_item("BAUD", ConfigType::Int)
+            },
+            ConfigItem::test_item("PORT", ConfigType::Int),
+        ];
+
+        let table: Table = toml::from_str("BASE = \"0x8000\"\nUART_BAUD = 9600\nPORT = true\n")?;
+        let origin = Path::new("merged.config");
+        let values = load_table(origin, &table, &items, false)?;
+        assert_eq!(values.get("BASE"), Some(&Value::Integer(0x8000)));
+        assert_eq!(values.get("BAUD"), Some(&Value::Integer(9600)));
+        assert!(!values.contains_key("UART_BAUD"));
+        assert!(!values.contains_key("PORT"));
+
+        assert!(load_table(origin, &table, &items, true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_hidden() -> Result<()> {
+        let mut menu = node(
+            "Wifi",
+            vec![ConfigItem::test_item("WIFI_CHANNEL", ConfigType::Int)],
+            vec![],
+        );
+        menu.depends_on = Some("ENABLE_NET".to_string());
+        let tree = node(
+            "Root",
+            vec![
+                ConfigItem::test_item("ENABLE_NET", ConfigType::Bool),
+                ConfigItem {
+                    depends_on: Some("ENABLE_NET".to_string()),
+                    ..ConfigItem::test_item("MAX_SOCKETS", ConfigType::Int)
+                },
+                ConfigItem::test_item("OTHER", ConfigType::Int),
+            ],
+            vec![menu],
+        );
+
+        let mut values = HashMap::new();
+        values.insert("ENABLE_NET".to_string(), Value::Boolean(false));
+        values.insert("MAX_SOCKETS".to_string(), Value::Integer(16));
+        values.insert("WIFI_CHANNEL".to_string(), Value::Integer(6));
+        values.insert("OTHER".to_string(), Value::Integer(1));
+
+        let pruned = prune_hidden(&tree, &values);
+        let mut keys: Vec<_> = pruned.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["ENABLE_NET", "OTHER"]);
+
+        let dir = tempfile::tempdir()?;
+        let config_path = dir.path().join(".config");
+        save_config_with(&config_path, &tree, &values, true)?;
+        let saved = fs::read_to_string(&config_path)?;
+        assert!(!saved.contains("MAX_SOCKETS"), "{}", saved);
+        save_config_with(&config_path, &tree, &values, false)?;
+        assert!(fs::read_to_string(&config_path)?.contains("MAX_SOCKETS = 16"));
+
+        values.insert("ENABLE_NET".to_string(), Value::Boolean(true));
+        assert_eq!(prune_hidden(&tree, &values).len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_config() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let config_path = dir.path().join(".config");
+
+        let items = vec![
+            ConfigItem {
+                default: Some(Value::Boolean(false)),
+                ..ConfigItem::test_item("ENABLE_NET", ConfigType::Bool)
+            },
+            ConfigItem {
+                default: Some(Value::Boolean(false)),
+                depends_on: Some("ENABLE_NET".to_string()),
+                ..ConfigItem::test_item("ENABLE_IPV6", ConfigType::Bool)
+            },
+            ConfigItem {
+                range: Some((1, 65535)),
+                ..ConfigItem::test_item("PORT", ConfigType::Int)
+            },
+            ConfigItem::test_item("BAUD", ConfigType::Int),
+        ];
+
+        fs::write(&config_path, "BAUD = 9600\nPORT = 80\n")?;
+        assert!(verify_config(&config_path, &items)?.is_empty());
+
+        fs::write(&config_path, "ENABLE_IPV6 = true\nPORT = 0\nOLD_NAME = 1\n")?;
+        let violations = verify_config(&config_path, &items)?;
+        assert_eq!(violations.len(), 4);
+        assert!(matches!(&violations[0], Violation::Unknown { key } if key == "OLD_NAME"));
+        assert!(matches!(&violations[1], Violation::InvalidValue { key, .. } if key == "PORT"));
+        assert_eq!(
+            violations[2],
+            Violation::UnmetDependency {
+                key: "ENABLE_IPV6".to_string(),
+                depends_on: "ENABLE_NET".to_string(),
+            }
+        );
+        assert_eq!(
+            violations[3],
+            Violation::Missing {
+                key: "BAUD".to_string()
+            }
+        );
+
+        assert!(verify_config(&dir.path().join("missing"), &items).is_err());
+
+        // Former names are accepted like in `load_config`.
+        let mut items = items;
+        items[3].alias = Some(vec!["UART_BAUD".to_string()]);
+        fs::write(&config_path, "UART_BAUD = 9600\nPORT = 80\n")?;
+        assert!(verify_config(&config_path, &items)?.is_empty());
+        fs::write(&config_path, "UART_BAUD = true\nPORT = 80\n")?;
+        let violations = verify_config(&config_path, &items)?;
+        assert!(
+            matches!(&violations[..], [Violation::InvalidValue { key, .. }] if key == "UART_BAUD")
+        );
+
+        // Conditional and environment defaults count as a value.
+        items[3].default_if = Some(vec![crate::schema::ConditionalDefault {
+            value: Value::Integer(115200),
+            depends_on: "ENABLE_NET".to_string(),
+        }]);
+        items.push(ConfigItem {
+            default_env: Some("ANAXA_TEST_VERIFY_TIMEOUT".to_string()),
+            ..ConfigItem::test_item("TIMEOUT", ConfigType::Int)
+        });
+        std::env::set_var("ANAXA_TEST_VERIFY_TIMEOUT", "30");
+        fs::write(&config_path, "ENABLE_NET = true\nPORT = 80\n")?;
+        assert!(verify_config(&config_path, &items)?.is_empty());
+        fs::write(&config_path, "PORT = 80\n")?;
+        assert_eq!(
+            verify_config(&config_path, &items)?,
+            vec![Violation::Missing {
+                key: "BAUD".to_string()
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_config_reports_deprecated() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let config_path = dir.path().join(".config");
+        fs::write(&config_path, "OLD_BAUD = 9600\nBAUD = 9600\n")?;
+
+        let items = vec![
+            ConfigItem {
+                deprecated: Some("BAUD".to_string()),
+                ..ConfigItem::test_item("OLD_BAUD", ConfigType::Int)
+            },
+            ConfigItem {
+                deprecated: Some(String::new()),
+                ..ConfigItem::test_item("OLD_UNUSED", ConfigType::Int)
+            },
+            ConfigItem::test_item("BAUD", ConfigType::Int),
+        ];
+
+        let loaded = load_config_impl(&config_path, &items, false)?;
+        assert_eq!(loaded.values.get("OLD_BAUD"), Some(&Value::Integer(9600)));
+        assert_eq!(
+            loaded.deprecated,
+            vec!["CONFIG_OLD_BAUD is deprecated; use CONFIG_BAUD".to_string()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hex_strings_load_as_integers() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "BASE"
+type = "hex"
+default = "0x1000"
+desc = "Base"
+
+[[config]]
+name = "MASK"
+type = "hex"
+default = 0
+desc = "Mask"
+"#,
+        )?;
+        let tree = crate::parser::build_config_tree(dir.path())?;
+        let items = crate::parser::flatten_configs(&tree);
+        assert_eq!(items[0].default, Some(Value::Integer(4096)));
+
+        let config_path = dir.path().join(".config");
+        fs::write(&config_path, "MASK = \"0xFF\"\n")?;
+        let values = load_config(&config_path, &items)?;
+        assert_eq!(values.get("BASE"), Some(&Value::Integer(4096)));
+        assert_eq!(values.get("MASK"), Some(&Value::Integer(0xFF)));
+        assert!(verify_config(&config_path, &items)?.is_empty());
+
+        save_config(&config_path, &tree, &values)?;
+        let reloaded = load_config(&config_path, &items)?;
+        assert_eq!(reloaded, values);
+
+        fs::write(&config_path, "MASK = -1\n")?;
+        assert!(load_config_strict(&config_path, &items).is_err());
+        assert!(matches!(
+            &verify_config(&config_path, &items)?[..],
+            [Violation::InvalidValue { key, .. }] if key == "MASK"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_exclusive_groups() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+exclusive_group = ["UART_A", "UART_B"]
+
+[[config]]
+name = "UART_A"
+type = "bool"
+default = true
+desc = "UART A"
+
+[[config]]
+name = "UART_B"
+type = "bool"
+default = false
+desc = "UART B"
+"#,
+        )?;
+        let items = crate::parser::flatten_configs(&crate::parser::build_config_tree(dir.path())?);
+
+        let config_path = dir.path().join(".config");
+        fs::write(&config_path, "UART_A = false\nUART_B = true\n")?;
+        let values = load_config(&config_path, &items)?;
+        assert!(check_exclusive_groups(&items, &values).is_ok());
+        assert!(verify_config(&config_path, &items)?.is_empty());
+
+        fs::write(&config_path, "UART_B = true\n")?;
+        let err = load_config(&config_path, &items).unwrap_err();
+        assert!(err.to_string().contains("UART_A, UART_B are"), "{}", err);
+        assert_eq!(
+            verify_config(&config_path, &items)?,
+            vec![Violation::ExclusiveGroup {
+                group: vec!["UART_A".to_string(), "UART_B".to_string()],
+                enabled: vec!["UART_A".to_string(), "UART_B".to_string()],
+            }]
+        );
+
+        // Left to the caller, e.g. to apply edits that resolve the conflict.
+        let mut values = load_config_unchecked(&config_path, &items, false)?;
+        assert!(check_exclusive_groups(&items, &values).is_err());
+        values.insert("UART_A".to_string(), Value::Boolean(false));
+        assert!(check_exclusive_groups(&items, &values).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_config_resolves_conditional_defaults() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "ARCH_ARM"
+type = "bool"
+default = false
+desc = "ARM"
+
+[[config]]
+name = "LOAD_ADDR"
+type = "hex"
+default = 0x1000
+default_if = [{ value = "0x8000", depends_on = "ARCH_ARM" }]
+desc = "Load address"
+"#,
+        )?;
+        let items = crate::parser::flatten_configs(&crate::parser::build_config_tree(dir.path())?);
+        let config_path = dir.path().join(".config");
+
+        fs::write(&config_path, "ARCH_ARM = true\n")?;
+        let values = load_config(&config_path, &items)?;
+        assert_eq!(values.get("LOAD_ADDR"), Some(&Value::Integer(0x8000)));
+
+        fs::write(&config_path, "ARCH_ARM = true\nLOAD_ADDR = 0x2000\n")?;
+        let values = load_config(&config_path, &items)?;
+        assert_eq!(values.get("LOAD_ADDR"), Some(&Value::Integer(0x2000)));
+
+        fs::write(&config_path, "ARCH_ARM = false\n")?;
+        let values = load_config(&config_path, &items)?;
+        assert_eq!(values.get("LOAD_ADDR"), Some(&Value::Integer(0x1000)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_config_applies_aliases() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let config_path = dir.path().join(".config");
+        fs::write(&config_path, "UART_BAUD = 115200\nOLD_PORT = 1\nPORT = 2\n")?;
+
+        let tree = node(
+            "Root",
+            vec![
+                ConfigItem {
+                    alias: Some(vec!["UART_BAUD".to_string()]),
+                    ..ConfigItem::test_item("BAUD_RATE", ConfigType::Int)
+                },
+                ConfigItem {
+                    alias: Some(vec!["OLD_PORT".to_string()]),
+                    ..ConfigItem::test_item("PORT", ConfigType::Int)
+                },
+            ],
+            vec![],
+        );
+        let items = crate::parser::flatten_configs(&tree);
+
+        let (values, unknown) = load_config_reporting(&config_path, &items)?;
+        assert_eq!(values.get("BAUD_RATE"), Some(&Value::Integer(115200)));
+        assert_eq!(values.get("PORT"), Some(&Value::Integer(2)));
+        assert!(!values.contains_key("UART_BAUD"));
+        assert!(unknown.is_empty());
+
+        save_config(&config_path, &tree, &values)?;
+        let saved = fs::read_to_string(&config_path)?;
+        assert!(saved.contains("BAUD_RATE = 115200"));
+        assert!(!saved.contains("UART_BAUD"));
+        assert!(!saved.contains("OLD_PORT"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_config_strict() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let config_path = dir.path().join(".config");
+        fs::write(&config_path, "PORT = 70000\n")?;
+
+        let items = vec![ConfigItem {
+            default: Some(Value::Integer(80)),
+            desc: "Port".to_string(),
+            range: Some((1, 65535)),
+            ..ConfigItem::test_item("PORT", ConfigType::Int)
+        }];
+
+        let lenient = load_config(&config_path, &items)?;
+        assert_eq!(lenient.get("PORT"), Some(&Value::Integer(80)));
+
+        let err = load_config_strict(&config_path, &items).unwrap_err();
+        assert!(err.to_string().contains("PORT"));
+        assert!(err.to_string().contains(".config"));
+        assert!(
+            matches!(&err, AnaxaError::Validation { symbol, .. } if symbol == "PORT"),
+            "{:?}",
+            err
+        );
+
+        fs::write(&config_path, "PORT = [\n")?;
+        let err = load_config(&config_path, &items).unwrap_err();
+        assert!(matches!(err, AnaxaError::Parse { .. }), "{:?}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_config_reports_unknown_keys() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let config_path = dir.path().join(".config");
+        fs::write(&config_path, "ENABLE_A = false\nOLD_NAME = 1\n")?;
+
+        let items = vec![ConfigItem {
+            default: Some(Value::Boolean(true)),
+            desc: "A".to_string(),
+            ..ConfigItem::test_item("ENABLE_A", ConfigType::Bool)
+        }];
+
+        let (values, unknown) = load_config_reporting(&config_path, &items)?;
+        assert_eq!(values.get("ENABLE_A"), Some(&Value::Boolean(false)));
+        assert!(!values.contains_key("OLD_NAME"));
+        assert_eq!(unknown, vec!["OLD_NAME".to_string()]);
+        Ok(())
+    }
 }
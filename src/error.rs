@@ -0,0 +1,88 @@
+//! The error type returned at the library boundary.
+//!
+//! Entry points such as [`crate::parser::build_config_tree`],
+//! [`crate::config_io::load_config`] and [`crate::graph::ConfigGraph::build`]
+//! return [`AnaxaError`] so callers can match on the kind of failure. It
+//! converts into `anyhow::Error` like any other error.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AnaxaError {
+    /// A schema or config file that can't be deserialized, or a schema tree
+    /// that is laid out wrongly.
+    #[error("Failed to parse {path:?}: {message}")]
+    Parse { path: PathBuf, message: String },
+    /// A symbol whose name, default or value is invalid. `reason` names the
+    /// symbol and, where known, the file.
+    #[error("{reason}")]
+    Validation { symbol: String, reason: String },
+    /// A cycle among symbol dependencies or `source` includes. The path
+    /// starts and ends at the same symbol or file.
+    #[error("{}", describe_cycle(*kind, path))]
+    Cycle { kind: CycleKind, path: Vec<String> },
+    /// A file that couldn't be read or written.
+    #[error("Failed to access {path:?}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// What an [`AnaxaError::Cycle`] runs through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CycleKind {
+    /// Symbols whose `depends_on` or `default_if` conditions reference each
+    /// other.
+    Dependency,
+    /// Kconfig files that `source` each other.
+    Include,
+}
+
+impl AnaxaError {
+    pub(crate) fn io(path: &Path, source: std::io::Error) -> Self {
+        AnaxaError::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub(crate) fn parse(path: &Path, message: impl fmt::Display) -> Self {
+        AnaxaError::Parse {
+            path: path.to_path_buf(),
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn validation(symbol: &str, reason: impl Into<String>) -> Self {
+        AnaxaError::Validation {
+            symbol: symbol.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Prefixes the reason of a validation error with what was invalid and
+    /// the file it was found in.
+    pub(crate) fn in_file(self, what: &str, path: &Path) -> Self {
+        match self {
+            AnaxaError::Validation { symbol, reason } => AnaxaError::Validation {
+                symbol,
+                reason: format!("{} in {:?}: {}", what, path, reason),
+            },
+            other => other,
+        }
+    }
+}
+
+fn describe_cycle(kind: CycleKind, path: &[String]) -> String {
+    match (kind, path) {
+        (CycleKind::Include, _) => format!("Include cycle detected: {}", path.join(" -> ")),
+        (CycleKind::Dependency, [node, _]) => format!("Self-dependency cycle detected: {}", node),
+        (CycleKind::Dependency, _) => format!(
+            "Cycle detected in configuration dependencies: {}",
+            path.join(" -> ")
+        ),
+    }
+}
@@ -1,28 +1,54 @@
 use crate::schema::ConfigItem;
 use anyhow::{Context, Result};
-use evalexpr::{ContextWithMutableVariables, HashMapContext, Value};
-use std::collections::HashMap;
+use evalexpr::{Context as _, ContextWithMutableVariables, HashMapContext, Node, Value};
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
 
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct Evaluator {
     context: HashMapContext,
+    /// Parsed operator trees, keyed by expression. These don't depend on
+    /// variable values and live as long as the evaluator.
+    nodes: Mutex<HashMap<String, Node>>,
+    /// Evaluation results, keyed by expression. Cleared by `set_variable`
+    /// and `set_explicit`, the only ways to change what an expression
+    /// evaluates to.
+    results: Mutex<HashMap<String, bool>>,
+    /// Symbols `defined(NAME)` is true for, see [`Evaluator::set_explicit`].
+    explicit: HashSet<String>,
 }
 
 impl Evaluator {
     pub fn new() -> Self {
         Self {
             context: HashMapContext::new(),
+            nodes: Mutex::new(HashMap::new()),
+            results: Mutex::new(HashMap::new()),
+            explicit: HashSet::new(),
         }
     }
 
+    /// Sets the symbols that are explicitly set to a non-default value, as
+    /// returned by [`defined_symbols`]. `defined(NAME)` is true only for
+    /// these.
+    pub fn set_explicit(&mut self, names: HashSet<String>) {
+        self.explicit = names;
+        self.results.get_mut().unwrap().clear();
+    }
+
     pub fn set_variable(&mut self, name: &str, value: &toml::Value) -> Result<()> {
         let val = match value {
             toml::Value::Boolean(b) => Value::Boolean(*b),
             toml::Value::Integer(i) => Value::Int(*i),
+            toml::Value::Float(f) => Value::Float(*f),
             toml::Value::String(s) => Value::String(s.clone()),
             _ => return Ok(()),
         };
         self.context.set_value(name.to_string(), val)?;
+        self.results.get_mut().unwrap().clear();
         Ok(())
     }
 
@@ -31,13 +57,197 @@ impl Evaluator {
             return Ok(true);
         }
 
-        let val = evalexpr::eval_with_context(expr, &self.context)
+        if let Some(&cached) = self.results.lock().unwrap().get(expr) {
+            return Ok(cached);
+        }
+
+        let desugared = desugar_choice_equality(expr);
+        let resolved = self.resolve_defined(&desugared);
+        let mut nodes = self.nodes.lock().unwrap();
+        let node = match nodes.get(resolved.as_ref()) {
+            Some(node) => node,
+            None => {
+                let node = evalexpr::build_operator_tree(&resolved)
+                    .with_context(|| format!("Failed to parse expression: {}", expr))?;
+                nodes.entry(resolved.into_owned()).or_insert(node)
+            }
+        };
+        let val = node
+            .eval_with_context(&self.context)
             .with_context(|| format!("Failed to evaluate expression: {}", expr))?;
 
-        match val {
-            Value::Boolean(b) => Ok(b),
-            Value::Int(i) => Ok(i != 0),
-            _ => Ok(false),
+        let result = match val {
+            Value::Boolean(b) => b,
+            Value::Int(i) => i != 0,
+            _ => false,
+        };
+        self.results
+            .lock()
+            .unwrap()
+            .insert(expr.to_string(), result);
+        Ok(result)
+    }
+
+    /// Explains why `expr` is false: breaks it into its top-level `&&`
+    /// operands, descending into parenthesized conjunctions, and returns the
+    /// false ones. An expression that isn't a conjunction is its own single
+    /// operand, and one that fails to evaluate counts as false.
+    pub fn unmet_conditions(&self, expr: &str) -> Vec<UnmetCondition> {
+        let mut unmet = Vec::new();
+        self.collect_unmet(expr, &mut unmet);
+        unmet
+    }
+
+    fn collect_unmet(&self, expr: &str, unmet: &mut Vec<UnmetCondition>) {
+        if self.check_dependency(expr).unwrap_or(false) {
+            return;
+        }
+        let expr = strip_parens(expr.trim());
+        let operands = split_conjunction(expr);
+        if operands.len() > 1 {
+            for operand in operands {
+                self.collect_unmet(operand, unmet);
+            }
+            return;
+        }
+        let symbols = crate::graph::extract_variables(expr)
+            .into_iter()
+            .map(|name| {
+                let value = self.context.get_value(&name).map(|v| v.to_string());
+                (name, value)
+            })
+            .collect();
+        unmet.push(UnmetCondition {
+            expr: expr.to_string(),
+            symbols,
+        });
+    }
+
+    /// Replaces each `defined(NAME)` with `true` or `false` depending on
+    /// whether `NAME` is explicitly set to a non-default value. Unlike
+    /// evalexpr's builtins such as `min`/`max`, its argument is a symbol name
+    /// rather than a value, so it can't be evaluated as a regular function
+    /// call.
+    fn resolve_defined<'a>(&self, expr: &'a str) -> Cow<'a, str> {
+        static DEFINED: OnceLock<Regex> = OnceLock::new();
+        let re = DEFINED
+            .get_or_init(|| Regex::new(r"\bdefined\s*\(\s*([A-Za-z_][A-Za-z0-9_]*)\s*\)").unwrap());
+        re.replace_all(expr, |caps: &Captures| {
+            self.explicit.contains(&caps[1]).to_string()
+        })
+    }
+}
+
+/// A false part of a dependency expression, see
+/// [`Evaluator::unmet_conditions`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnmetCondition {
+    /// The sub-expression, e.g. `ENABLE_NET` or `MAX > 4`.
+    pub expr: String,
+    /// The symbols it references with their current values, `None` if unset.
+    pub symbols: Vec<(String, Option<String>)>,
+}
+
+impl fmt::Display for UnmetCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |(name, value): &(String, Option<String>)| match value {
+            Some(value) => format!("{}={}", name, value),
+            None => format!("{} is unset", name),
+        };
+        match self.symbols.as_slice() {
+            [symbol] if symbol.0 == self.expr => write!(f, "{}", describe(symbol)),
+            [] => write!(f, "{}", self.expr),
+            symbols => {
+                let described: Vec<String> = symbols.iter().map(describe).collect();
+                write!(f, "{} ({})", self.expr, described.join(", "))
+            }
+        }
+    }
+}
+
+/// Splits `expr` at the `&&` operators outside parentheses and strings.
+fn split_conjunction(expr: &str) -> Vec<&str> {
+    let bytes = expr.as_bytes();
+    let mut operands = Vec::new();
+    let (mut depth, mut in_string, mut escaped) = (0usize, false, false);
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == b'\\' {
+                escaped = true;
+            } else if c == b'"' {
+                in_string = false;
+            }
+        } else {
+            match c {
+                b'"' => in_string = true,
+                b'(' => depth += 1,
+                b')' => depth = depth.saturating_sub(1),
+                b'&' if depth == 0 && bytes.get(i + 1) == Some(&b'&') => {
+                    operands.push(expr[start..i].trim());
+                    start = i + 2;
+                    i += 1;
+                }
+                _ => {}
+            }
+        }
+        i += 1;
+    }
+    operands.push(expr[start..].trim());
+    operands
+}
+
+/// Removes parentheses enclosing the whole of `expr`, e.g. `((A && B))`.
+fn strip_parens(mut expr: &str) -> &str {
+    while let Some(inner) = expr.strip_prefix('(').and_then(|e| e.strip_suffix(')')) {
+        // `(A) && (B)` starts and ends with parentheses that don't match.
+        let mut depth = 0usize;
+        let balanced = inner.chars().all(|c| {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => return false,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            true
+        });
+        if !balanced {
+            break;
+        }
+        expr = inner.trim();
+    }
+    expr
+}
+
+/// Rewrites the `NAME:OPTION` shorthand into `(NAME == "OPTION")`, so a
+/// choice can be compared without quoting the option. Quoted strings are
+/// left alone.
+pub fn desugar_choice_equality(expr: &str) -> Cow<'_, str> {
+    static SHORTHAND: OnceLock<Regex> = OnceLock::new();
+    if !expr.contains(':') {
+        return Cow::Borrowed(expr);
+    }
+    let re = SHORTHAND.get_or_init(|| {
+        Regex::new(r#""(?:[^"\\]|\\.)*"|\b([A-Za-z_][A-Za-z0-9_]*)\s*:\s*([A-Za-z0-9_.\-]+)"#)
+            .unwrap()
+    });
+    re.replace_all(expr, |caps: &Captures| match (caps.get(1), caps.get(2)) {
+        (Some(name), Some(option)) => format!("({} == \"{}\")", name.as_str(), option.as_str()),
+        _ => caps[0].to_string(),
+    })
+}
+
+impl Clone for Evaluator {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+            nodes: Mutex::new(self.nodes.lock().unwrap().clone()),
+            results: Mutex::new(self.results.lock().unwrap().clone()),
+            explicit: self.explicit.clone(),
         }
     }
 }
@@ -48,16 +258,97 @@ impl Default for Evaluator {
     }
 }
 
+/// Collects each config's default value, see
+/// [`ConfigItem::effective_default`] for the precedence of the sources.
+/// `default_if` conditions see the defaults of the symbols they reference.
 pub fn collect_defaults(items: &[ConfigItem]) -> HashMap<String, toml::Value> {
     let mut map = HashMap::new();
-    for item in items {
-        if let Some(ref val) = item.default {
-            map.insert(item.name.clone(), val.clone());
+    let mut evaluator = Evaluator::new();
+    for item in resolution_order(items) {
+        if let Some(val) = item.effective_default(&evaluator) {
+            let _ = evaluator.set_variable(&item.name, &val);
+            map.insert(item.name.clone(), val);
         }
     }
     map
 }
 
+/// `items` in dependency order, so a `default_if` condition is resolved
+/// after the symbols it references. Falls back to declaration order when
+/// the dependencies form a cycle, which [`ConfigGraph::build`] reports.
+///
+/// [`ConfigGraph::build`]: crate::graph::ConfigGraph::build
+fn resolution_order(items: &[ConfigItem]) -> Vec<&ConfigItem> {
+    let by_name: HashMap<&str, &ConfigItem> = items.iter().map(|i| (i.name.as_str(), i)).collect();
+    match crate::graph::ConfigGraph::build(items).and_then(|graph| graph.topo_order()) {
+        Ok(order) => order.into_iter().map(|name| by_name[name]).collect(),
+        Err(_) => items.iter().collect(),
+    }
+}
+
+/// The symbols in `explicit` whose value in `values` differs from their
+/// effective default under `values`, i.e. those `defined(NAME)` is true for.
+pub fn defined_symbols(
+    items: &[ConfigItem],
+    values: &HashMap<String, toml::Value>,
+    explicit: &HashSet<String>,
+) -> HashSet<String> {
+    let mut evaluator = Evaluator::new();
+    for (name, val) in values {
+        let _ = evaluator.set_variable(name, val);
+    }
+    items
+        .iter()
+        .filter(|i| explicit.contains(&i.name))
+        .filter(|i| {
+            values
+                .get(&i.name)
+                .is_some_and(|v| i.effective_default(&evaluator).as_ref() != Some(v))
+        })
+        .map(|i| i.name.clone())
+        .collect()
+}
+
+/// Re-resolves the defaults of symbols with `default_if` against `values`,
+/// skipping the `fixed` ones (set explicitly or from the environment).
+/// Symbols are resolved in dependency order, so a condition sees the
+/// re-resolved defaults of the symbols it references.
+pub fn apply_conditional_defaults(
+    items: &[ConfigItem],
+    values: &mut HashMap<String, toml::Value>,
+    fixed: &HashSet<String>,
+) {
+    if !items
+        .iter()
+        .any(|i| i.default_if.is_some() && !fixed.contains(&i.name))
+    {
+        return;
+    }
+
+    let mut evaluator = Evaluator::new();
+    for (name, val) in values.iter() {
+        let _ = evaluator.set_variable(name, val);
+    }
+    evaluator.set_explicit(defined_symbols(items, values, fixed));
+    for item in resolution_order(items) {
+        if item.default_if.is_none() || fixed.contains(&item.name) {
+            continue;
+        }
+        match item
+            .conditional_default(&evaluator)
+            .or_else(|| item.default.clone())
+        {
+            Some(val) => {
+                let _ = evaluator.set_variable(&item.name, &val);
+                values.insert(item.name.clone(), val);
+            }
+            None => {
+                values.remove(&item.name);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -99,6 +390,303 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_unmet_conditions() -> Result<()> {
+        let mut evaluator = Evaluator::new();
+        evaluator.set_variable("A", &TomlValue::Boolean(true))?;
+        evaluator.set_variable("B", &TomlValue::Boolean(false))?;
+        evaluator.set_variable("MAX", &TomlValue::Integer(2))?;
+
+        let unmet = evaluator.unmet_conditions("A && B");
+        assert_eq!(
+            unmet,
+            vec![UnmetCondition {
+                expr: "B".to_string(),
+                symbols: vec![("B".to_string(), Some("false".to_string()))],
+            }]
+        );
+        assert_eq!(unmet[0].to_string(), "B=false");
+
+        let unmet = evaluator.unmet_conditions("(A && (MAX > 4)) && (B || !A) && UNSET");
+        let described: Vec<String> = unmet.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            described,
+            vec![
+                "MAX > 4 (MAX=2)",
+                "B || !A (B=false, A=true)",
+                "UNSET is unset"
+            ]
+        );
+
+        assert!(evaluator.unmet_conditions("A && !B").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_choice_equality_shorthand() -> Result<()> {
+        assert_eq!(desugar_choice_equality("MODE:PROD"), "(MODE == \"PROD\")");
+        assert_eq!(
+            desugar_choice_equality("SCHED : round-robin && NAME == \"A:B\""),
+            "(SCHED == \"round-robin\") && NAME == \"A:B\""
+        );
+
+        let mut evaluator = Evaluator::new();
+        for mode in ["PROD", "DEV"] {
+            evaluator.set_variable("MODE", &TomlValue::String(mode.to_string()))?;
+            evaluator.set_variable("DEBUG", &TomlValue::Boolean(mode == "DEV"))?;
+            for (sugar, quoted) in [
+                ("MODE:PROD", "MODE == \"PROD\""),
+                ("MODE:DEV && DEBUG", "MODE == \"DEV\" && DEBUG"),
+                ("!MODE:PROD", "!(MODE == \"PROD\")"),
+            ] {
+                assert_eq!(
+                    evaluator.check_dependency(sugar)?,
+                    evaluator.check_dependency(quoted)?,
+                    "{} with MODE={}",
+                    sugar,
+                    mode
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluator_cache_matches_uncached() -> Result<()> {
+        let exprs = ["A && !B", "MAX > 5", "MODE == \"PROD\" || B"];
+        let mut evaluator = Evaluator::new();
+
+        for (a, b, max, mode) in [
+            (true, false, 10, "PROD"),
+            (false, true, 3, "DEV"),
+            (true, true, 6, "DEV"),
+        ] {
+            evaluator.set_variable("A", &TomlValue::Boolean(a))?;
+            evaluator.set_variable("B", &TomlValue::Boolean(b))?;
+            evaluator.set_variable("MAX", &TomlValue::Integer(max))?;
+            evaluator.set_variable("MODE", &TomlValue::String(mode.to_string()))?;
+
+            for expr in exprs {
+                let expected = match evalexpr::eval_with_context(expr, &evaluator.context)? {
+                    Value::Boolean(b) => b,
+                    _ => unreachable!(),
+                };
+                // Once to populate the cache, once to hit it.
+                assert_eq!(evaluator.check_dependency(expr)?, expected);
+                assert_eq!(evaluator.check_dependency(expr)?, expected);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluator_cache_is_invalidated() -> Result<()> {
+        let mut evaluator = Evaluator::new();
+        evaluator.set_variable("A", &TomlValue::Boolean(true))?;
+        assert!(evaluator.check_dependency("A && !defined(B)")?);
+
+        evaluator.set_explicit(["B".to_string()].into());
+        assert!(!evaluator.check_dependency("A && !defined(B)")?);
+
+        evaluator.set_explicit(HashSet::new());
+        assert!(evaluator.check_dependency("A && !defined(B)")?);
+        evaluator.set_variable("A", &TomlValue::Boolean(false))?;
+        assert!(!evaluator.check_dependency("A && !defined(B)")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluator_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Evaluator>();
+    }
+
+    #[test]
+    fn test_evaluator_min_max() -> Result<()> {
+        let mut evaluator = Evaluator::new();
+        evaluator.set_variable("A", &TomlValue::Integer(2))?;
+        evaluator.set_variable("B", &TomlValue::Integer(5))?;
+
+        assert!(evaluator.check_dependency("max(A, B) > 4")?);
+        assert!(!evaluator.check_dependency("min(A, B) > 4")?);
+        assert!(evaluator.check_dependency("min(A, B) == 2")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluator_defined() -> Result<()> {
+        let parsed: crate::schema::KconfigFile = toml::from_str(
+            r#"
+            [[config]]
+            name = "SET"
+            desc = "SET"
+            type = "bool"
+            default = true
+
+            [[config]]
+            name = "SAME"
+            desc = "SAME"
+            type = "int"
+            default = 4
+
+            [[config]]
+            name = "DEFAULT_ONLY"
+            desc = "DEFAULT_ONLY"
+            type = "int"
+            default = 4
+
+            [[config]]
+            name = "CONDITIONAL"
+            desc = "CONDITIONAL"
+            type = "int"
+            default = 4
+            default_if = [{ value = 8, depends_on = "!SET" }]
+        "#,
+        )?;
+        let items = parsed.configs.unwrap();
+        let mut values = collect_defaults(&items);
+        values.insert("SET".to_string(), TomlValue::Boolean(false));
+        values.insert("CONDITIONAL".to_string(), TomlValue::Integer(8));
+        let explicit: HashSet<String> = ["SET", "SAME", "CONDITIONAL"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let mut evaluator = Evaluator::new();
+        for (name, val) in &values {
+            evaluator.set_variable(name, val)?;
+        }
+        evaluator.set_explicit(defined_symbols(&items, &values, &explicit));
+
+        assert!(evaluator.check_dependency("defined(SET)")?);
+        // Set to its default, or not set at all: neither counts.
+        assert!(!evaluator.check_dependency("defined(SAME)")?);
+        assert!(!evaluator.check_dependency("defined(CONDITIONAL)")?);
+        assert!(!evaluator.check_dependency("defined(DEFAULT_ONLY)")?);
+        assert!(!evaluator.check_dependency("defined(UNSET)")?);
+        assert!(evaluator.check_dependency("!defined( UNSET ) && !SET")?);
+
+        evaluator.set_explicit(["UNSET".to_string()].into());
+        assert!(evaluator.check_dependency("defined(UNSET)")?);
+        assert!(!evaluator.check_dependency("defined(SET)")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_defaults_from_env() {
+        let item = |name: &str, var: &str| ConfigItem {
+            default: Some(TomlValue::Integer(1)),
+            default_env: Some(var.to_string()),
+            ..ConfigItem::test_item(name, crate::schema::ConfigType::Int)
+        };
+        let items = vec![
+            item("FROM_ENV", "ANAXA_TEST_DEFAULT_FROM_ENV"),
+            item("BAD_ENV", "ANAXA_TEST_DEFAULT_BAD_ENV"),
+            item("UNSET_ENV", "ANAXA_TEST_DEFAULT_UNSET_ENV"),
+        ];
+        std::env::set_var("ANAXA_TEST_DEFAULT_FROM_ENV", "42");
+        std::env::set_var("ANAXA_TEST_DEFAULT_BAD_ENV", "forty-two");
+        std::env::remove_var("ANAXA_TEST_DEFAULT_UNSET_ENV");
+
+        let defaults = collect_defaults(&items);
+        assert_eq!(defaults.get("FROM_ENV"), Some(&TomlValue::Integer(42)));
+        assert_eq!(defaults.get("BAD_ENV"), Some(&TomlValue::Integer(1)));
+        assert_eq!(defaults.get("UNSET_ENV"), Some(&TomlValue::Integer(1)));
+    }
+
+    #[test]
+    fn test_default_precedence() {
+        let bool_item = |name: &str, default: bool| ConfigItem {
+            default: Some(TomlValue::Boolean(default)),
+            ..ConfigItem::test_item(name, crate::schema::ConfigType::Bool)
+        };
+        let conditional = |value: i64, depends_on: &str| crate::schema::ConditionalDefault {
+            value: TomlValue::Integer(value),
+            depends_on: depends_on.to_string(),
+        };
+        let mut base = ConfigItem {
+            default: Some(TomlValue::Integer(1)),
+            default_if: Some(vec![conditional(2, "ARM"), conditional(3, "RISCV")]),
+            ..ConfigItem::test_item("BASE", crate::schema::ConfigType::Int)
+        };
+        let items = vec![
+            bool_item("ARM", false),
+            bool_item("RISCV", true),
+            base.clone(),
+        ];
+
+        // The first matching `default_if` wins over `default`.
+        let defaults = collect_defaults(&items);
+        assert_eq!(defaults.get("BASE"), Some(&TomlValue::Integer(3)));
+
+        let mut evaluator = Evaluator::new();
+        evaluator
+            .set_variable("ARM", &TomlValue::Boolean(true))
+            .unwrap();
+        evaluator
+            .set_variable("RISCV", &TomlValue::Boolean(true))
+            .unwrap();
+        assert_eq!(
+            base.effective_default(&evaluator),
+            Some(TomlValue::Integer(2))
+        );
+        assert_eq!(
+            base.effective_default(&Evaluator::new()),
+            Some(TomlValue::Integer(1))
+        );
+
+        // The environment wins over both.
+        base.default_env = Some("ANAXA_TEST_DEFAULT_PRECEDENCE".to_string());
+        std::env::set_var("ANAXA_TEST_DEFAULT_PRECEDENCE", "4");
+        assert_eq!(
+            base.effective_default(&evaluator),
+            Some(TomlValue::Integer(4))
+        );
+        let items = vec![bool_item("ARM", true), base];
+        assert_eq!(
+            collect_defaults(&items).get("BASE"),
+            Some(&TomlValue::Integer(4))
+        );
+        std::env::remove_var("ANAXA_TEST_DEFAULT_PRECEDENCE");
+    }
+
+    #[test]
+    fn test_chained_conditional_defaults() {
+        let conditional = |value: TomlValue, depends_on: &str| {
+            Some(vec![crate::schema::ConditionalDefault {
+                value,
+                depends_on: depends_on.to_string(),
+            }])
+        };
+        // Declared before the symbols their conditions reference.
+        let items = vec![
+            ConfigItem {
+                default: Some(TomlValue::Integer(1)),
+                default_if: conditional(TomlValue::Integer(2), "B"),
+                ..ConfigItem::test_item("A", crate::schema::ConfigType::Int)
+            },
+            ConfigItem {
+                default: Some(TomlValue::Boolean(false)),
+                default_if: conditional(TomlValue::Boolean(true), "C"),
+                ..ConfigItem::test_bool("B", None)
+            },
+            ConfigItem {
+                default: Some(TomlValue::Boolean(true)),
+                ..ConfigItem::test_bool("C", None)
+            },
+        ];
+
+        let mut values = collect_defaults(&items);
+        assert_eq!(values.get("B"), Some(&TomlValue::Boolean(true)));
+        assert_eq!(values.get("A"), Some(&TomlValue::Integer(2)));
+
+        // Setting C re-resolves B, and A through it.
+        values.insert("C".to_string(), TomlValue::Boolean(false));
+        apply_conditional_defaults(&items, &mut values, &["C".to_string()].into());
+        assert_eq!(values.get("B"), Some(&TomlValue::Boolean(false)));
+        assert_eq!(values.get("A"), Some(&TomlValue::Integer(1)));
+    }
+
     #[test]
     fn test_evaluator_empty_expr() -> Result<()> {
         let evaluator = Evaluator::new();
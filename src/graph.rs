@@ -1,8 +1,11 @@
-use crate::schema::ConfigItem;
-use anyhow::{anyhow, Result};
-use petgraph::algo::tarjan_scc;
+use crate::error::{AnaxaError, CycleKind};
+use crate::evaluator::Evaluator;
+use crate::schema::{ConfigItem, ConfigType};
+use anyhow::Result;
+use petgraph::algo::{tarjan_scc, toposort};
 use petgraph::graphmap::DiGraphMap;
-use std::collections::HashMap;
+use petgraph::Direction;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 #[derive(Debug)]
 pub struct ConfigGraph<'a> {
@@ -10,7 +13,7 @@ pub struct ConfigGraph<'a> {
 }
 
 impl<'a> ConfigGraph<'a> {
-    pub fn build(items: &'a [ConfigItem]) -> Result<Self> {
+    pub fn build(items: &'a [ConfigItem]) -> Result<Self, AnaxaError> {
         let mut graph = DiGraphMap::new();
         let item_map: HashMap<&str, &str> = items
             .iter()
@@ -22,7 +25,18 @@ impl<'a> ConfigGraph<'a> {
         }
 
         for item in items {
-            if let Some(ref dep) = item.depends_on {
+            let option_deps = item
+                .options
+                .iter()
+                .flatten()
+                .filter_map(|o| o.depends_on.as_ref());
+            let default_deps = item.default_if.iter().flatten().map(|d| &d.depends_on);
+            for dep in item
+                .depends_on
+                .iter()
+                .chain(option_deps)
+                .chain(default_deps)
+            {
                 let vars = extract_variables(dep);
                 for var in vars {
                     if let Some(&dependency) = item_map.get(var.as_str()) {
@@ -32,52 +46,319 @@ impl<'a> ConfigGraph<'a> {
             }
         }
 
-        let sccs = tarjan_scc(&graph);
-        for scc in sccs {
-            if scc.len() > 1 {
-                return Err(anyhow!(
-                    "Cycle detected in configuration dependencies: {:?}",
-                    scc
-                ));
+        match find_cycle(&graph) {
+            Some(cycle) => Err(cycle),
+            None => Ok(Self { graph }),
+        }
+    }
+
+    /// Returns the symbols whose `depends_on`, or a `default_if` condition,
+    /// directly references `symbol`.
+    pub fn dependents(&self, symbol: &str) -> Vec<&'a str> {
+        match self.node(symbol) {
+            Some(node) => self
+                .graph
+                .neighbors_directed(node, Direction::Outgoing)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns every symbol `symbol` depends on, directly or through other
+    /// symbols, nearest first and alphabetically within each level. Unknown
+    /// symbols have no dependencies.
+    pub fn transitive_deps(&self, symbol: &str) -> Vec<&'a str> {
+        let Some(start) = self.node(symbol) else {
+            return Vec::new();
+        };
+        let mut seen = HashSet::from([start]);
+        let mut deps = Vec::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            let mut direct: Vec<&'a str> = self
+                .graph
+                .neighbors_directed(node, Direction::Incoming)
+                .filter(|dep| seen.insert(dep))
+                .collect();
+            direct.sort_unstable();
+            deps.extend(&direct);
+            queue.extend(direct);
+        }
+        deps
+    }
+
+    /// Looks up the graph's own key for `symbol`, so queries can take
+    /// borrowed names of any lifetime.
+    fn node(&self, symbol: &str) -> Option<&'a str> {
+        self.graph.nodes().find(|&n| n == symbol)
+    }
+
+    /// Returns all symbols in dependency order: every symbol appears after
+    /// the symbols its `depends_on` and `default_if` conditions reference.
+    pub fn topo_order(&self) -> Result<Vec<&'a str>, AnaxaError> {
+        toposort(&self.graph, None).map_err(|cycle| {
+            find_cycle(&self.graph).unwrap_or_else(|| AnaxaError::Cycle {
+                kind: CycleKind::Dependency,
+                path: vec![cycle.node_id().to_string()],
+            })
+        })
+    }
+}
+
+/// The first dependency cycle in `graph`, if any, as an
+/// [`AnaxaError::Cycle`].
+fn find_cycle(graph: &DiGraphMap<&str, ()>) -> Option<AnaxaError> {
+    tarjan_scc(graph).into_iter().find_map(|scc| {
+        let path = if scc.len() > 1 {
+            cycle_path(graph, &scc)
+        } else if graph.contains_edge(scc[0], scc[0]) {
+            vec![scc[0], scc[0]]
+        } else {
+            return None;
+        };
+        Some(AnaxaError::Cycle {
+            kind: CycleKind::Dependency,
+            path: path.into_iter().map(String::from).collect(),
+        })
+    })
+}
+
+/// Reconstructs a shortest cycle through the strongly connected component
+/// `scc`, starting and ending at its alphabetically first symbol. Each symbol
+/// in the returned path depends on the next one.
+fn cycle_path<'a>(graph: &DiGraphMap<&'a str, ()>, scc: &[&'a str]) -> Vec<&'a str> {
+    let members: HashSet<&str> = scc.iter().copied().collect();
+    let start = *scc.iter().min().unwrap();
+
+    // Breadth-first search along "depends on" edges (incoming in the graph)
+    // until one leads back to `start`.
+    let mut parent: HashMap<&'a str, &'a str> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        let mut deps: Vec<&'a str> = graph
+            .neighbors_directed(node, Direction::Incoming)
+            .filter(|dep| members.contains(dep))
+            .collect();
+        deps.sort_unstable();
+        for dep in deps {
+            if dep == start {
+                let mut path = vec![node];
+                while let Some(&prev) = parent.get(path[path.len() - 1]) {
+                    path.push(prev);
+                }
+                path.reverse();
+                path.push(start);
+                return path;
+            }
+            if !parent.contains_key(dep) {
+                parent.insert(dep, node);
+                queue.push_back(dep);
             }
-            if scc.len() == 1 {
-                let node = scc[0];
-                if graph.contains_edge(node, node) {
-                    return Err(anyhow!("Self-dependency cycle detected: {}", node));
+        }
+    }
+    scc.to_vec()
+}
+
+/// Fails if any `depends_on` or `visible_if` expression, on a config or on one
+/// of its choice options, references a symbol that no config defines.
+pub fn check_references(items: &[ConfigItem]) -> Result<(), AnaxaError> {
+    let known: HashSet<&str> = items.iter().map(|i| i.name.as_str()).collect();
+
+    for item in items {
+        let option_deps = item
+            .options
+            .iter()
+            .flatten()
+            .filter_map(|o| o.depends_on.as_ref());
+        let default_deps = item.default_if.iter().flatten().map(|d| &d.depends_on);
+        for dep in item
+            .depends_on
+            .iter()
+            .chain(&item.visible_if)
+            .chain(option_deps)
+            .chain(default_deps)
+        {
+            for var in extract_variables(dep) {
+                if var != "true" && var != "false" && !known.contains(var.as_str()) {
+                    return Err(AnaxaError::validation(
+                        &item.name,
+                        format!("Config '{}' depends on unknown symbol '{}'", item.name, var),
+                    ));
                 }
             }
         }
+    }
+
+    Ok(())
+}
+
+/// Upper bound on free symbols whose value combinations are enumerated when
+/// checking whether a dependency can ever be met. Beyond this we assume it can.
+const MAX_ENUMERATED_SYMBOLS: usize = 12;
+
+/// Reports symbols whose `depends_on` can never be satisfied.
+///
+/// Symbols are visited in dependency order. A symbol that can never become
+/// visible keeps its value from `root_defaults` forever; every other bool or
+/// choice symbol referenced by an expression is treated as free and all of its
+/// values are tried. Expressions involving other types, unknown symbols or
+/// too many free symbols are conservatively assumed to be satisfiable.
+pub fn find_unreachable(
+    items: &[ConfigItem],
+    root_defaults: &HashMap<String, toml::Value>,
+) -> Result<Vec<String>> {
+    let graph = ConfigGraph::build(items)?;
+    let by_name: HashMap<&str, &ConfigItem> = items.iter().map(|i| (i.name.as_str(), i)).collect();
+    let mut dead: HashSet<&str> = HashSet::new();
 
-        Ok(Self { graph })
+    for name in graph.topo_order()? {
+        if let Some(expr) = &by_name[name].depends_on {
+            if !is_satisfiable(expr, &by_name, &dead, root_defaults) {
+                dead.insert(name);
+            }
+        }
+    }
+
+    Ok(items
+        .iter()
+        .filter(|i| dead.contains(i.name.as_str()))
+        .map(|i| i.name.clone())
+        .collect())
+}
+
+fn is_satisfiable(
+    expr: &str,
+    by_name: &HashMap<&str, &ConfigItem>,
+    dead: &HashSet<&str>,
+    defaults: &HashMap<String, toml::Value>,
+) -> bool {
+    let mut evaluator = Evaluator::new();
+    let mut free: Vec<(&str, Vec<toml::Value>)> = Vec::new();
+
+    let mut vars = extract_variables(expr);
+    vars.sort();
+    vars.dedup();
+    for var in &vars {
+        if var == "true" || var == "false" {
+            continue;
+        }
+        let Some(item) = by_name.get(var.as_str()) else {
+            return true;
+        };
+        if dead.contains(var.as_str()) {
+            let value = match (defaults.get(var), &item.config_type) {
+                (Some(v), _) => v.clone(),
+                (None, ConfigType::Bool) => toml::Value::Boolean(false),
+                (None, _) => return true,
+            };
+            let _ = evaluator.set_variable(var, &value);
+        } else {
+            let domain = match item.config_type {
+                ConfigType::Bool => vec![toml::Value::Boolean(true), toml::Value::Boolean(false)],
+                ConfigType::Choice => item
+                    .option_values()
+                    .into_iter()
+                    .chain(item.optional.then_some(""))
+                    .map(|v| toml::Value::String(v.to_string()))
+                    .collect(),
+                _ => return true,
+            };
+            free.push((var.as_str(), domain));
+        }
+    }
+
+    if free.len() > MAX_ENUMERATED_SYMBOLS {
+        return true;
     }
+    any_assignment(&evaluator, &free, expr)
 }
 
-fn extract_variables(expr: &str) -> Vec<String> {
-    expr.split(|c: char| !c.is_alphanumeric() && c != '_')
-        .filter(|s| !s.is_empty() && !s.chars().next().unwrap().is_numeric())
-        .map(|s| s.to_string())
-        .collect()
+fn any_assignment(evaluator: &Evaluator, free: &[(&str, Vec<toml::Value>)], expr: &str) -> bool {
+    match free.split_first() {
+        None => evaluator.check_dependency(expr).unwrap_or(true),
+        Some(((name, domain), rest)) => domain.iter().any(|value| {
+            let mut evaluator = evaluator.clone();
+            let _ = evaluator.set_variable(name, value);
+            any_assignment(&evaluator, rest, expr)
+        }),
+    }
+}
+
+pub(crate) fn extract_variables(expr: &str) -> Vec<String> {
+    // `MODE:PROD` names the option `PROD`, not a symbol.
+    let expr = crate::evaluator::desugar_choice_equality(expr);
+    // Blank out quoted string literals so e.g. `MODE == "PROD"` only yields
+    // `MODE`; evalexpr strings use `"` with backslash escapes.
+    let mut code = String::with_capacity(expr.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in expr.chars() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            code.push(' ');
+        } else {
+            if c == '"' {
+                in_string = true;
+            }
+            code.push(if in_string { ' ' } else { c });
+        }
+    }
+
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut vars = Vec::new();
+    let mut rest = code.as_str();
+    while let Some(start) = rest.find(is_ident) {
+        rest = &rest[start..];
+        if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            rest = &rest[numeric_literal_len(rest)..];
+            continue;
+        }
+        let end = rest.find(|c| !is_ident(c)).unwrap_or(rest.len());
+        let (ident, tail) = rest.split_at(end);
+        // Identifiers followed by `(` are function calls such as `max(A, B)`.
+        if !ident.starts_with(char::is_numeric) && !tail.trim_start().starts_with('(') {
+            vars.push(ident.to_string());
+        }
+        rest = tail;
+    }
+    vars
+}
+
+/// Length of the numeric literal at the start of `s`: decimal (`1_000`,
+/// `1.5e-3`), hex (`0xFF`) or binary (`0b101`). Trailing identifier characters
+/// count as part of the literal, so a malformed one like `0xFG` yields no
+/// symbol either.
+fn numeric_literal_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let radix_prefixed = bytes.len() > 1 && bytes[0] == b'0' && bytes[1].is_ascii_alphabetic();
+    let digit_at = |i: usize| bytes.get(i).is_some_and(u8::is_ascii_digit);
+    let mut len = 0;
+    while len < bytes.len() {
+        let c = bytes[len];
+        let exponent_sign = (c == b'+' || c == b'-')
+            && !radix_prefixed
+            && matches!(bytes[len - 1], b'e' | b'E')
+            && digit_at(len + 1);
+        let decimal_point = c == b'.' && !radix_prefixed && digit_at(len + 1);
+        if !(c.is_ascii_alphanumeric() || c == b'_' || exponent_sign || decimal_point) {
+            break;
+        }
+        len += 1;
+    }
+    len
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::schema::{ConfigItem, ConfigType};
-
-    fn create_item(name: &str, depends_on: Option<&str>) -> ConfigItem {
-        ConfigItem {
-            name: name.to_string(),
-            config_type: ConfigType::Bool,
-            default: None,
-            desc: name.to_string(),
-            depends_on: depends_on.map(|s| s.to_string()),
-            help: None,
-            options: None,
-            feature: None,
-            range: None,
-            regex: None,
-        }
-    }
+    use crate::evaluator::collect_defaults;
+    use crate::schema::ConfigItem;
 
     #[test]
     fn test_extract_variables() {
@@ -86,14 +367,37 @@ mod tests {
 
         let vars = extract_variables("ENABLE_NET && MAX_SOCKETS > 10");
         assert_eq!(vars, vec!["ENABLE_NET", "MAX_SOCKETS"]);
+
+        let vars = extract_variables("MODE == \"PROD\"");
+        assert_eq!(vars, vec!["MODE"]);
+
+        let vars = extract_variables("NAME == \"a \\\" B\" && C");
+        assert_eq!(vars, vec!["NAME", "C"]);
+
+        let vars = extract_variables("max(A, B) > 4 && defined (C)");
+        assert_eq!(vars, vec!["A", "B", "C"]);
+
+        let vars = extract_variables("MODE:PROD && B");
+        assert_eq!(vars, vec!["MODE", "B"]);
+    }
+
+    #[test]
+    fn test_extract_variables_skips_numeric_literals() {
+        let vars = extract_variables("MASK == 0xFF && FLAGS != 0b101 && LIMIT > 1_000");
+        assert_eq!(vars, vec!["MASK", "FLAGS", "LIMIT"]);
+
+        let vars = extract_variables("RATIO < 1.5e-3 && SCALE > 2E+4 && 0x1E-A > 0");
+        assert_eq!(vars, vec!["RATIO", "SCALE", "A"]);
+
+        assert!(extract_variables("0xFG + 3.14").is_empty());
     }
 
     #[test]
     fn test_graph_build_success() -> Result<()> {
         let items = vec![
-            create_item("A", None),
-            create_item("B", Some("A")),
-            create_item("C", Some("B && A")),
+            ConfigItem::test_bool("A", None),
+            ConfigItem::test_bool("B", Some("A")),
+            ConfigItem::test_bool("C", Some("B && A")),
         ];
 
         let graph = ConfigGraph::build(&items)?;
@@ -104,16 +408,39 @@ mod tests {
 
     #[test]
     fn test_graph_cycle_detection() {
-        let items = vec![create_item("A", Some("B")), create_item("B", Some("A"))];
+        let items = vec![
+            ConfigItem::test_bool("A", Some("B")),
+            ConfigItem::test_bool("B", Some("A")),
+        ];
 
         let result = ConfigGraph::build(&items);
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Cycle detected"));
     }
 
+    #[test]
+    fn test_graph_cycle_path() {
+        let items = vec![
+            ConfigItem::test_bool("C", Some("A")),
+            ConfigItem::test_bool("A", Some("B && D")),
+            ConfigItem::test_bool("B", Some("C")),
+            ConfigItem::test_bool("D", None),
+        ];
+
+        let err = ConfigGraph::build(&items).unwrap_err();
+        assert!(err.to_string().contains("A -> B -> C -> A"), "{}", err);
+        match err {
+            AnaxaError::Cycle { kind, path } => {
+                assert_eq!(kind, CycleKind::Dependency);
+                assert_eq!(path, ["A", "B", "C", "A"]);
+            }
+            other => panic!("expected a cycle, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_graph_self_cycle() {
-        let items = vec![create_item("A", Some("A"))];
+        let items = vec![ConfigItem::test_bool("A", Some("A"))];
 
         let result = ConfigGraph::build(&items);
         assert!(result.is_err());
@@ -122,4 +449,108 @@ mod tests {
             .to_string()
             .contains("Self-dependency cycle"));
     }
+
+    #[test]
+    fn test_graph_cycle_through_default_if() {
+        let items = vec![
+            ConfigItem {
+                default_if: Some(vec![crate::schema::ConditionalDefault {
+                    value: toml::Value::Boolean(true),
+                    depends_on: "B".to_string(),
+                }]),
+                ..ConfigItem::test_bool("A", None)
+            },
+            ConfigItem::test_bool("B", Some("A")),
+        ];
+
+        let err = ConfigGraph::build(&items).unwrap_err();
+        assert!(err.to_string().contains("A -> B -> A"), "{}", err);
+    }
+
+    #[test]
+    fn test_topo_order() -> Result<()> {
+        let items = vec![
+            ConfigItem::test_bool("C", Some("B")),
+            ConfigItem::test_bool("B", Some("A")),
+            ConfigItem::test_bool("A", None),
+        ];
+
+        let graph = ConfigGraph::build(&items)?;
+        assert_eq!(graph.topo_order()?, vec!["A", "B", "C"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dependents() -> Result<()> {
+        let items = vec![
+            ConfigItem::test_bool("A", None),
+            ConfigItem::test_bool("B", Some("A")),
+            ConfigItem::test_bool("C", Some("A && !D")),
+            ConfigItem::test_bool("D", None),
+        ];
+
+        let graph = ConfigGraph::build(&items)?;
+        let mut deps = graph.dependents("A");
+        deps.sort();
+        assert_eq!(deps, vec!["B", "C"]);
+        assert_eq!(graph.dependents("D"), vec!["C"]);
+        assert!(graph.dependents("B").is_empty());
+        assert!(graph.dependents("MISSING").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_transitive_deps() -> Result<()> {
+        let items = vec![
+            ConfigItem::test_bool("SSL", Some("NET && CRYPTO")),
+            ConfigItem::test_bool("NET", Some("DRIVERS")),
+            ConfigItem::test_bool("CRYPTO", None),
+            ConfigItem::test_bool("DRIVERS", None),
+            ConfigItem::test_bool("USB", Some("DRIVERS")),
+        ];
+
+        let graph = ConfigGraph::build(&items)?;
+        assert_eq!(
+            graph.transitive_deps("SSL"),
+            vec!["CRYPTO", "NET", "DRIVERS"]
+        );
+        assert_eq!(graph.transitive_deps("NET"), vec!["DRIVERS"]);
+        assert!(graph.transitive_deps("DRIVERS").is_empty());
+        assert!(graph.transitive_deps("MISSING").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_references() {
+        let items = vec![
+            ConfigItem::test_bool("ENABLE_NET", None),
+            ConfigItem::test_bool("MAX_SOCKETS", Some("ENABLE_NET && true")),
+        ];
+        assert!(check_references(&items).is_ok());
+
+        let items = vec![
+            ConfigItem::test_bool("ENABLE_NET", None),
+            ConfigItem::test_bool("MAX_SOCKETS", Some("ENABLE_NT && 1 < 2")),
+        ];
+        let err = check_references(&items).unwrap_err();
+        assert!(matches!(&err, AnaxaError::Validation { symbol, .. } if symbol == "MAX_SOCKETS"));
+        let err = err.to_string();
+        assert!(err.contains("MAX_SOCKETS"));
+        assert!(err.contains("ENABLE_NT"));
+    }
+
+    #[test]
+    fn test_find_unreachable() -> Result<()> {
+        let items = vec![
+            ConfigItem::test_bool("A", Some("false")),
+            ConfigItem::test_bool("B", Some("A")),
+            ConfigItem::test_bool("C", Some("D && !D")),
+            ConfigItem::test_bool("D", None),
+            ConfigItem::test_bool("E", Some("D || A")),
+        ];
+
+        let unreachable = find_unreachable(&items, &collect_defaults(&items))?;
+        assert_eq!(unreachable, vec!["A", "B", "C"]);
+        Ok(())
+    }
 }
@@ -1,11 +1,127 @@
 pub mod build_rs;
 pub mod codegen;
 pub mod config_io;
+pub mod error;
 pub mod evaluator;
 pub mod graph;
 pub mod parser;
 pub mod schema;
+pub mod term;
+#[cfg(feature = "tui")]
 pub mod tui;
 
 pub use build_rs::BuildHelper;
 pub use codegen::rust::generate_consts;
+pub use error::AnaxaError;
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Fully resolved configuration values: schema defaults overlaid with the
+/// values from a `.config` file.
+#[derive(Debug, Clone)]
+pub struct ResolvedConfig {
+    values: HashMap<String, toml::Value>,
+}
+
+impl ResolvedConfig {
+    pub fn get(&self, name: &str) -> Option<&toml::Value> {
+        self.values.get(name)
+    }
+
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get(name)?.as_bool()
+    }
+
+    pub fn get_int(&self, name: &str) -> Option<i64> {
+        self.get(name)?.as_integer()
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name)?.as_str()
+    }
+
+    pub fn values(&self) -> &HashMap<String, toml::Value> {
+        &self.values
+    }
+}
+
+/// Scans `kconfig_dir` for schemas and resolves their values against
+/// `config_file`. A missing config file resolves to the schema defaults and,
+/// unlike [`config_io::load_config`], is not created.
+pub fn resolve<P1, P2>(kconfig_dir: P1, config_file: P2) -> Result<ResolvedConfig>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let tree = parser::build_config_tree(kconfig_dir.as_ref())?;
+    let configs = parser::flatten_configs(&tree);
+    let values = if config_file.as_ref().exists() {
+        config_io::load_config(config_file.as_ref(), &configs)?
+    } else {
+        evaluator::collect_defaults(&configs)
+    };
+    Ok(ResolvedConfig {
+        values: config_io::expand_templates(&values),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_resolve_typed_getters() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "ENABLE_NET"
+type = "bool"
+default = false
+desc = "Net"
+
+[[config]]
+name = "MAX_SOCKETS"
+type = "int"
+default = 16
+desc = "Sockets"
+
+[[config]]
+name = "DEVICE_NAME"
+type = "string"
+default = "node"
+desc = "Name"
+"#,
+        )?;
+        let config_path = dir.path().join(".config");
+        fs::write(&config_path, "ENABLE_NET = true\n")?;
+
+        let resolved = resolve(dir.path(), &config_path)?;
+        assert_eq!(resolved.get_bool("ENABLE_NET"), Some(true));
+        assert_eq!(resolved.get_int("MAX_SOCKETS"), Some(16));
+        assert_eq!(resolved.get_str("DEVICE_NAME"), Some("node"));
+
+        assert_eq!(resolved.get_bool("MISSING"), None);
+        assert_eq!(resolved.get_int("DEVICE_NAME"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_without_config_file() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(
+            dir.path().join("Kconfig.toml"),
+            "[[config]]\nname = \"A\"\ntype = \"bool\"\ndefault = true\ndesc = \"A\"\n",
+        )?;
+        let config_path = dir.path().join(".config");
+
+        let resolved = resolve(dir.path(), &config_path)?;
+        assert_eq!(resolved.get_bool("A"), Some(true));
+        assert!(!config_path.exists());
+        Ok(())
+    }
+}
@@ -1,7 +1,13 @@
-use anaxa_builder::{graph, parser};
-use anyhow::Result;
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use anaxa_builder::schema::{ConfigItem, ConfigNode};
+use anaxa_builder::tui::Theme;
+use anaxa_builder::{codegen, config_io, graph, parser, term};
+use anyhow::{Context, Result};
+use clap::builder::PossibleValuesParser;
+use clap::{Args, Parser, Subcommand};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(name = "cargo-anaxa")]
@@ -14,21 +20,50 @@ struct Cli {
     /// Source directory containing Kconfig.toml files
     #[arg(short, long, default_value = "src", global = true)]
     dir: PathBuf,
+
+    /// Fail on invalid values in the config file instead of skipping them
+    #[arg(long, global = true)]
+    strict: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Validate schemas and check for cycles
-    Check,
+    Check {
+        /// Also verify the values in this config file, failing on any violation
+        #[arg(short, long)]
+        config_file: Option<PathBuf>,
+    },
     /// Inspect parsed configuration structure
-    Dump,
+    Dump {
+        /// How to print the tree: Rust debug output, an indented tree of
+        /// menus and symbols, one symbol name per line, or JSON
+        #[arg(long, alias = "output-format", value_enum, default_value_t = DumpFormat::Debug)]
+        format: DumpFormat,
+        /// Print the configuration tree as JSON (same as `--format json`)
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+    },
+    /// Summarize the schema: symbols by type, dependencies, menu depth and
+    /// features, plus how many values in the config file differ from defaults
+    Stats {
+        #[arg(short, long, default_value = ".config")]
+        config_file: PathBuf,
+    },
     /// Launch interactive TUI
     Menuconfig {
         /// Path to the local configuration file
         #[arg(short, long, default_value = ".config")]
         config: PathBuf,
+        /// Color theme
+        #[arg(long, default_value = "default")]
+        #[arg(value_parser = PossibleValuesParser::new(Theme::NAMES))]
+        theme: String,
+        /// Drop values of symbols whose dependencies are unmet when saving
+        #[arg(long)]
+        prune_hidden: bool,
     },
-    /// Generate code artifacts (Rust, C, DOT)
+    /// Generate code artifacts (Rust, C, DOT, JSON Schema, Markdown, ...)
     Generate {
         /// Output directory for generated files
         #[arg(short, long, default_value = "generated")]
@@ -36,15 +71,17 @@ enum Commands {
         /// Path to the local configuration file
         #[arg(short, long, default_value = ".config")]
         config_file: PathBuf,
-        /// Generate C autoconf.h header
-        #[arg(long)]
-        c: bool,
-        /// Generate Rust constants and cfgs
+        #[command(flatten)]
+        formats: Formats,
+        /// Generate every format
         #[arg(long)]
-        rust: bool,
-        /// Generate DOT dependency graph
+        all: bool,
+        /// Annotate the DOT graph with current config values (with --dot or --all)
         #[arg(long)]
-        dot: bool,
+        dot_values: bool,
+        /// Prefix of the variables in config.env
+        #[arg(long, default_value = codegen::env::DEFAULT_PREFIX)]
+        env_prefix: String,
     },
     /// Wrapper for cargo build with dynamic features from config
     Build {
@@ -54,6 +91,15 @@ enum Commands {
         /// Do not inject ANAXA_* environment variables
         #[arg(long)]
         no_env: bool,
+        /// Prefix of the injected environment variables
+        #[arg(long, default_value = codegen::env::DEFAULT_PREFIX)]
+        env_prefix: String,
+        /// Also pass string/choice configs as `--cfg NAME="value"`
+        #[arg(long)]
+        cfg_values: bool,
+        /// Print the cargo invocation with its environment instead of running it
+        #[arg(long)]
+        dry_run: bool,
         /// Additional arguments to pass to cargo build
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
@@ -65,15 +111,560 @@ enum Commands {
         #[arg(short, long, default_value = ".config")]
         config_file: PathBuf,
     },
+    /// Write a commented config scaffold listing every symbol with its default
+    Template {
+        #[arg(short, long, default_value = "template.config")]
+        out: PathBuf,
+    },
     /// Generate config from defconfig
     Defconfig {
         #[arg(short, long)]
         file: PathBuf,
         #[arg(short, long, default_value = ".config")]
         config_file: PathBuf,
+        /// Drop values of symbols whose dependencies are unmet when saving
+        #[arg(long)]
+        prune_hidden: bool,
+    },
+    /// Verify that a config file is complete and valid for the schema
+    Verify {
+        #[arg(short, long, default_value = ".config")]
+        config_file: PathBuf,
+    },
+    /// Verify every config file (`.config` or `*.config`) under a directory
+    VerifyAll {
+        /// Directory searched recursively for config files
+        #[arg(value_name = "DIR")]
+        configs_dir: PathBuf,
+    },
+    /// Merge config fragments on top of a base config, later ones winning
+    Merge {
+        /// Base config file
+        base: PathBuf,
+        /// Fragments applied in order on top of the base
+        fragments: Vec<PathBuf>,
+        #[arg(short, long, default_value = ".config")]
+        config_file: PathBuf,
+        /// Drop values of symbols whose dependencies are unmet when saving
+        #[arg(long)]
+        prune_hidden: bool,
+    },
+    /// Print the resolved value of a symbol
+    Get {
+        symbol: String,
+        #[arg(short, long, default_value = ".config")]
+        config_file: PathBuf,
+        /// Print the name, type, value and source as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Explain which dependencies keep a symbol disabled or hidden
+    Why {
+        symbol: String,
+        #[arg(short, long, default_value = ".config")]
+        config_file: PathBuf,
+    },
+    /// Set config values without the TUI, e.g. `set MAX_CONN=64 DEBUG=y`
+    Set {
+        /// Assignments of the form SYMBOL=value
+        #[arg(required = true)]
+        assignments: Vec<String>,
+        #[arg(short, long, default_value = ".config")]
+        config_file: PathBuf,
+        /// Drop values of symbols whose dependencies are unmet when saving
+        #[arg(long)]
+        prune_hidden: bool,
     },
 }
 
+/// Output formats of `generate`.
+#[derive(Args, Debug, Clone, Copy, Default, PartialEq)]
+struct Formats {
+    /// Generate C autoconf.h header
+    #[arg(long)]
+    c: bool,
+    /// Generate Rust constants and cfgs
+    #[arg(long)]
+    rust: bool,
+    /// Generate DOT dependency graph
+    #[arg(long)]
+    dot: bool,
+    /// Generate a Mermaid dependency graph
+    #[arg(long)]
+    mermaid: bool,
+    /// Generate JSON Schema for .config files
+    #[arg(long)]
+    json_schema: bool,
+    /// Generate Markdown documentation of all symbols
+    #[arg(long)]
+    md: bool,
+    /// Generate a Cargo [features] table for referenced features
+    #[arg(long)]
+    cargo_features: bool,
+    /// Generate a Python module with config constants
+    #[arg(long)]
+    python: bool,
+    /// Generate a config.env file with ANAXA_* variables
+    #[arg(long)]
+    env: bool,
+}
+
+impl Formats {
+    const ALL: Formats = Formats {
+        c: true,
+        rust: true,
+        dot: true,
+        mermaid: true,
+        json_schema: true,
+        md: true,
+        cargo_features: true,
+        python: true,
+        env: true,
+    };
+
+    /// Applies `--all` and rejects a selection without any format, which
+    /// would otherwise silently generate nothing.
+    fn resolve(self, all: bool) -> Result<Formats> {
+        if all {
+            return Ok(Formats::ALL);
+        }
+        if self == Formats::default() {
+            anyhow::bail!(
+                "No output format selected. Pass one or more of --rust, --c, --dot, \
+                 --mermaid, --json-schema, --md, --cargo-features, --python, --env, or --all"
+            );
+        }
+        Ok(self)
+    }
+}
+
+/// Writes the artifacts selected in `formats` to `out`, returning the
+/// `(format, file name)` of each.
+fn generate_artifacts(
+    out: &Path,
+    tree: &ConfigNode,
+    configs: &[ConfigItem],
+    values: &HashMap<String, toml::Value>,
+    formats: Formats,
+    dot_values: bool,
+    env_prefix: &str,
+) -> Result<Vec<(&'static str, &'static str)>> {
+    if !out.exists() {
+        std::fs::create_dir_all(out)?;
+    }
+
+    let mut generated = Vec::new();
+    let mut emit = |format: &'static str, file: &'static str, what: &str, content: String| {
+        std::fs::write(out.join(file), content)?;
+        println!("Generated {} in {:?}", what, out.join(file));
+        generated.push((format, file));
+        anyhow::Ok(())
+    };
+
+    if formats.rust {
+        let rust_code = codegen::rust::generate_consts(configs, values)?;
+        emit("rust", "config.rs", "Rust constants", rust_code)?;
+    }
+    if formats.c {
+        let c_code = codegen::c::generate(configs, values)?;
+        emit("c", "autoconf.h", "C header", c_code)?;
+    }
+    if formats.dot {
+        let graph = graph::ConfigGraph::build(configs)?;
+        let dot_code = if dot_values {
+            codegen::dot::generate_with_values(&graph, values)?
+        } else {
+            codegen::dot::generate(&graph)?
+        };
+        emit("dot", "depends.dot", "DOT graph", dot_code)?;
+    }
+    if formats.mermaid {
+        let graph = graph::ConfigGraph::build(configs)?;
+        let mermaid_code = codegen::mermaid::generate(&graph)?;
+        emit("mermaid", "depends.mmd", "Mermaid graph", mermaid_code)?;
+    }
+    if formats.json_schema {
+        let schema = codegen::json_schema::generate(configs)?;
+        emit("json-schema", "config.schema.json", "JSON Schema", schema)?;
+    }
+    if formats.md {
+        let md_doc = codegen::markdown::generate(tree)?;
+        emit("md", "config.md", "Markdown docs", md_doc)?;
+    }
+    if formats.cargo_features {
+        let table = codegen::cargo::generate_features(configs)?;
+        emit(
+            "cargo-features",
+            "features.toml",
+            "Cargo features table",
+            table,
+        )?;
+    }
+    if formats.python {
+        let py_code = codegen::python::generate(configs, values)?;
+        emit("python", "config.py", "Python module", py_code)?;
+    }
+    if formats.env {
+        let env_file = codegen::env::generate(configs, values, env_prefix)?;
+        emit("env", "config.env", "env file", env_file)?;
+    }
+
+    Ok(generated)
+}
+
+/// Writes `manifest.json` listing the generated files and their formats, plus
+/// a hash of the inputs they were generated from.
+fn write_manifest(out: &Path, generated: &[(&str, &str)], inputs_hash: &str) -> Result<()> {
+    let files: Vec<_> = generated
+        .iter()
+        .map(|(format, file)| serde_json::json!({ "file": file, "format": format }))
+        .collect();
+    let manifest = serde_json::json!({
+        "generator": format!("anaxa-builder {}", env!("CARGO_PKG_VERSION")),
+        "inputs_hash": inputs_hash,
+        "files": files,
+    });
+    std::fs::write(
+        out.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+    Ok(())
+}
+
+fn load_values(
+    path: &Path,
+    configs: &[ConfigItem],
+    strict: bool,
+) -> Result<HashMap<String, toml::Value>> {
+    if strict {
+        Ok(config_io::load_config_strict(path, configs)?)
+    } else {
+        Ok(config_io::load_config(path, configs)?)
+    }
+}
+
+/// Parses `SYMBOL=value` assignments, coercing each value to its symbol's
+/// type, and applies them to `values`. Nothing is applied unless every
+/// assignment is valid.
+fn apply_assignments(
+    assignments: &[String],
+    configs: &[ConfigItem],
+    values: &mut HashMap<String, toml::Value>,
+) -> Result<()> {
+    let mut parsed = Vec::new();
+    let mut errors = Vec::new();
+    for assignment in assignments {
+        let Some((name, text)) = assignment.split_once('=') else {
+            errors.push(format!("'{}' is not of the form SYMBOL=value", assignment));
+            continue;
+        };
+        let name = name.trim();
+        let Some(item) = configs.iter().find(|i| i.name == name) else {
+            errors.push(format!("Unknown config '{}'", name));
+            continue;
+        };
+        match item.parse_value(text) {
+            Ok(val) => parsed.push((item.name.clone(), val)),
+            Err(e) => errors.push(e),
+        }
+    }
+    if !errors.is_empty() {
+        anyhow::bail!("{}", errors.join("\n"));
+    }
+    values.extend(parsed);
+    Ok(())
+}
+
+/// Resolves `symbol` against `config_file` (without creating it), returning
+/// its config item, value and whether the file sets it explicitly.
+fn lookup_symbol<'a>(
+    symbol: &str,
+    config_file: &Path,
+    configs: &'a [ConfigItem],
+) -> Result<(&'a ConfigItem, Option<toml::Value>, bool)> {
+    let item = configs
+        .iter()
+        .find(|i| i.name == symbol)
+        .with_context(|| format!("Unknown config '{}'", symbol))?;
+    let mut values = config_io::expand_templates(&current_values(config_file, configs)?);
+    let explicit = config_io::explicit_keys(config_file, configs)?.contains(&item.name);
+    Ok((item, values.remove(&item.name), explicit))
+}
+
+/// Values of `config_file`, or the defaults if it doesn't exist. Unlike
+/// [`config_io::load_config`], a missing file isn't created.
+fn current_values(
+    config_file: &Path,
+    configs: &[ConfigItem],
+) -> Result<HashMap<String, toml::Value>> {
+    if config_file.exists() {
+        Ok(config_io::load_config(config_file, configs)?)
+    } else {
+        Ok(anaxa_builder::evaluator::collect_defaults(configs))
+    }
+}
+
+/// Renders a value for shell scripts: strings unquoted, hex in hex and
+/// string lists comma-separated.
+fn plain_value(item: &ConfigItem, val: &toml::Value) -> String {
+    if item.config_type == anaxa_builder::schema::ConfigType::Hex {
+        if let Some(u) = anaxa_builder::schema::hex_value(val) {
+            return format!("0x{:x}", u);
+        }
+    }
+    codegen::env::value_string(item, val).unwrap_or_else(|| val.to_string())
+}
+
+/// Counters printed by `stats`.
+#[derive(Debug, Default, PartialEq)]
+struct Stats {
+    /// Number of symbols per type, keyed by the schema's type name.
+    by_type: BTreeMap<String, usize>,
+    total: usize,
+    /// Symbols with a `depends_on` of their own or from an enclosing menu.
+    with_dependencies: usize,
+    /// Nesting depth of the deepest menu; the root menu is depth 0.
+    max_depth: usize,
+    /// Distinct cargo features named by the symbols.
+    features: usize,
+    /// Symbols whose value differs from the default, when values are given.
+    modified: Option<usize>,
+}
+
+fn compute_stats(
+    tree: &ConfigNode,
+    configs: &[ConfigItem],
+    values: Option<&HashMap<String, toml::Value>>,
+    defined: &HashSet<String>,
+) -> Result<Stats> {
+    fn depth(node: &ConfigNode) -> usize {
+        node.children
+            .iter()
+            .map(|c| depth(c) + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    let mut by_type = BTreeMap::new();
+    for item in configs {
+        let name = serde_json::to_value(&item.config_type)?;
+        *by_type
+            .entry(name.as_str().unwrap_or_default().to_string())
+            .or_insert(0) += 1;
+    }
+    let modified = match values {
+        Some(values) => {
+            let mut evaluator = anaxa_builder::evaluator::Evaluator::new();
+            for (name, val) in values {
+                evaluator.set_variable(name, val)?;
+            }
+            evaluator.set_explicit(defined.clone());
+            Some(config_io::get_minimal_config(values, configs, &evaluator).len())
+        }
+        None => None,
+    };
+    let features: BTreeSet<&String> = configs
+        .iter()
+        .flat_map(|i| i.feature.iter().flatten())
+        .collect();
+    Ok(Stats {
+        by_type,
+        total: configs.len(),
+        with_dependencies: configs.iter().filter(|i| i.depends_on.is_some()).count(),
+        max_depth: depth(tree),
+        features: features.len(),
+        modified,
+    })
+}
+
+/// The `cargo build` invocation for `values`: enabled bools whose
+/// dependencies are met become features and `--cfg` flags in RUSTFLAGS, and
+/// every value is exported as an environment variable named with
+/// `env_prefix` unless it is `None`.
+fn build_command(
+    configs: &[ConfigItem],
+    values: &HashMap<String, toml::Value>,
+    defined: &HashSet<String>,
+    env_prefix: Option<&str>,
+    cfg_values: bool,
+    args: &[String],
+) -> Result<std::process::Command> {
+    let mut evaluator = anaxa_builder::evaluator::Evaluator::new();
+    for (name, val) in values {
+        evaluator.set_variable(name, val)?;
+    }
+    evaluator.set_explicit(defined.clone());
+
+    let mut features = Vec::new();
+    let mut cfgs = Vec::new();
+    for item in configs {
+        if let Some(expr) = &item.depends_on {
+            if !evaluator.check_dependency(expr)? {
+                continue;
+            }
+        }
+        if let Some(val) = values.get(&item.name) {
+            if val.as_bool() == Some(true) {
+                if cfgs.contains(&item.name) {
+                    continue;
+                }
+                cfgs.push(item.name.clone());
+                if let Some(f) = &item.feature {
+                    features.extend(f.iter().cloned());
+                }
+            }
+        }
+    }
+    if cfg_values {
+        cfgs.extend(codegen::rust::generate_rust_value_cfgs(configs, values)?);
+    }
+
+    let mut cmd = std::process::Command::new("cargo");
+    cmd.arg("build");
+    if !features.is_empty() {
+        cmd.arg("--features");
+        cmd.arg(features.join(","));
+    }
+    if !cfgs.is_empty() {
+        let raw_rustflags = std::env::var("RUSTFLAGS").unwrap_or_default();
+        cmd.env(
+            "RUSTFLAGS",
+            format!("{} --cfg {}", raw_rustflags, cfgs.join(" --cfg ")).trim_start(),
+        );
+    }
+    if let Some(prefix) = env_prefix {
+        let mut items: Vec<&ConfigItem> = configs.iter().collect();
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        for item in items {
+            if let Some(v) = values
+                .get(&item.name)
+                .and_then(|val| codegen::env::value_string(item, val))
+            {
+                cmd.env(codegen::env::var_name(prefix, &item.name), v);
+            }
+        }
+    }
+    cmd.args(args);
+    Ok(cmd)
+}
+
+/// Renders `cmd` as shell lines: one `NAME=value` line per environment
+/// variable it sets, then the command itself.
+fn describe_command(cmd: &std::process::Command) -> String {
+    let quote = |s: &std::ffi::OsStr| {
+        let s = s.to_string_lossy();
+        let plain = !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_-.,/:=+@%".contains(c));
+        if plain {
+            s.into_owned()
+        } else {
+            format!("'{}'", s.replace('\'', "'\\''"))
+        }
+    };
+
+    let mut lines: Vec<String> = cmd
+        .get_envs()
+        .filter_map(|(k, v)| Some(format!("{}={}", k.to_string_lossy(), quote(v?))))
+        .collect();
+    lines.sort();
+    let words: Vec<String> = std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(quote)
+        .collect();
+    lines.push(words.join(" "));
+    lines.join("\n")
+}
+
+/// Prints every violation in `config_file` and fails if there are any.
+fn report_violations(config_file: &Path, configs: &[ConfigItem]) -> Result<()> {
+    let violations = config_io::verify_config(config_file, configs)?;
+    for violation in &violations {
+        eprintln!("{} {}", term::Styler::stderr().error(), violation);
+    }
+    if !violations.is_empty() {
+        anyhow::bail!("{} violation(s) in {:?}", violations.len(), config_file);
+    }
+    Ok(())
+}
+
+/// Output format of `dump`.
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum DumpFormat {
+    Debug,
+    Tree,
+    Flat,
+    Json,
+}
+
+/// Renders `node` as an indented tree: menus end in `/`, symbols show their
+/// type and description.
+fn render_tree(node: &ConfigNode) -> Result<String> {
+    fn render(node: &ConfigNode, depth: usize, out: &mut String) -> Result<()> {
+        let indent = "  ".repeat(depth);
+        writeln!(out, "{}{}/", indent, node.desc)?;
+        for item in &node.configs {
+            let config_type = serde_json::to_value(&item.config_type)?;
+            write!(
+                out,
+                "{}  {} ({})",
+                indent,
+                item.name,
+                config_type.as_str().unwrap_or_default()
+            )?;
+            if item.desc.is_empty() {
+                writeln!(out)?;
+            } else {
+                writeln!(out, ": {}", item.desc.replace('\n', " "))?;
+            }
+        }
+        for child in &node.children {
+            render(child, depth + 1, out)?;
+        }
+        Ok(())
+    }
+
+    let mut out = String::new();
+    render(node, 0, &mut out)?;
+    Ok(out)
+}
+
+/// Renders every symbol name in `node`, one per line, in schema order.
+fn render_flat(node: &ConfigNode) -> String {
+    parser::flatten_configs(node)
+        .iter()
+        .map(|item| format!("{}\n", item.name))
+        .collect()
+}
+
+/// Config files under `dir`: files named `.config` or with a `.config`
+/// extension, sorted by path.
+fn find_config_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.with_context(|| format!("Failed to read {:?}", dir))?;
+        let path = entry.path();
+        let is_config = path.file_name().is_some_and(|n| n == ".config")
+            || path.extension().is_some_and(|e| e == "config");
+        if entry.file_type().is_file() && is_config {
+            files.push(path.to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+/// Verifies `files` in parallel, returning the violations of each file in
+/// the order given.
+fn verify_files(
+    files: &[PathBuf],
+    configs: &[ConfigItem],
+) -> Vec<Result<Vec<config_io::Violation>>> {
+    files
+        .par_iter()
+        .map(|file| config_io::verify_config(file, configs))
+        .collect()
+}
+
 fn main() -> Result<()> {
     let mut args: Vec<String> = std::env::args().collect();
     // When called as `cargo anaxa`, the arguments are `["cargo-anaxa", "anaxa", ...]`
@@ -86,120 +677,160 @@ fn main() -> Result<()> {
     let dir = &cli.dir;
 
     match &cli.command {
-        Commands::Check => {
+        Commands::Check { config_file } => {
             let tree = parser::build_config_tree(dir)?;
+            parser::check_duplicates(&tree)?;
+            parser::check_shadowing(&tree)?;
             let configs = parser::flatten_configs(&tree);
             graph::ConfigGraph::build(&configs)?;
+            graph::check_references(&configs)?;
 
             for item in &configs {
-                if let Some(default_val) = &item.default {
-                    if let Err(e) = item.validate(default_val) {
-                        anyhow::bail!("Invalid default value for config '{}': {}", item.name, e);
+                if let Some(mapped) = &item.option_values {
+                    if mapped.len() != item.option_values().len() {
+                        anyhow::bail!(
+                            "Config '{}' has {} option_values for {} options",
+                            item.name,
+                            mapped.len(),
+                            item.option_values().len()
+                        );
                     }
                 }
-                if item.config_type == anaxa_builder::schema::ConfigType::Choice
-                    && (item.options.is_none() || item.options.as_ref().unwrap().is_empty())
-                {
-                    anyhow::bail!("Config '{}' is a choice but has no options", item.name);
+            }
+
+            for name in parser::choices_without_default(&configs) {
+                eprintln!(
+                    "{} choice '{}' has no default and is not optional; config files must select an option",
+                    term::Styler::stderr().warning(),
+                    name
+                );
+            }
+
+            let defaults = anaxa_builder::evaluator::collect_defaults(&configs);
+            for name in graph::find_unreachable(&configs, &defaults)? {
+                eprintln!(
+                    "{} config '{}' can never be enabled (its dependencies are unsatisfiable)",
+                    term::Styler::stderr().warning(),
+                    name
+                );
+            }
+
+            let default_config = Path::new(".config");
+            if let Some(config_file) = config_file {
+                report_violations(config_file, &configs)?;
+            } else if default_config.exists() {
+                let unknown = if cli.strict {
+                    config_io::load_config_strict(default_config, &configs)?;
+                    Vec::new()
+                } else {
+                    config_io::load_config_reporting(default_config, &configs)?.1
+                };
+                for key in &unknown {
+                    eprintln!(
+                        "{} {:?} sets unknown config '{}' (renamed or removed?)",
+                        term::Styler::stderr().warning(),
+                        default_config,
+                        key
+                    );
                 }
             }
 
             println!("Configuration valid ({} items, no cycles).", configs.len());
         }
-        Commands::Dump => {
+        Commands::Dump { format, json } => {
             let tree = parser::build_config_tree(dir)?;
-            println!("{:#?}", tree);
+            let format = if *json { DumpFormat::Json } else { *format };
+            match format {
+                DumpFormat::Debug => println!("{:#?}", tree),
+                DumpFormat::Tree => print!("{}", render_tree(&tree)?),
+                DumpFormat::Flat => print!("{}", render_flat(&tree)),
+                DumpFormat::Json => println!("{}", serde_json::to_string_pretty(&tree)?),
+            }
         }
-        Commands::Menuconfig { config } => {
+        Commands::Stats { config_file } => {
             let tree = parser::build_config_tree(dir)?;
-            anaxa_builder::tui::run(tree, config.clone())?;
+            let configs = parser::flatten_configs(&tree);
+            let values = if config_file.exists() {
+                Some(load_values(config_file, &configs, cli.strict)?)
+            } else {
+                None
+            };
+            let defined = match &values {
+                Some(values) => config_io::defined_keys(config_file, &configs, values)?,
+                None => HashSet::new(),
+            };
+            let stats = compute_stats(&tree, &configs, values.as_ref(), &defined)?;
+
+            println!("Symbols: {}", stats.total);
+            for (config_type, count) in &stats.by_type {
+                println!("  {}: {}", config_type, count);
+            }
+            println!("With dependencies: {}", stats.with_dependencies);
+            println!("Max menu depth: {}", stats.max_depth);
+            println!("Features: {}", stats.features);
+            if let Some(modified) = stats.modified {
+                println!("Modified in {:?}: {}", config_file, modified);
+            }
+        }
+        Commands::Menuconfig {
+            config,
+            theme,
+            prune_hidden,
+        } => {
+            let tree = parser::build_config_tree(dir)?;
+            let theme = Theme::from_name(theme).unwrap_or_default();
+            anaxa_builder::tui::run_with_options(tree, config.clone(), theme, *prune_hidden)?;
         }
         Commands::Generate {
             out,
             config_file,
-            c,
-            rust,
-            dot,
+            formats,
+            all,
+            dot_values,
+            env_prefix,
         } => {
+            let formats = formats.resolve(*all)?;
             let tree = parser::build_config_tree(dir)?;
             let configs = parser::flatten_configs(&tree);
-            let values = anaxa_builder::config_io::load_config(config_file, &configs)?;
-
-            if !out.exists() {
-                std::fs::create_dir_all(out)?;
-            }
-
-            if *rust {
-                let rust_code = anaxa_builder::codegen::rust::generate_consts(&configs, &values)?;
-                std::fs::write(out.join("config.rs"), rust_code)?;
-                println!("Generated Rust constants in {:?}", out.join("config.rs"));
-            }
-
-            if *c {
-                let c_code = anaxa_builder::codegen::c::generate(&configs, &values)?;
-                std::fs::write(out.join("autoconf.h"), c_code)?;
-                println!("Generated C header in {:?}", out.join("autoconf.h"));
-            }
+            let values = load_values(config_file, &configs, cli.strict)?;
+            let values = config_io::expand_templates(&values);
 
-            if *dot {
-                let graph = anaxa_builder::graph::ConfigGraph::build(&configs)?;
-                let dot_code = anaxa_builder::codegen::dot::generate(&graph)?;
-                std::fs::write(out.join("depends.dot"), dot_code)?;
-                println!("Generated DOT graph in {:?}", out.join("depends.dot"));
+            let generated = generate_artifacts(
+                out,
+                &tree,
+                &configs,
+                &values,
+                formats,
+                *dot_values,
+                env_prefix,
+            )?;
+            if *all {
+                let hash = anaxa_builder::build_rs::inputs_hash(&configs, &values)?;
+                write_manifest(out, &generated, &hash)?;
+                println!("Generated manifest in {:?}", out.join("manifest.json"));
             }
         }
         Commands::Build {
             config_file,
             no_env,
+            env_prefix,
+            cfg_values,
+            dry_run,
             args,
         } => {
             let tree = parser::build_config_tree(dir)?;
             let configs = parser::flatten_configs(&tree);
-            let values = anaxa_builder::config_io::load_config(config_file, &configs)?;
-
-            let mut features = Vec::new();
-            let mut cfgs = Vec::new();
-            for item in &configs {
-                if let Some(val) = values.get(&item.name) {
-                    if val.as_bool() == Some(true) {
-                        if cfgs.contains(&item.name) {
-                            continue;
-                        }
-                        cfgs.push(item.name.clone());
-                        if let Some(f) = &item.feature {
-                            features.extend(f.iter().cloned());
-                        }
-                    }
-                }
-            }
+            let values = load_values(config_file, &configs, cli.strict)?;
+            let defined = config_io::defined_keys(config_file, &configs, &values)?;
+            let values = config_io::expand_templates(&values);
+            let env_prefix = (!*no_env).then_some(env_prefix.as_str());
+            let mut cmd =
+                build_command(&configs, &values, &defined, env_prefix, *cfg_values, args)?;
 
-            let mut cmd = std::process::Command::new("cargo");
-            cmd.arg("build");
-            if !features.is_empty() {
-                cmd.arg("--features");
-                cmd.arg(features.join(","));
+            if *dry_run {
+                println!("{}", describe_command(&cmd));
+                return Ok(());
             }
-            if !cfgs.is_empty() {
-                let raw_rustflags = std::env::var("RUSTFLAGS").unwrap_or_default();
-                cmd.env(
-                    "RUSTFLAGS",
-                    format!("{} --cfg {}", raw_rustflags, cfgs.join(" --cfg ")),
-                );
-            }
-            if !*no_env {
-                for (k, v) in values.iter() {
-                    let v = match v {
-                        toml::Value::String(s) => s.clone(),
-                        toml::Value::Integer(i) => i.to_string(),
-                        toml::Value::Float(f) => f.to_string(),
-                        toml::Value::Boolean(b) => b.to_string(),
-                        _ => continue,
-                    };
-                    cmd.env(format!("ANAXA_{}", k.to_uppercase()), v);
-                }
-            }
-            cmd.args(args);
-
             println!("Executing: {:?}", cmd);
             let status = cmd.status()?;
             if !status.success() {
@@ -210,17 +841,608 @@ fn main() -> Result<()> {
             let tree = parser::build_config_tree(dir)?;
             let configs = parser::flatten_configs(&tree);
             let values = anaxa_builder::config_io::load_config(config_file, &configs)?;
-            let minimal = anaxa_builder::config_io::get_minimal_config(&values, &configs);
-            anaxa_builder::config_io::save_config(out, &minimal)?;
+            let mut evaluator = anaxa_builder::evaluator::Evaluator::new();
+            for (name, val) in &values {
+                evaluator.set_variable(name, val)?;
+            }
+            evaluator.set_explicit(config_io::defined_keys(config_file, &configs, &values)?);
+            let minimal =
+                anaxa_builder::config_io::get_minimal_config(&values, &configs, &evaluator);
+            anaxa_builder::config_io::save_config(out, &tree, &minimal)?;
             println!("Saved minimal defconfig to {:?}", out);
         }
-        Commands::Defconfig { file, config_file } => {
+        Commands::Template { out } => {
+            let tree = parser::build_config_tree(dir)?;
+            let configs = parser::flatten_configs(&tree);
+            let template = codegen::template::generate(&configs)?;
+            std::fs::write(out, template).with_context(|| format!("Failed to write {:?}", out))?;
+            println!("Wrote config template to {:?}", out);
+        }
+        Commands::Defconfig {
+            file,
+            config_file,
+            prune_hidden,
+        } => {
             let tree = parser::build_config_tree(dir)?;
             let configs = parser::flatten_configs(&tree);
             let values = anaxa_builder::config_io::load_config(file, &configs)?;
-            anaxa_builder::config_io::save_config(config_file, &values)?;
+            config_io::save_config_with(config_file, &tree, &values, *prune_hidden)?;
             println!("Updated configuration from {:?} to {:?}", file, config_file);
         }
+        Commands::Verify { config_file } => {
+            let tree = parser::build_config_tree(dir)?;
+            let configs = parser::flatten_configs(&tree);
+            report_violations(config_file, &configs)?;
+            println!("{:?} is valid ({} items).", config_file, configs.len());
+        }
+        Commands::VerifyAll { configs_dir } => {
+            let tree = parser::build_config_tree(dir)?;
+            let configs = parser::flatten_configs(&tree);
+            let files = find_config_files(configs_dir)?;
+            if files.is_empty() {
+                anyhow::bail!("No config files found in {:?}", configs_dir);
+            }
+
+            let styler = term::Styler::stdout();
+            let mut failed = 0;
+            for (file, result) in files.iter().zip(verify_files(&files, &configs)) {
+                let problems = match result {
+                    Ok(violations) => violations.iter().map(|v| v.to_string()).collect(),
+                    Err(e) => vec![format!("{:#}", e)],
+                };
+                if problems.is_empty() {
+                    println!(
+                        "{} {}",
+                        styler.paint("PASS", term::Color::Green),
+                        file.display()
+                    );
+                } else {
+                    failed += 1;
+                    println!(
+                        "{} {}",
+                        styler.paint("FAIL", term::Color::Red),
+                        file.display()
+                    );
+                    for problem in problems {
+                        println!("    {}", problem);
+                    }
+                }
+            }
+
+            println!("{} passed, {} failed", files.len() - failed, failed);
+            if failed > 0 {
+                anyhow::bail!(
+                    "{} of {} config file(s) failed verification",
+                    failed,
+                    files.len()
+                );
+            }
+        }
+        Commands::Merge {
+            base,
+            fragments,
+            config_file,
+            prune_hidden,
+        } => {
+            let tree = parser::build_config_tree(dir)?;
+            let configs = parser::flatten_configs(&tree);
+            let (merged, conflicts) = config_io::merge_configs(base, fragments)?;
+            for conflict in &conflicts {
+                eprintln!(
+                    "{} '{}' = {} from {:?} overridden by {} from {:?}",
+                    term::Styler::stderr().warning(),
+                    conflict.key,
+                    conflict.previous.1,
+                    conflict.previous.0,
+                    conflict.overridden_by.1,
+                    conflict.overridden_by.0
+                );
+            }
+
+            let merged = merged.into_iter().collect();
+            let values = config_io::load_table(base, &merged, &configs, cli.strict)?;
+            config_io::save_config_with(config_file, &tree, &values, *prune_hidden)?;
+            println!(
+                "Merged {} fragment(s) onto {:?} into {:?}",
+                fragments.len(),
+                base,
+                config_file
+            );
+        }
+        Commands::Get {
+            symbol,
+            config_file,
+            json,
+        } => {
+            let tree = parser::build_config_tree(dir)?;
+            let configs = parser::flatten_configs(&tree);
+            let (item, val, explicit) = lookup_symbol(symbol, config_file, &configs)?;
+            if *json {
+                let output = serde_json::json!({
+                    "name": item.name,
+                    "type": item.config_type,
+                    "value": val,
+                    "source": if explicit { "explicit" } else { "default" },
+                });
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else {
+                println!("{}", val.map(|v| plain_value(item, &v)).unwrap_or_default());
+            }
+        }
+        Commands::Why {
+            symbol,
+            config_file,
+        } => {
+            let tree = parser::build_config_tree(dir)?;
+            let configs = parser::flatten_configs(&tree);
+            let item = configs
+                .iter()
+                .find(|i| i.name == *symbol)
+                .with_context(|| format!("Unknown config '{}'", symbol))?;
+            let values = current_values(config_file, &configs)?;
+            let mut evaluator = anaxa_builder::evaluator::Evaluator::new();
+            for (name, val) in &values {
+                evaluator.set_variable(name, val)?;
+            }
+            evaluator.set_explicit(config_io::defined_keys(config_file, &configs, &values)?);
+
+            let unmet = |expr: &Option<String>| {
+                expr.as_deref()
+                    .map(|e| evaluator.unmet_conditions(e))
+                    .unwrap_or_default()
+            };
+            let (state, reasons) = match unmet(&item.depends_on) {
+                reasons if !reasons.is_empty() => ("disabled", reasons),
+                _ => ("hidden", unmet(&item.visible_if)),
+            };
+            if reasons.is_empty() {
+                println!("'{}' has no unmet dependencies.", item.name);
+            } else {
+                println!("'{}' is {} because:", item.name, state);
+                for reason in reasons {
+                    println!("  {}", reason);
+                }
+            }
+        }
+        Commands::Set {
+            assignments,
+            config_file,
+            prune_hidden,
+        } => {
+            let tree = parser::build_config_tree(dir)?;
+            let configs = parser::flatten_configs(&tree);
+            // Groups are checked after the assignments, which may resolve a
+            // conflict in the file.
+            let mut values = config_io::load_config_unchecked(config_file, &configs, cli.strict)?;
+            apply_assignments(assignments, &configs, &mut values)?;
+            config_io::check_exclusive_groups(&configs, &values)?;
+            config_io::save_config_with(config_file, &tree, &values, *prune_hidden)?;
+            println!("Set {} value(s) in {:?}", assignments.len(), config_file);
+        }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_violations_fails_on_bad_value() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "PORT"
+type = "int"
+default = 80
+range = [1, 1024]
+desc = "Port"
+"#,
+        )?;
+        let configs = parser::flatten_configs(&parser::build_config_tree(dir.path())?);
+
+        let good = dir.path().join("good.config");
+        std::fs::write(&good, "PORT = 443\n")?;
+        assert!(report_violations(&good, &configs).is_ok());
+
+        let bad = dir.path().join("bad.config");
+        std::fs::write(&bad, "PORT = 70000\n")?;
+        let err = report_violations(&bad, &configs).unwrap_err().to_string();
+        assert!(err.contains("1 violation(s)"), "{}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_requires_a_format() {
+        let err = Formats::default().resolve(false).unwrap_err().to_string();
+        assert!(err.contains("No output format selected"), "{}", err);
+        assert!(err.contains("--all"), "{}", err);
+
+        let rust_only = Formats {
+            rust: true,
+            ..Formats::default()
+        };
+        assert_eq!(rust_only.resolve(false).unwrap(), rust_only);
+        assert_eq!(Formats::default().resolve(true).unwrap(), Formats::ALL);
+    }
+
+    #[test]
+    fn test_generate_all_writes_manifest() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "ENABLE_NET"
+type = "bool"
+default = true
+desc = "Networking"
+feature = ["net"]
+"#,
+        )?;
+        let tree = parser::build_config_tree(dir.path())?;
+        let configs = parser::flatten_configs(&tree);
+        let values = anaxa_builder::evaluator::collect_defaults(&configs);
+        let out = dir.path().join("generated");
+
+        let generated = generate_artifacts(
+            &out,
+            &tree,
+            &configs,
+            &values,
+            Formats::ALL,
+            false,
+            codegen::env::DEFAULT_PREFIX,
+        )?;
+        write_manifest(&out, &generated, "0123")?;
+
+        let expected = [
+            "config.rs",
+            "autoconf.h",
+            "depends.dot",
+            "depends.mmd",
+            "config.schema.json",
+            "config.md",
+            "features.toml",
+            "config.py",
+            "config.env",
+        ];
+        for file in expected {
+            assert!(out.join(file).exists(), "{}", file);
+        }
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out.join("manifest.json"))?)?;
+        assert_eq!(manifest["inputs_hash"], "0123");
+        let files: Vec<&str> = manifest["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["file"].as_str().unwrap())
+            .collect();
+        assert_eq!(files, expected);
+        assert_eq!(manifest["files"][0]["format"], "rust");
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_assignments() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "DEBUG"
+type = "bool"
+default = false
+desc = "Debug"
+
+[[config]]
+name = "MAX_CONN"
+type = "int"
+default = 8
+range = [1, 100]
+desc = "Max connections"
+
+[[config]]
+name = "BASE"
+type = "hex"
+default = 0
+desc = "Base"
+
+[[config]]
+name = "MODE"
+type = "choice"
+default = "fast"
+options = ["fast", "safe"]
+desc = "Mode"
+"#,
+        )?;
+        let configs = parser::flatten_configs(&parser::build_config_tree(dir.path())?);
+        let mut values = anaxa_builder::evaluator::collect_defaults(&configs);
+
+        let assignments: Vec<String> = ["DEBUG=y", "MAX_CONN=64", "BASE=0x8000", "MODE=safe"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        apply_assignments(&assignments, &configs, &mut values)?;
+        assert_eq!(values["DEBUG"], toml::Value::Boolean(true));
+        assert_eq!(values["MAX_CONN"], toml::Value::Integer(64));
+        assert_eq!(values["BASE"], toml::Value::Integer(0x8000));
+        assert_eq!(values["MODE"], toml::Value::String("safe".to_string()));
+
+        let bad: Vec<String> = ["DEBUG=n", "MAX_CONN=500", "MODE=slow", "NOPE=1", "DEBUG"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let err = apply_assignments(&bad, &configs, &mut values)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("out of range"), "{}", err);
+        assert!(err.contains("not a valid option"), "{}", err);
+        assert!(err.contains("Unknown config 'NOPE'"), "{}", err);
+        assert!(err.contains("'DEBUG' is not of the form"), "{}", err);
+        // Nothing is applied when any assignment is invalid.
+        assert_eq!(values["DEBUG"], toml::Value::Boolean(true));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lookup_symbol() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "PORT"
+type = "int"
+default = 80
+desc = "Port"
+
+[[config]]
+name = "BASE"
+type = "hex"
+default = 0x1000
+desc = "Base"
+"#,
+        )?;
+        let configs = parser::flatten_configs(&parser::build_config_tree(dir.path())?);
+        let config_file = dir.path().join(".config");
+
+        let (_, val, explicit) = lookup_symbol("PORT", &config_file, &configs)?;
+        assert_eq!(val, Some(toml::Value::Integer(80)));
+        assert!(!explicit);
+        assert!(!config_file.exists());
+
+        std::fs::write(&config_file, "PORT = 8080\n")?;
+        let (item, val, explicit) = lookup_symbol("PORT", &config_file, &configs)?;
+        assert_eq!(plain_value(item, &val.unwrap()), "8080");
+        assert!(explicit);
+
+        let (item, val, explicit) = lookup_symbol("BASE", &config_file, &configs)?;
+        assert_eq!(plain_value(item, &val.unwrap()), "0x1000");
+        assert!(!explicit);
+
+        let err = lookup_symbol("NOPE", &config_file, &configs).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown config 'NOPE'");
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_files() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "PORT"
+type = "int"
+default = 80
+desc = "Port"
+range = [1, 1024]
+"#,
+        )?;
+        let configs = parser::flatten_configs(&parser::build_config_tree(dir.path())?);
+
+        let boards = dir.path().join("boards");
+        std::fs::create_dir_all(boards.join("nested"))?;
+        std::fs::write(boards.join("good.config"), "PORT = 443\n")?;
+        std::fs::write(boards.join("nested/.config"), "PORT = 99999\n")?;
+        std::fs::write(boards.join("README.md"), "not a config")?;
+
+        let files = find_config_files(&boards)?;
+        assert_eq!(
+            files,
+            vec![boards.join("good.config"), boards.join("nested/.config")]
+        );
+
+        let results = verify_files(&files, &configs);
+        assert!(results[0].as_ref().unwrap().is_empty());
+        assert_eq!(results[1].as_ref().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_command_dry_run() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "ENABLE_NET"
+type = "bool"
+default = true
+desc = "Net"
+feature = ["net"]
+
+[[config]]
+name = "ENABLE_TLS"
+type = "bool"
+default = false
+desc = "TLS"
+feature = ["tls"]
+
+[[config]]
+name = "TLS_TICKETS"
+type = "bool"
+default = true
+desc = "Session tickets"
+depends_on = "ENABLE_TLS"
+
+[[config]]
+name = "BOARD"
+type = "string"
+default = "dev kit"
+desc = "Board"
+
+[[config]]
+name = "CHIP"
+type = "string"
+default = "esp32"
+desc = "Chip"
+"#,
+        )?;
+        let configs = parser::flatten_configs(&parser::build_config_tree(dir.path())?);
+        let values = anaxa_builder::evaluator::collect_defaults(&configs);
+
+        let args = vec!["--release".to_string()];
+        let cmd = build_command(
+            &configs,
+            &values,
+            &HashSet::new(),
+            Some("ANAXA_"),
+            false,
+            &args,
+        )?;
+        let description = describe_command(&cmd);
+        assert!(description.contains("--cfg ENABLE_NET"), "{}", description);
+        assert!(!description.contains("--cfg ENABLE_TLS"), "{}", description);
+        assert!(
+            !description.contains("--cfg TLS_TICKETS"),
+            "{}",
+            description
+        );
+        assert!(
+            description.contains("ANAXA_BOARD='dev kit'\n"),
+            "{}",
+            description
+        );
+        assert!(
+            description.ends_with("\ncargo build --features net --release"),
+            "{}",
+            description
+        );
+
+        let cmd = build_command(&configs, &values, &HashSet::new(), None, true, &[])?;
+        let description = describe_command(&cmd);
+        assert!(!description.contains("ANAXA_"), "{}", description);
+        assert!(
+            description.contains("--cfg CHIP=\"esp32\""),
+            "{}",
+            description
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compute_stats() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+[[config]]
+name = "ENABLE_NET"
+type = "bool"
+default = true
+desc = "Net"
+feature = ["net"]
+
+[[config]]
+name = "PORT"
+type = "int"
+default = 80
+desc = "Port"
+depends_on = "ENABLE_NET"
+"#,
+        )?;
+        let drivers = dir.path().join("drivers/usb");
+        std::fs::create_dir_all(&drivers)?;
+        std::fs::write(
+            drivers.join("Kconfig.toml"),
+            r#"
+depends_on = "ENABLE_NET"
+
+[[config]]
+name = "USB"
+type = "bool"
+default = false
+desc = "USB"
+feature = ["usb", "net"]
+"#,
+        )?;
+        let tree = parser::build_config_tree(dir.path())?;
+        let configs = parser::flatten_configs(&tree);
+
+        let stats = compute_stats(&tree, &configs, None, &HashSet::new())?;
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.by_type["bool"], 2);
+        assert_eq!(stats.by_type["int"], 1);
+        assert_eq!(stats.with_dependencies, 2);
+        assert_eq!(stats.max_depth, 1);
+        assert_eq!(stats.features, 2);
+        assert_eq!(stats.modified, None);
+
+        let mut values = anaxa_builder::evaluator::collect_defaults(&configs);
+        values.insert("PORT".to_string(), toml::Value::Integer(8080));
+        let stats = compute_stats(&tree, &configs, Some(&values), &HashSet::new())?;
+        assert_eq!(stats.modified, Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dump_tree_and_flat() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+title = "Root"
+
+[[config]]
+name = "ENABLE_NET"
+type = "bool"
+default = true
+desc = "Networking"
+"#,
+        )?;
+        let net = dir.path().join("net");
+        std::fs::create_dir_all(&net)?;
+        std::fs::write(
+            net.join("Kconfig.toml"),
+            r#"
+title = "Network"
+
+[[config]]
+name = "PORT"
+type = "int"
+default = 80
+desc = "Port"
+
+[[config]]
+name = "HOST"
+type = "string"
+default = "localhost"
+desc = ""
+"#,
+        )?;
+        let tree = parser::build_config_tree(dir.path())?;
+
+        assert_eq!(
+            render_tree(&tree)?,
+            "Root/\n  ENABLE_NET (bool): Networking\n  Network/\n    PORT (int): Port\n    HOST (string)\n"
+        );
+        assert_eq!(render_flat(&tree), "ENABLE_NET\nPORT\nHOST\n");
+        Ok(())
+    }
+}
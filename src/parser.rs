@@ -1,46 +1,48 @@
-use crate::schema::{ConfigItem, ConfigNode, KconfigFile};
-use anyhow::{Context, Result};
-use std::collections::BTreeMap;
+use crate::error::{AnaxaError, CycleKind};
+use crate::schema::{ConfigItem, ConfigNode, ConfigType, KconfigFile};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-/// Recursively scans the given root directory for `Kconfig.toml` files
-/// and builds a hierarchical `ConfigNode` tree.
-pub fn build_config_tree<P: AsRef<Path>>(root: P) -> Result<ConfigNode> {
-    let root_path = root.as_ref().canonicalize()?;
+/// File names recognized as Kconfig files during the directory walk.
+pub const KCONFIG_FILE_NAMES: [&str; 3] = ["Kconfig.toml", "Kconfig.yaml", "Kconfig.json"];
+
+/// Returns whether `name` is one of [`KCONFIG_FILE_NAMES`].
+pub fn is_kconfig_file_name(name: &std::ffi::OsStr) -> bool {
+    KCONFIG_FILE_NAMES.iter().any(|n| name == *n)
+}
+
+/// Recursively scans the given root directory for Kconfig files
+/// (TOML, YAML or JSON) and builds a hierarchical `ConfigNode` tree.
+pub fn build_config_tree<P: AsRef<Path>>(root: P) -> Result<ConfigNode, AnaxaError> {
+    let root_path = root
+        .as_ref()
+        .canonicalize()
+        .map_err(|e| AnaxaError::io(root.as_ref(), e))?;
     let mut nodes: BTreeMap<PathBuf, ConfigNode> = BTreeMap::new();
+    // Canonical paths of the files loaded so far, so that a file that is
+    // both sourced and found by the walk, or sourced twice, is parsed once.
+    let mut loaded = HashSet::new();
 
     for entry in WalkDir::new(&root_path)
         .follow_links(true)
         .into_iter()
         .filter_map(|e| e.ok())
     {
-        if entry.file_name() == "Kconfig.toml" {
+        if is_kconfig_file_name(entry.file_name()) {
             let path = entry.path();
-            let rel_path = path.parent().unwrap().strip_prefix(&root_path)?;
-
-            let content = fs::read_to_string(path)
-                .with_context(|| format!("Failed to read config file: {:?}", path))?;
-
-            let kconfig: KconfigFile = toml::from_str(&content)
-                .with_context(|| format!("Failed to parse TOML structure in: {:?}", path))?;
-
-            let desc = kconfig
-                .title
-                .clone()
-                .unwrap_or_else(|| rel_path.to_string_lossy().into_owned());
-
-            nodes.insert(
-                rel_path.to_path_buf(),
-                ConfigNode {
-                    desc,
-                    configs: kconfig.configs.unwrap_or_default(),
-                    children: Vec::new(),
-                    path: rel_path.to_string_lossy().into_owned(),
-                    depends_on: kconfig.depends_on.clone(),
-                },
-            );
+            let dir = path.parent().unwrap();
+            let rel_path = dir
+                .strip_prefix(&root_path)
+                .expect("the walk only yields paths under the root");
+            if nodes.contains_key(rel_path) {
+                return Err(AnaxaError::parse(dir, "multiple Kconfig files found"));
+            }
+            if let Some(node) = load_kconfig_file(path, &root_path, &mut Vec::new(), &mut loaded)? {
+                nodes.insert(rel_path.to_path_buf(), node);
+            }
         }
     }
 
@@ -79,16 +81,371 @@ pub fn build_config_tree<P: AsRef<Path>>(root: P) -> Result<ConfigNode> {
 
     nodes
         .remove(&PathBuf::new())
-        .context("No root Kconfig.toml found in the root directory")
+        .ok_or_else(|| AnaxaError::parse(&root_path, "No root Kconfig.toml found"))
+}
+
+/// Deserializes a Kconfig file, choosing the format from its extension.
+/// Anything that isn't `.yaml`/`.yml`/`.json` is treated as TOML.
+fn parse_kconfig(path: &Path, content: &str) -> Result<KconfigFile, AnaxaError> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml") | Some("yml") => {
+            serde_yaml_ng::from_str(content).map_err(|e| AnaxaError::parse(path, e))
+        }
+        Some("json") => serde_json::from_str(content).map_err(|e| AnaxaError::parse(path, e)),
+        _ => toml::from_str(content).map_err(|e| AnaxaError::parse(path, e)),
+    }
 }
 
-/// Helper to flatten the hierarchical tree into a flat list of items
+/// Parses a single Kconfig file into a node, recursively attaching the files
+/// listed in its `source` key as children. `stack` holds the files currently
+/// being loaded and is used to reject include cycles. Returns `None` for
+/// files already in `loaded`.
+fn load_kconfig_file(
+    path: &Path,
+    root_path: &Path,
+    stack: &mut Vec<PathBuf>,
+    loaded: &mut HashSet<PathBuf>,
+) -> Result<Option<ConfigNode>, AnaxaError> {
+    let canonical = path.canonicalize().map_err(|e| AnaxaError::io(path, e))?;
+    if stack.contains(&canonical) {
+        return Err(AnaxaError::Cycle {
+            kind: CycleKind::Include,
+            path: stack
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect(),
+        });
+    }
+
+    if !loaded.insert(canonical.clone()) {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| AnaxaError::io(path, e))?;
+
+    let mut kconfig = parse_kconfig(path, &content)?;
+
+    let configs = kconfig.configs.as_deref().unwrap_or_default();
+    validate_names(configs).map_err(|e| e.in_file("Invalid config name", path))?;
+    validate_choices(configs).map_err(|e| e.in_file("Invalid choice", path))?;
+    validate_ranges(configs).map_err(|e| e.in_file("Invalid range", path))?;
+    validate_defaults(configs).map_err(|e| e.in_file("Invalid default value", path))?;
+    validate_exclusive_group(&kconfig).map_err(|e| e.in_file("Invalid exclusive_group", path))?;
+
+    for item in kconfig.configs.iter_mut().flatten() {
+        item.default = item.default.take().map(|d| item.normalize(d));
+        let mut default_if = item.default_if.take();
+        for conditional in default_if.iter_mut().flatten() {
+            conditional.value = item.normalize(conditional.value.clone());
+        }
+        item.default_if = default_if;
+    }
+
+    let dir = canonical.parent().unwrap_or(Path::new(""));
+    let rel_path = dir
+        .strip_prefix(root_path)
+        .unwrap_or(dir)
+        .to_string_lossy()
+        .into_owned();
+
+    let mut children = Vec::new();
+    stack.push(canonical.clone());
+    for source in kconfig.source.iter().flatten() {
+        children.extend(load_kconfig_file(
+            &dir.join(source),
+            root_path,
+            stack,
+            loaded,
+        )?);
+    }
+    stack.pop();
+
+    Ok(Some(ConfigNode {
+        desc: kconfig.title.unwrap_or_else(|| rel_path.clone()),
+        configs: kconfig.configs.unwrap_or_default(),
+        children,
+        path: rel_path,
+        depends_on: kconfig.depends_on,
+        help: kconfig.help,
+        exclusive_group: kconfig.exclusive_group,
+        file: canonical
+            .strip_prefix(root_path)
+            .unwrap_or(&canonical)
+            .to_string_lossy()
+            .into_owned(),
+    }))
+}
+
+/// Helper to flatten the hierarchical tree into a flat list of items. Each
+/// item's `depends_on` is ANDed with those of its enclosing menus, so a
+/// symbol in a disabled menu counts as disabled everywhere, and members of
+/// a menu's `exclusive_group` carry the group.
 pub fn flatten_configs(node: &ConfigNode) -> Vec<ConfigItem> {
-    let mut all_configs = node.configs.clone();
+    let mut all_configs = Vec::new();
+    flatten_into(node, None, &mut all_configs);
+    all_configs
+}
+
+fn flatten_into(node: &ConfigNode, inherited: Option<&str>, out: &mut Vec<ConfigItem>) {
+    let menu_dep = and_expressions(inherited, node.depends_on.as_deref());
+    for item in &node.configs {
+        let mut item = item.clone();
+        item.depends_on = and_expressions(menu_dep.as_deref(), item.depends_on.as_deref());
+        item.exclusive_group = node
+            .exclusive_group
+            .clone()
+            .filter(|group| group.contains(&item.name));
+        out.push(item);
+    }
     for child in &node.children {
-        all_configs.extend(flatten_configs(child));
+        flatten_into(child, menu_dep.as_deref(), out);
     }
-    all_configs
+}
+
+/// `(a) && (b)`, or whichever of the two is present and non-empty.
+fn and_expressions(a: Option<&str>, b: Option<&str>) -> Option<String> {
+    let non_empty = |e: &&str| !e.trim().is_empty();
+    match (a.filter(non_empty), b.filter(non_empty)) {
+        (Some(a), Some(b)) => Some(format!("({}) && ({})", a, b)),
+        (a, b) => a.or(b).map(String::from),
+    }
+}
+
+/// Returns whether `name` is a valid symbol name (`^[A-Z][A-Z0-9_]*$`).
+/// Names become Rust cfgs and C macros, so they must be valid identifiers.
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Ensures every config name matches `^[A-Z][A-Z0-9_]*$`.
+pub fn validate_names(configs: &[ConfigItem]) -> Result<(), AnaxaError> {
+    for item in configs {
+        if !is_valid_name(&item.name) {
+            return Err(AnaxaError::validation(
+                &item.name,
+                format!("Config name '{}' must match ^[A-Z][A-Z0-9_]*$", item.name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Ensures every choice has at least one option, and that its
+/// `option_values` are scalars of a single type. Whether a choice's default
+/// is one of its options is checked by [`validate_defaults`].
+pub fn validate_choices(configs: &[ConfigItem]) -> Result<(), AnaxaError> {
+    for item in configs {
+        if item.config_type == ConfigType::Choice && item.option_values().is_empty() {
+            return Err(AnaxaError::validation(
+                &item.name,
+                format!("Config '{}' is a choice but has no options", item.name),
+            ));
+        }
+        let mapped = item.option_values.as_deref().unwrap_or_default();
+        if let Some(bad) = mapped.iter().find(|v| {
+            !matches!(
+                v,
+                toml::Value::String(_)
+                    | toml::Value::Integer(_)
+                    | toml::Value::Float(_)
+                    | toml::Value::Boolean(_)
+            )
+        }) {
+            return Err(AnaxaError::validation(
+                &item.name,
+                format!(
+                    "Config '{}' has an option_value of unsupported type {}",
+                    item.name,
+                    bad.type_str()
+                ),
+            ));
+        }
+        if let Some(first) = mapped.first() {
+            if let Some(other) = mapped.iter().find(|v| v.type_str() != first.type_str()) {
+                return Err(AnaxaError::validation(
+                    &item.name,
+                    format!(
+                        "Config '{}' mixes option_values of type {} and {}",
+                        item.name,
+                        first.type_str(),
+                        other.type_str()
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Ensures hex symbols have no negative `range` bound. Hex values are
+/// unsigned, so such a range would reject every value.
+pub fn validate_ranges(configs: &[ConfigItem]) -> Result<(), AnaxaError> {
+    for item in configs {
+        if let (ConfigType::Hex, Some((min, max))) = (&item.config_type, item.range) {
+            if min < 0 || max < 0 {
+                return Err(AnaxaError::validation(
+                    &item.name,
+                    format!(
+                        "Config '{}' is hex but has a negative range bound [{}, {}]",
+                        item.name, min, max
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Ensures every `default` and `default_if` value passes its symbol's own
+/// validation (type, range, regex, options), so an invalid default can't
+/// reach generated code. Values are checked as written, before
+/// normalization.
+pub fn validate_defaults(configs: &[ConfigItem]) -> Result<(), AnaxaError> {
+    for item in configs {
+        let conditional = item.default_if.iter().flatten().map(|d| &d.value);
+        for default in item.default.iter().chain(conditional) {
+            item.validate(default)
+                .map_err(|reason| AnaxaError::validation(&item.name, reason))?;
+        }
+    }
+    Ok(())
+}
+
+/// Names of the non-optional choices with no default of any kind. They start
+/// out unset, so every config file has to pick an option for them.
+pub fn choices_without_default(configs: &[ConfigItem]) -> Vec<&str> {
+    configs
+        .iter()
+        .filter(|item| {
+            item.config_type == ConfigType::Choice
+                && !item.optional
+                && item.default.is_none()
+                && item.default_if.is_none()
+                && item.default_env.is_none()
+        })
+        .map(|item| item.name.as_str())
+        .collect()
+}
+
+/// Ensures every member of the file's `exclusive_group` is one of its own bool
+/// configs.
+fn validate_exclusive_group(kconfig: &KconfigFile) -> Result<(), AnaxaError> {
+    let configs = kconfig.configs.as_deref().unwrap_or_default();
+    let mut enabled = Vec::new();
+    for member in kconfig.exclusive_group.iter().flatten() {
+        match configs.iter().find(|c| c.name == *member) {
+            Some(item) if item.config_type == ConfigType::Bool => {
+                if item.default.as_ref().and_then(|d| d.as_bool()) == Some(true) {
+                    enabled.push(member.as_str());
+                }
+            }
+            Some(_) => {
+                return Err(AnaxaError::validation(
+                    member,
+                    format!("'{}' is not a bool config", member),
+                ))
+            }
+            None => {
+                return Err(AnaxaError::validation(
+                    member,
+                    format!("'{}' is not defined in this file", member),
+                ))
+            }
+        }
+    }
+    if enabled.len() > 1 {
+        return Err(AnaxaError::validation(
+            enabled[1],
+            format!(
+                "more than one member enabled by default: {}",
+                enabled.join(", ")
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the `exclusive_group` containing `name`, if any.
+pub fn exclusive_group_of<'a>(node: &'a ConfigNode, name: &str) -> Option<&'a [String]> {
+    if let Some(group) = node.exclusive_group.as_deref() {
+        if group.iter().any(|m| m == name) {
+            return Some(group);
+        }
+    }
+    node.children
+        .iter()
+        .find_map(|child| exclusive_group_of(child, name))
+}
+
+/// Ensures no config name is defined more than once across the tree.
+pub fn check_duplicates(node: &ConfigNode) -> Result<(), AnaxaError> {
+    let mut seen: HashMap<String, String> = HashMap::new();
+    check_duplicates_in(node, &mut seen)
+}
+
+fn check_duplicates_in(
+    node: &ConfigNode,
+    seen: &mut HashMap<String, String>,
+) -> Result<(), AnaxaError> {
+    let source = source_file(node);
+    for item in &node.configs {
+        if let Some(first) = seen.insert(item.name.clone(), source.clone()) {
+            return Err(AnaxaError::validation(
+                &item.name,
+                format!(
+                    "Duplicate config '{}' defined in '{}' and '{}'",
+                    item.name, first, source
+                ),
+            ));
+        }
+    }
+    for child in &node.children {
+        check_duplicates_in(child, seen)?;
+    }
+    Ok(())
+}
+
+/// Rejects menus whose `depends_on` references a symbol defined inside the
+/// menu itself or one of its submenus. Such a symbol is hidden along with its
+/// own menu, so it can never be turned on to reveal it.
+pub fn check_shadowing(node: &ConfigNode) -> Result<(), AnaxaError> {
+    if let Some(expr) = &node.depends_on {
+        for var in crate::graph::extract_variables(expr) {
+            if let Some(defined_in) = find_definition(node, &var) {
+                return Err(AnaxaError::validation(
+                    &var,
+                    format!(
+                        "Menu '{}' depends on '{}', which is defined inside it in '{}'",
+                        source_file(node),
+                        var,
+                        defined_in
+                    ),
+                ));
+            }
+        }
+    }
+    for child in &node.children {
+        check_shadowing(child)?;
+    }
+    Ok(())
+}
+
+/// Source file of the node in `node`'s subtree that defines `name`.
+fn find_definition(node: &ConfigNode, name: &str) -> Option<String> {
+    if node.configs.iter().any(|c| c.name == name) {
+        return Some(source_file(node));
+    }
+    node.children
+        .iter()
+        .find_map(|child| find_definition(child, name))
+}
+
+/// The schema file a node was parsed from, as shown in error messages.
+fn source_file(node: &ConfigNode) -> String {
+    node.file.clone()
 }
 
 /// Legacy function for compatibility, if needed
@@ -100,35 +457,18 @@ pub fn parse_kconfigs<P: AsRef<Path>>(root: P) -> Result<Vec<ConfigItem>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::schema::ConfigType;
     use std::fs;
     use tempfile::tempdir;
 
     #[test]
     fn test_flatten_configs() {
         let item1 = ConfigItem {
-            name: "A".to_string(),
-            config_type: ConfigType::Bool,
-            default: None,
             desc: "A".to_string(),
-            depends_on: None,
-            help: None,
-            options: None,
-            feature: None,
-            range: None,
-            regex: None,
+            ..ConfigItem::test_item("A", ConfigType::Bool)
         };
         let item2 = ConfigItem {
-            name: "B".to_string(),
-            config_type: ConfigType::Bool,
-            default: None,
             desc: "B".to_string(),
-            depends_on: None,
-            help: None,
-            options: None,
-            feature: None,
-            range: None,
-            regex: None,
+            ..ConfigItem::test_item("B", ConfigType::Bool)
         };
 
         let root = ConfigNode {
@@ -140,9 +480,15 @@ mod tests {
                 children: Vec::new(),
                 path: "child".to_string(),
                 depends_on: None,
+                help: None,
+                exclusive_group: None,
+                file: String::new(),
             }],
             path: "".to_string(),
             depends_on: None,
+            help: None,
+            exclusive_group: None,
+            file: String::new(),
         };
 
         let flattened = flatten_configs(&root);
@@ -170,6 +516,7 @@ mod tests {
         fs::create_dir(&sub_path)?;
         let sub_kconfig = r#"
             title = "Sub"
+            help = "Options of the sub system"
             [[config]]
             name = "SUB_OPT"
             type = "bool"
@@ -185,9 +532,542 @@ mod tests {
         assert_eq!(tree.configs[0].name, "ROOT_OPT");
         assert_eq!(tree.children.len(), 1);
         assert_eq!(tree.children[0].desc, "Sub");
+        assert_eq!(tree.help, None);
+        assert_eq!(
+            tree.children[0].help.as_deref(),
+            Some("Options of the sub system")
+        );
         assert_eq!(tree.children[0].configs.len(), 1);
         assert_eq!(tree.children[0].configs[0].name, "SUB_OPT");
 
         Ok(())
     }
+
+    #[test]
+    fn test_config_tree_json_roundtrip() -> Result<()> {
+        let dir = tempdir()?;
+        let root_path = dir.path();
+
+        fs::write(
+            root_path.join("Kconfig.toml"),
+            r#"
+            title = "Root"
+            [[config]]
+            name = "ROOT_OPT"
+            type = "int"
+            default = 4
+            range = [1, 8]
+            desc = "Root option"
+        "#,
+        )?;
+        for sub in ["beta", "alpha"] {
+            let sub_path = root_path.join(sub);
+            fs::create_dir(&sub_path)?;
+            fs::write(
+                sub_path.join("Kconfig.toml"),
+                format!("title = \"{}\"\n", sub),
+            )?;
+        }
+
+        let tree = build_config_tree(root_path)?;
+        let json = serde_json::to_string_pretty(&tree)?;
+        let parsed: ConfigNode = serde_json::from_str(&json)?;
+
+        assert_eq!(parsed.desc, "Root");
+        assert_eq!(parsed.configs.len(), 1);
+        assert_eq!(parsed.configs[0].name, "ROOT_OPT");
+        assert_eq!(parsed.configs[0].config_type, ConfigType::Int);
+        assert_eq!(parsed.configs[0].range, Some((1, 8)));
+        let children: Vec<_> = parsed.children.iter().map(|c| c.desc.as_str()).collect();
+        assert_eq!(children, vec!["alpha", "beta"]);
+        assert_eq!(serde_json::to_string_pretty(&parsed)?, json);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_duplicates() -> Result<()> {
+        let dir = tempdir()?;
+        let root_path = dir.path();
+        let kconfig = r#"
+            [[config]]
+            name = "DEBUG"
+            type = "bool"
+            default = false
+            desc = "Debug"
+        "#;
+        fs::write(root_path.join("Kconfig.toml"), "title = \"Root\"\n")?;
+        for sub in ["a", "b"] {
+            fs::create_dir(root_path.join(sub))?;
+            fs::write(root_path.join(sub).join("Kconfig.toml"), kconfig)?;
+        }
+
+        let tree = build_config_tree(root_path)?;
+        let err = check_duplicates(&tree).unwrap_err();
+        assert!(matches!(&err, AnaxaError::Validation { symbol, .. } if symbol == "DEBUG"));
+        let err = err.to_string();
+        assert!(err.contains("DEBUG"));
+        assert!(err.contains("a/Kconfig.toml"));
+        assert!(err.contains("b/Kconfig.toml"));
+
+        fs::remove_dir_all(root_path.join("b"))?;
+        let tree = build_config_tree(root_path)?;
+        assert!(check_duplicates(&tree).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_shadowing() -> Result<()> {
+        let dir = tempdir()?;
+        let root_path = dir.path();
+        fs::write(
+            root_path.join("Kconfig.toml"),
+            r#"
+            title = "Root"
+            [[config]]
+            name = "NET"
+            type = "bool"
+            default = true
+            desc = "Networking"
+        "#,
+        )?;
+        fs::create_dir_all(root_path.join("drivers/usb"))?;
+        fs::write(
+            root_path.join("drivers/Kconfig.toml"),
+            "title = \"Drivers\"\ndepends_on = \"NET && USB\"\n",
+        )?;
+        fs::write(
+            root_path.join("drivers/usb/Kconfig.toml"),
+            r#"
+            title = "USB"
+            [[config]]
+            name = "USB"
+            type = "bool"
+            default = false
+            desc = "USB"
+        "#,
+        )?;
+
+        let tree = build_config_tree(root_path)?;
+        let err = check_shadowing(&tree).unwrap_err();
+        assert!(matches!(&err, AnaxaError::Validation { symbol, .. } if symbol == "USB"));
+        let err = err.to_string();
+        assert!(err.contains("'USB'"), "{}", err);
+        assert!(err.contains("drivers/Kconfig.toml"), "{}", err);
+        assert!(err.contains("drivers/usb/Kconfig.toml"), "{}", err);
+
+        fs::write(
+            root_path.join("drivers/Kconfig.toml"),
+            "title = \"Drivers\"\ndepends_on = \"NET\"\n",
+        )?;
+        let tree = build_config_tree(root_path)?;
+        assert!(check_shadowing(&tree).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_valid_name() {
+        assert!(is_valid_name("A"));
+        assert!(is_valid_name("ENABLE_NET"));
+        assert!(is_valid_name("MAX_SOCKETS_2"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("1ST_OPTION"));
+        assert!(!is_valid_name("_PRIVATE"));
+        assert!(!is_valid_name("enable_net"));
+        assert!(!is_valid_name("ENABLE-NET"));
+    }
+
+    #[test]
+    fn test_build_config_tree_rejects_invalid_name() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+            [[config]]
+            name = "2FAST"
+            type = "bool"
+            desc = "Bad"
+        "#,
+        )?;
+
+        let err = format!("{:#}", build_config_tree(dir.path()).unwrap_err());
+        assert!(err.contains("2FAST"));
+        assert!(err.contains("Kconfig.toml"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_menu_depends_on_applies_to_its_symbols() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("Kconfig.toml"),
+            "[[config]]\nname = \"ENABLE_NET\"\ntype = \"bool\"\ndefault = false\ndesc = \"Net\"\n",
+        )?;
+        let net = dir.path().join("net");
+        fs::create_dir(&net)?;
+        fs::write(
+            net.join("Kconfig.toml"),
+            r#"
+            depends_on = "ENABLE_NET"
+
+            [[config]]
+            name = "SOCKETS"
+            type = "bool"
+            default = true
+            desc = "Sockets"
+
+            [[config]]
+            name = "IPV6"
+            type = "bool"
+            default = false
+            desc = "IPv6"
+            depends_on = "SOCKETS"
+        "#,
+        )?;
+
+        let configs = flatten_configs(&build_config_tree(dir.path())?);
+        let find = |name: &str| configs.iter().find(|c| c.name == name).unwrap();
+        assert_eq!(find("ENABLE_NET").depends_on, None);
+        assert_eq!(find("SOCKETS").depends_on.as_deref(), Some("ENABLE_NET"));
+        assert_eq!(
+            find("IPV6").depends_on.as_deref(),
+            Some("(ENABLE_NET) && (SOCKETS)")
+        );
+
+        let graph = crate::graph::ConfigGraph::build(&configs)?;
+        assert!(graph.dependents("ENABLE_NET").contains(&"SOCKETS"));
+
+        let config_path = dir.path().join(".config");
+        fs::write(&config_path, "ENABLE_NET = false\nSOCKETS = true\n")?;
+        let violations = crate::config_io::verify_config(&config_path, &configs)?;
+        assert_eq!(
+            violations,
+            vec![crate::config_io::Violation::UnmetDependency {
+                key: "SOCKETS".to_string(),
+                depends_on: "ENABLE_NET".to_string(),
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_choice_default_must_be_an_option() -> Result<()> {
+        let dir = tempdir()?;
+        let kconfig = dir.path().join("Kconfig.toml");
+        let choice = |default: &str| {
+            format!(
+                "[[config]]\nname = \"SCHED\"\ntype = \"choice\"\n\
+                 options = [\"rr\", \"fifo\"]\n{}desc = \"Scheduler\"\n",
+                default
+            )
+        };
+
+        fs::write(&kconfig, choice("default = \"fifo\"\n"))?;
+        let configs = flatten_configs(&build_config_tree(dir.path())?);
+        let defaults = crate::evaluator::collect_defaults(&configs);
+        assert_eq!(defaults["SCHED"].as_str(), Some("fifo"));
+        assert!(choices_without_default(&configs).is_empty());
+
+        fs::write(&kconfig, choice("default = \"lifo\"\n"))?;
+        let err = build_config_tree(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("lifo"), "{}", err);
+        assert!(
+            matches!(&err, AnaxaError::Validation { symbol, .. } if symbol == "SCHED"),
+            "{:?}",
+            err
+        );
+
+        fs::write(&kconfig, choice(""))?;
+        let configs = flatten_configs(&build_config_tree(dir.path())?);
+        assert_eq!(choices_without_default(&configs), vec!["SCHED"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_choice_without_options_is_rejected() -> Result<()> {
+        let dir = tempdir()?;
+        let kconfig = dir.path().join("Kconfig.toml");
+        for options in ["", "options = []\n"] {
+            fs::write(
+                &kconfig,
+                format!(
+                    "[[config]]\nname = \"SCHED\"\ntype = \"choice\"\n{}desc = \"Scheduler\"\n",
+                    options
+                ),
+            )?;
+            let err = build_config_tree(dir.path()).unwrap_err();
+            let message = err.to_string();
+            assert!(message.contains("has no options"), "{}", message);
+            assert!(message.contains("Kconfig.toml"), "{}", message);
+            assert!(
+                matches!(&err, AnaxaError::Validation { symbol, .. } if symbol == "SCHED"),
+                "{:?}",
+                err
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_mixed_option_values_are_rejected() -> Result<()> {
+        let dir = tempdir()?;
+        let kconfig = dir.path().join("Kconfig.toml");
+        for (values, expected) in [
+            (
+                "[1, \"x\"]",
+                "mixes option_values of type integer and string",
+            ),
+            ("[[1], [2]]", "unsupported type array"),
+        ] {
+            fs::write(
+                &kconfig,
+                format!(
+                    "[[config]]\nname = \"MODE\"\ntype = \"choice\"\noptions = [\"a\", \"b\"]\noption_values = {}\ndesc = \"Mode\"\n",
+                    values
+                ),
+            )?;
+            let message = build_config_tree(dir.path()).unwrap_err().to_string();
+            assert!(message.contains(expected), "{}", message);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_config_tree_rejects_invalid_default() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("Kconfig.toml"),
+            r#"
+            [[config]]
+            name = "MAX_CONN"
+            type = "int"
+            default = 500
+            range = [1, 100]
+            desc = "Max connections"
+        "#,
+        )?;
+
+        let err = build_config_tree(dir.path()).unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(message.contains("Invalid default value in"), "{}", message);
+        assert!(message.contains("Kconfig.toml"), "{}", message);
+        assert!(message.contains("MAX_CONN"), "{}", message);
+        assert!(
+            matches!(&err, AnaxaError::Validation { symbol, .. } if symbol == "MAX_CONN"),
+            "{:?}",
+            err
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hex_rejects_negative_values() -> Result<()> {
+        let dir = tempdir()?;
+        let kconfig = dir.path().join("Kconfig.toml");
+        for (fields, expected) in [
+            ("default = 0\nrange = [-1, 16]", "Invalid range in"),
+            ("default = -1", "Invalid default value in"),
+        ] {
+            fs::write(
+                &kconfig,
+                format!(
+                    "[[config]]\nname = \"MASK\"\ntype = \"hex\"\n{}\ndesc = \"Mask\"\n",
+                    fields
+                ),
+            )?;
+            let message = build_config_tree(dir.path()).unwrap_err().to_string();
+            assert!(message.contains(expected), "{}", message);
+        }
+
+        fs::write(
+            &kconfig,
+            "[[config]]\nname = \"MASK\"\ntype = \"hex\"\ndefault = \"0xffffffffffffffff\"\ndesc = \"Mask\"\n",
+        )?;
+        let tree = build_config_tree(dir.path())?;
+        assert_eq!(tree.configs[0].default, Some(toml::Value::Integer(-1)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_config_tree_error_kinds() -> Result<()> {
+        let dir = tempdir()?;
+        let err = build_config_tree(dir.path().join("missing")).unwrap_err();
+        assert!(matches!(err, AnaxaError::Io { .. }), "{:?}", err);
+
+        let err = build_config_tree(dir.path()).unwrap_err();
+        assert!(matches!(err, AnaxaError::Parse { .. }), "{:?}", err);
+
+        let kconfig = dir.path().join("Kconfig.toml");
+        fs::write(&kconfig, "[[config]]\nname = \"A\"\ntype = \"bool\"\n")?;
+        match build_config_tree(dir.path()).unwrap_err() {
+            AnaxaError::Parse { path, .. } => assert!(path.ends_with("Kconfig.toml")),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_includes_sibling_file() -> Result<()> {
+        let dir = tempdir()?;
+        let src = dir.path().join("src");
+        let shared = dir.path().join("shared");
+        fs::create_dir(&src)?;
+        fs::create_dir(&shared)?;
+
+        fs::write(
+            src.join("Kconfig.toml"),
+            r#"
+            title = "Root"
+            source = ["../shared/common.toml"]
+        "#,
+        )?;
+        fs::write(
+            shared.join("common.toml"),
+            r#"
+            title = "Shared"
+            [[config]]
+            name = "SHARED_OPT"
+            type = "bool"
+            default = true
+            desc = "Shared option"
+        "#,
+        )?;
+
+        let tree = build_config_tree(&src)?;
+        assert_eq!(tree.children.len(), 1);
+        assert_eq!(tree.children[0].desc, "Shared");
+        assert_eq!(tree.children[0].configs[0].name, "SHARED_OPT");
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_loads_each_file_once() -> Result<()> {
+        let dir = tempdir()?;
+        let src = dir.path().join("src");
+        let drivers = src.join("drivers");
+        let shared = dir.path().join("shared");
+        fs::create_dir_all(&drivers)?;
+        fs::create_dir(&shared)?;
+
+        fs::write(
+            src.join("Kconfig.toml"),
+            r#"
+            source = ["drivers/Kconfig.toml", "../shared/common.toml", "../shared/common.toml"]
+        "#,
+        )?;
+        let item = |name: &str| {
+            format!(
+                "[[config]]\nname = \"{}\"\ntype = \"bool\"\ndesc = \"\"\n",
+                name
+            )
+        };
+        fs::write(drivers.join("Kconfig.toml"), item("USB"))?;
+        fs::write(shared.join("common.toml"), item("SHARED_OPT"))?;
+
+        let tree = build_config_tree(&src)?;
+        check_duplicates(&tree)?;
+        let names: Vec<String> = flatten_configs(&tree).into_iter().map(|i| i.name).collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"USB".to_string()));
+        assert!(names.contains(&"SHARED_OPT".to_string()));
+
+        // A file from outside the root is recorded under its real path.
+        let shared_node = tree
+            .children
+            .iter()
+            .find(|c| c.configs[0].name == "SHARED_OPT")
+            .unwrap();
+        assert_eq!(
+            Path::new(&shared_node.file),
+            shared.canonicalize()?.join("common.toml")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_cycle_detection() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("Kconfig.toml"), "source = [\"a.toml\"]\n")?;
+        fs::write(dir.path().join("a.toml"), "source = [\"b.toml\"]\n")?;
+        fs::write(dir.path().join("b.toml"), "source = [\"a.toml\"]\n")?;
+
+        let err = build_config_tree(dir.path()).unwrap_err();
+        assert!(format!("{:#}", err).contains("Include cycle detected"));
+        match err {
+            AnaxaError::Cycle { kind, path } => {
+                assert_eq!(kind, CycleKind::Include);
+                assert_eq!(path.len(), 4);
+                assert!(path[1].ends_with("a.toml"));
+                assert_eq!(path[1], path[3]);
+            }
+            other => panic!("expected an include cycle, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_yaml_and_json_kconfig() -> Result<()> {
+        let toml_dir = tempdir()?;
+        fs::write(
+            toml_dir.path().join("Kconfig.toml"),
+            r#"
+            title = "Root"
+            [[config]]
+            name = "PORT"
+            type = "int"
+            default = 80
+            range = [1, 65535]
+            desc = "Port"
+        "#,
+        )?;
+
+        let mixed_dir = tempdir()?;
+        fs::write(
+            mixed_dir.path().join("Kconfig.yaml"),
+            r#"
+title: Root
+config:
+  - name: PORT
+    type: int
+    default: 80
+    range: [1, 65535]
+    desc: Port
+"#,
+        )?;
+        let sub = mixed_dir.path().join("sub");
+        fs::create_dir(&sub)?;
+        fs::write(
+            sub.join("Kconfig.json"),
+            r#"{"title": "Sub", "config": [{"name": "FLAG", "type": "bool", "default": true, "desc": "Flag"}]}"#,
+        )?;
+
+        let from_toml = build_config_tree(toml_dir.path())?;
+        let from_yaml = build_config_tree(mixed_dir.path())?;
+
+        assert_eq!(from_yaml.desc, from_toml.desc);
+        assert_eq!(
+            serde_json::to_value(&from_yaml.configs)?,
+            serde_json::to_value(&from_toml.configs)?
+        );
+        assert_eq!(from_yaml.children.len(), 1);
+        assert_eq!(from_yaml.children[0].desc, "Sub");
+        assert_eq!(
+            from_yaml.children[0].configs[0].default,
+            Some(toml::Value::Boolean(true))
+        );
+
+        // Errors name the file the symbol actually comes from.
+        assert_eq!(from_yaml.file, "Kconfig.yaml");
+        assert_eq!(from_yaml.children[0].file, "sub/Kconfig.json");
+        fs::write(
+            sub.join("Kconfig.json"),
+            r#"{"config": [{"name": "PORT", "type": "int", "default": 1, "desc": "Port"}]}"#,
+        )?;
+        let err = check_duplicates(&build_config_tree(mixed_dir.path())?).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Duplicate config 'PORT' defined in 'Kconfig.yaml' and 'sub/Kconfig.json'"
+        );
+        Ok(())
+    }
 }
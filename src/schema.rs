@@ -1,4 +1,6 @@
+use crate::evaluator::Evaluator;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -6,9 +8,14 @@ use std::fmt;
 pub enum ConfigType {
     Bool,
     Int,
+    /// An unsigned 64-bit value. Since TOML integers are signed, it is stored
+    /// as the `i64` with the same bit pattern, see [`hex_value`].
     Hex,
+    Float,
     String,
     Choice,
+    /// A list of strings, e.g. allowed origins.
+    StringList,
 }
 
 impl ConfigType {
@@ -18,8 +25,13 @@ impl ConfigType {
                 .as_bool()
                 .map(|b| if b { "1".into() } else { "0".into() }),
             ConfigType::Int => val.as_integer().map(|i| i.to_string()),
-            ConfigType::Hex => val.as_integer().map(|i| format!("0x{:x}", i)),
-            ConfigType::String | ConfigType::Choice => val.as_str().map(|s| format!("\"{}\"", s)),
+            ConfigType::Hex => hex_value(val).map(|u| format!("0x{:x}", u)),
+            ConfigType::Float => as_float(val).map(|f| format!("{:?}", f)),
+            ConfigType::String => val.as_str().map(quote),
+            ConfigType::Choice => format_choice_value(val, |b| if b { "1" } else { "0" }),
+            ConfigType::StringList => {
+                string_list(val).map(|items| format!("{{{}}}", quote_all(&items)))
+            }
         }
     }
 
@@ -27,8 +39,13 @@ impl ConfigType {
         match self {
             ConfigType::Bool => val.as_bool().map(|b| b.to_string()),
             ConfigType::Int => val.as_integer().map(|i| i.to_string()),
-            ConfigType::Hex => val.as_integer().map(|i| format!("0x{:x}", i)),
-            ConfigType::String | ConfigType::Choice => val.as_str().map(|s| format!("\"{}\"", s)),
+            ConfigType::Hex => hex_value(val).map(|u| format!("0x{:x}", u)),
+            ConfigType::Float => as_float(val).map(|f| format!("{:?}", f)),
+            ConfigType::String => val.as_str().map(quote),
+            ConfigType::Choice => format_choice_value(val, |b| if b { "true" } else { "false" }),
+            ConfigType::StringList => {
+                string_list(val).map(|items| format!("&[{}]", quote_all(&items)))
+            }
         }
     }
 
@@ -37,9 +54,77 @@ impl ConfigType {
             ConfigType::Bool => "bool",
             ConfigType::Int => "i64",
             ConfigType::Hex => "u64",
+            ConfigType::Float => "f64",
             ConfigType::String | ConfigType::Choice => "&str",
+            ConfigType::StringList => "&[&str]",
+        }
+    }
+}
+
+/// Reads a hex config value as the `u64` whose bit pattern it stores.
+pub fn hex_value(val: &toml::Value) -> Option<u64> {
+    val.as_integer().map(|i| i as u64)
+}
+
+/// Parses hex digits, with or without a `0x` prefix, into the value stored
+/// for a hex config.
+pub fn parse_hex(text: &str) -> Option<toml::Value> {
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    u64::from_str_radix(digits, 16)
+        .ok()
+        .map(|u| toml::Value::Integer(u as i64))
+}
+
+/// Reads a string list value, or `None` if it isn't an array of strings.
+pub fn string_list(val: &toml::Value) -> Option<Vec<&str>> {
+    val.as_array()?.iter().map(|v| v.as_str()).collect()
+}
+
+fn quote_all(items: &[&str]) -> String {
+    items
+        .iter()
+        .map(|s| quote(s))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A string literal for `s`, valid in both Rust and C.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
         }
     }
+    out.push('"');
+    out
+}
+
+/// Floats may be written as TOML integers, e.g. `default = 1`.
+fn as_float(val: &toml::Value) -> Option<f64> {
+    val.as_float()
+        .or_else(|| val.as_integer().map(|i| i as f64))
+}
+
+/// Choices hold their label by default, but may map to typed values through
+/// `option_values`, so format whichever scalar they resolve to.
+fn format_choice_value(val: &toml::Value, format_bool: fn(bool) -> &'static str) -> Option<String> {
+    match val {
+        toml::Value::String(s) => Some(quote(s)),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(format!("{:?}", f)),
+        toml::Value::Boolean(b) => Some(format_bool(*b).to_string()),
+        _ => None,
+    }
 }
 
 impl fmt::Display for ConfigType {
@@ -48,22 +133,230 @@ impl fmt::Display for ConfigType {
     }
 }
 
+/// A single option of a `choice` config.
+///
+/// In TOML an option is either a plain string or a table carrying a
+/// condition or a display label, e.g.
+/// `{ value = "epoll", depends_on = "TARGET_LINUX" }` or
+/// `{ value = "x86_64", label = "Intel/AMD 64-bit" }`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(from = "ChoiceOptionRepr")]
+pub struct ChoiceOption {
+    pub value: String,
+    pub depends_on: Option<String>,
+    /// Text shown in the TUI instead of `value`. The config file and
+    /// generated code always use `value`.
+    pub label: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ChoiceOptionRepr {
+    Plain(String),
+    Detailed {
+        value: String,
+        depends_on: Option<String>,
+        label: Option<String>,
+    },
+}
+
+impl From<ChoiceOptionRepr> for ChoiceOption {
+    fn from(repr: ChoiceOptionRepr) -> Self {
+        match repr {
+            ChoiceOptionRepr::Plain(value) => Self {
+                value,
+                depends_on: None,
+                label: None,
+            },
+            ChoiceOptionRepr::Detailed {
+                value,
+                depends_on,
+                label,
+            } => Self {
+                value,
+                depends_on,
+                label,
+            },
+        }
+    }
+}
+
+impl From<&str> for ChoiceOption {
+    fn from(value: &str) -> Self {
+        Self {
+            value: value.to_string(),
+            depends_on: None,
+            label: None,
+        }
+    }
+}
+
+impl ChoiceOption {
+    /// The text to show for this option: its label, or else its value.
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.value)
+    }
+
+    /// Whether this option's condition holds under the given evaluator.
+    pub fn is_enabled(&self, evaluator: &Evaluator) -> bool {
+        self.depends_on
+            .as_ref()
+            .map(|expr| evaluator.check_dependency(expr).unwrap_or(true))
+            .unwrap_or(true)
+    }
+}
+
+/// A default that applies while its condition holds, e.g.
+/// `{ value = 0x8000, depends_on = "ARCH_ARM" }`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConditionalDefault {
+    pub value: toml::Value,
+    pub depends_on: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigItem {
     pub name: String,
     #[serde(rename = "type")]
     pub config_type: ConfigType,
     pub default: Option<toml::Value>,
+    /// Defaults that take precedence over `default`; the first one whose
+    /// condition holds applies.
+    pub default_if: Option<Vec<ConditionalDefault>>,
+    /// Environment variable that, when set at load time, overrides `default`
+    /// and `default_if`.
+    pub default_env: Option<String>,
     pub desc: String,
     pub depends_on: Option<String>,
+    /// Condition for showing the symbol in the TUI. Unlike `depends_on`, a
+    /// symbol hidden by `visible_if` still holds its value in the config.
+    pub visible_if: Option<String>,
     pub help: Option<String>,
-    pub options: Option<Vec<String>>,
+    /// Marks the symbol as deprecated, naming its replacement symbol (or
+    /// empty if there is none).
+    pub deprecated: Option<String>,
+    /// Former names of the symbol, still accepted when loading `.config`.
+    pub alias: Option<Vec<String>>,
+    pub options: Option<Vec<ChoiceOption>>,
+    /// Whether a choice may have no option selected, stored as `""`.
+    #[serde(default)]
+    pub optional: bool,
+    /// Typed values emitted by codegen for each of `options`, in the same
+    /// order. The option labels are still what `.config` and the TUI use.
+    pub option_values: Option<Vec<toml::Value>>,
     pub feature: Option<Vec<String>>,
     pub range: Option<(i64, i64)>,
+    pub float_range: Option<(f64, f64)>,
+    /// Increment used when adjusting an int/hex value within `range` in the
+    /// TUI. Defaults to 1.
+    pub step: Option<i64>,
     pub regex: Option<String>,
+    /// The `exclusive_group` of the menu the symbol belongs to, if it is a
+    /// member. Filled in by [`flatten_configs`](crate::parser::flatten_configs).
+    #[serde(skip)]
+    pub exclusive_group: Option<Vec<String>>,
+}
+
+#[cfg(test)]
+impl ConfigItem {
+    /// An item with only a name and type, for building test fixtures with
+    /// struct update syntax.
+    pub(crate) fn test_item(name: &str, config_type: ConfigType) -> Self {
+        Self {
+            name: name.to_string(),
+            config_type,
+            default: None,
+            default_if: None,
+            default_env: None,
+            desc: String::new(),
+            depends_on: None,
+            visible_if: None,
+            help: None,
+            deprecated: None,
+            alias: None,
+            options: None,
+            optional: false,
+            option_values: None,
+            feature: None,
+            range: None,
+            float_range: None,
+            step: None,
+            regex: None,
+            exclusive_group: None,
+        }
+    }
+
+    /// A bool [`test_item`](Self::test_item) described by its name, with an
+    /// optional `depends_on`.
+    pub(crate) fn test_bool(name: &str, depends_on: Option<&str>) -> Self {
+        Self {
+            desc: name.to_string(),
+            depends_on: depends_on.map(|s| s.to_string()),
+            ..Self::test_item(name, ConfigType::Bool)
+        }
+    }
 }
 
 impl ConfigItem {
+    /// Resolves the symbol's default, in order of precedence: the
+    /// `default_env` variable if it is set and valid, the first `default_if`
+    /// entry whose condition holds under `evaluator`, then `default`.
+    pub fn effective_default(&self, evaluator: &Evaluator) -> Option<toml::Value> {
+        self.env_default()
+            .or_else(|| self.conditional_default(evaluator))
+            .or_else(|| self.default.clone())
+    }
+
+    /// The value of the first `default_if` entry whose condition holds.
+    pub fn conditional_default(&self, evaluator: &Evaluator) -> Option<toml::Value> {
+        self.default_if
+            .iter()
+            .flatten()
+            .find(|d| evaluator.check_dependency(&d.depends_on).unwrap_or(false))
+            .map(|d| d.value.clone())
+    }
+
+    /// The value of the `default_env` variable, parsed as this symbol's type.
+    /// An invalid value is ignored with a warning.
+    pub fn env_default(&self) -> Option<toml::Value> {
+        let var = self.default_env.as_ref()?;
+        let text = std::env::var(var).ok()?;
+        match self.parse_value(&text) {
+            Ok(val) => Some(val),
+            Err(e) => {
+                eprintln!(
+                    "{} ignoring ${}: {}",
+                    crate::term::Styler::stderr().warning(),
+                    var,
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Converts values written in a more natural form into the stored one:
+    /// hex configs accept strings like `"0x1000"`, stored as integers.
+    pub fn normalize(&self, value: toml::Value) -> toml::Value {
+        match (&self.config_type, &value) {
+            (ConfigType::Hex, toml::Value::String(s)) => parse_hex(s).unwrap_or(value),
+            _ => value,
+        }
+    }
+
+    /// The inverse of [`normalize`](Self::normalize), for writing config
+    /// files: hex values are written as `"0x..."` strings, so values above
+    /// `i64::MAX` don't read as negative.
+    pub fn file_value(&self, value: &toml::Value) -> toml::Value {
+        match (&self.config_type, hex_value(value)) {
+            (ConfigType::Hex, Some(u)) => toml::Value::String(format!("0x{:x}", u)),
+            _ => value.clone(),
+        }
+    }
+
+    /// Checks `value` as written in a schema or config file, i.e. before
+    /// [`normalize`](Self::normalize). A normalized value is checked through
+    /// [`file_value`](Self::file_value).
     pub fn validate(&self, value: &toml::Value) -> Result<(), String> {
         match self.config_type {
             ConfigType::Bool => {
@@ -74,7 +367,31 @@ impl ConfigItem {
                     ));
                 }
             }
-            ConfigType::Int | ConfigType::Hex => {
+            ConfigType::Hex => {
+                // Values above `i64::MAX` can only be written as "0x..."
+                // strings, so a negative integer is never a hex value.
+                let val = match value {
+                    toml::Value::Integer(i) => u64::try_from(*i).ok(),
+                    toml::Value::String(s) => parse_hex(s).as_ref().and_then(hex_value),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    format!(
+                        "Config '{}' expected unsigned integer, found {:?}",
+                        self.name, value
+                    )
+                })?;
+                if let Some((min, max)) = self.range {
+                    let (min, max) = (min as u64, max as u64);
+                    if val < min || val > max {
+                        return Err(format!(
+                            "Config '{}' value 0x{:x} out of range [0x{:x}, 0x{:x}]",
+                            self.name, val, min, max
+                        ));
+                    }
+                }
+            }
+            ConfigType::Int => {
                 let val = value.as_integer().ok_or_else(|| {
                     format!("Config '{}' expected integer, found {:?}", self.name, value)
                 })?;
@@ -87,6 +404,19 @@ impl ConfigItem {
                     }
                 }
             }
+            ConfigType::Float => {
+                let val = as_float(value).ok_or_else(|| {
+                    format!("Config '{}' expected float, found {:?}", self.name, value)
+                })?;
+                if let Some((min, max)) = self.float_range {
+                    if val < min || val > max {
+                        return Err(format!(
+                            "Config '{}' value {} out of range [{}, {}]",
+                            self.name, val, min, max
+                        ));
+                    }
+                }
+            }
             ConfigType::String => {
                 let val = value.as_str().ok_or_else(|| {
                     format!("Config '{}' expected string, found {:?}", self.name, value)
@@ -109,11 +439,34 @@ impl ConfigItem {
                         self.name, value
                     )
                 })?;
+                if self.is_unselected(value) {
+                    return Ok(());
+                }
                 if let Some(options) = &self.options {
-                    if !options.contains(&val.to_string()) {
+                    if !options.iter().any(|o| o.value == val) {
                         return Err(format!(
                             "Config '{}' value \"{}\" is not a valid option. Valid options are: {:?}",
-                            self.name, val, options
+                            self.name,
+                            val,
+                            self.option_values()
+                        ));
+                    }
+                }
+            }
+            ConfigType::StringList => {
+                let items = string_list(value).ok_or_else(|| {
+                    format!(
+                        "Config '{}' expected array of strings, found {:?}",
+                        self.name, value
+                    )
+                })?;
+                if let Some(regex_str) = &self.regex {
+                    let re = regex::Regex::new(regex_str)
+                        .map_err(|e| format!("Invalid regex for config '{}': {}", self.name, e))?;
+                    if let Some(bad) = items.iter().find(|item| !re.is_match(item)) {
+                        return Err(format!(
+                            "Config '{}' element \"{}\" does not match regex / {} /",
+                            self.name, bad, regex_str
                         ));
                     }
                 }
@@ -121,6 +474,121 @@ impl ConfigItem {
         }
         Ok(())
     }
+
+    /// Parses a textual value (e.g. from an environment variable) into this
+    /// config's type, then validates it.
+    pub fn parse_value(&self, text: &str) -> Result<toml::Value, String> {
+        let text = text.trim();
+        let value = match self.config_type {
+            ConfigType::Bool => match text.to_ascii_lowercase().as_str() {
+                "true" | "y" | "yes" | "1" => Some(toml::Value::Boolean(true)),
+                "false" | "n" | "no" | "0" => Some(toml::Value::Boolean(false)),
+                _ => None,
+            },
+            ConfigType::Int => text.parse().ok().map(toml::Value::Integer),
+            ConfigType::Hex => parse_hex(text),
+            ConfigType::Float => text.parse().ok().map(toml::Value::Float),
+            ConfigType::String | ConfigType::Choice => Some(toml::Value::String(text.to_string())),
+            ConfigType::StringList => Some(toml::Value::Array(
+                text.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| toml::Value::String(s.to_string()))
+                    .collect(),
+            )),
+        }
+        .ok_or_else(|| {
+            format!(
+                "Config '{}' expected {}, found \"{}\"",
+                self.name,
+                self.config_type.to_string().to_lowercase(),
+                text
+            )
+        })?;
+        self.validate(&self.file_value(&value))?;
+        Ok(value)
+    }
+
+    /// Like [`validate`](Self::validate), but also rejects choice options
+    /// whose `depends_on` condition is not met.
+    pub fn validate_with(&self, value: &toml::Value, evaluator: &Evaluator) -> Result<(), String> {
+        self.validate(value)?;
+        if let (Some(options), Some(val)) = (&self.options, value.as_str()) {
+            if let Some(opt) = options.iter().find(|o| o.value == val) {
+                if !opt.is_enabled(evaluator) {
+                    return Err(format!(
+                        "Config '{}' option \"{}\" is disabled (depends on: {})",
+                        self.name,
+                        val,
+                        opt.depends_on.as_deref().unwrap_or_default()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The value codegen should emit for `value`: the entry of
+    /// `option_values` matching the selected option, or `value` itself.
+    pub fn mapped_value<'v>(&'v self, value: &'v toml::Value) -> &'v toml::Value {
+        let (Some(options), Some(mapped), Some(label)) =
+            (&self.options, &self.option_values, value.as_str())
+        else {
+            return value;
+        };
+        options
+            .iter()
+            .position(|o| o.value == label)
+            .and_then(|i| mapped.get(i))
+            .unwrap_or(value)
+    }
+
+    /// The Rust type of the generated const, taking `option_values` into
+    /// account for choices.
+    pub fn rust_type(&self) -> &'static str {
+        match self.option_values.as_ref().and_then(|v| v.first()) {
+            Some(toml::Value::Integer(_)) => "i64",
+            Some(toml::Value::Float(_)) => "f64",
+            Some(toml::Value::Boolean(_)) => "bool",
+            _ => self.config_type.rust_type(),
+        }
+    }
+
+    /// The warning to show when a deprecated symbol is still in use.
+    pub fn deprecation_message(&self) -> Option<String> {
+        let replacement = self.deprecated.as_ref()?;
+        Some(if replacement.is_empty() {
+            format!("CONFIG_{} is deprecated", self.name)
+        } else {
+            format!(
+                "CONFIG_{} is deprecated; use CONFIG_{}",
+                self.name, replacement
+            )
+        })
+    }
+
+    /// Whether `value` is the "(none)" selection of an optional choice.
+    pub fn is_unselected(&self, value: &toml::Value) -> bool {
+        self.optional && self.config_type == ConfigType::Choice && value.as_str() == Some("")
+    }
+
+    /// The raw values of a choice's options, in declaration order.
+    pub fn option_values(&self) -> Vec<&str> {
+        self.options
+            .iter()
+            .flatten()
+            .map(|o| o.value.as_str())
+            .collect()
+    }
+
+    /// The options of a choice whose conditions currently hold.
+    pub fn enabled_options(&self, evaluator: &Evaluator) -> Vec<&ChoiceOption> {
+        self.options
+            .iter()
+            .flatten()
+            .filter(|o| o.is_enabled(evaluator))
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -142,6 +610,10 @@ mod tests {
             ConfigType::Hex.format_value_c(&Value::Integer(255)),
             Some("0xff".to_string())
         );
+        assert_eq!(
+            ConfigType::Float.format_value_c(&Value::Float(1.5)),
+            Some("1.5".to_string())
+        );
         assert_eq!(
             ConfigType::String.format_value_c(&Value::String("hi".to_string())),
             Some("\"hi\"".to_string())
@@ -158,6 +630,11 @@ mod tests {
             ConfigType::Int.format_value_rust(&Value::Integer(42)),
             Some("42".to_string())
         );
+        assert_eq!(
+            ConfigType::Float.format_value_rust(&Value::Integer(2)),
+            Some("2.0".to_string())
+        );
+        assert_eq!(ConfigType::Float.rust_type(), "f64");
         assert_eq!(
             ConfigType::String.format_value_rust(&Value::String("hi".to_string())),
             Some("\"hi\"".to_string())
@@ -167,16 +644,9 @@ mod tests {
     #[test]
     fn test_validation() {
         let item = ConfigItem {
-            name: "PORT".to_string(),
-            config_type: ConfigType::Int,
-            default: None,
             desc: "Port".to_string(),
-            depends_on: None,
-            help: None,
-            options: None,
-            feature: None,
             range: Some((1, 65535)),
-            regex: None,
+            ..ConfigItem::test_item("PORT", ConfigType::Int)
         };
 
         assert!(item.validate(&Value::Integer(80)).is_ok());
@@ -184,16 +654,9 @@ mod tests {
         assert!(item.validate(&Value::Integer(70000)).is_err());
 
         let item_re = ConfigItem {
-            name: "NAME".to_string(),
-            config_type: ConfigType::String,
-            default: None,
             desc: "Name".to_string(),
-            depends_on: None,
-            help: None,
-            options: None,
-            feature: None,
-            range: None,
             regex: Some(r"^[a-z]+$".to_string()),
+            ..ConfigItem::test_item("NAME", ConfigType::String)
         };
 
         assert!(item_re
@@ -203,6 +666,218 @@ mod tests {
             .validate(&Value::String("HELLO".to_string()))
             .is_err());
         assert!(item_re.validate(&Value::String("123".to_string())).is_err());
+
+        let item_float = ConfigItem {
+            desc: "Ratio".to_string(),
+            float_range: Some((0.0, 1.0)),
+            ..ConfigItem::test_item("RATIO", ConfigType::Float)
+        };
+
+        assert!(item_float.validate(&Value::Float(0.5)).is_ok());
+        assert!(item_float.validate(&Value::Integer(1)).is_ok());
+        assert!(item_float.validate(&Value::Float(1.5)).is_err());
+        assert!(item_float
+            .validate(&Value::String("0.5".to_string()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_hex_is_unsigned() -> Result<(), String> {
+        let item = ConfigItem {
+            desc: "Mask".to_string(),
+            ..ConfigItem::test_item("MASK", ConfigType::Hex)
+        };
+
+        let max = item.parse_value("0xFFFFFFFFFFFFFFFF")?;
+        assert_eq!(hex_value(&max), Some(u64::MAX));
+        assert_eq!(
+            ConfigType::Hex.format_value_rust(&max),
+            Some("0xffffffffffffffff".to_string())
+        );
+        assert_eq!(
+            ConfigType::Hex.format_value_c(&max),
+            Some("0xffffffffffffffff".to_string())
+        );
+        assert!(item.parse_value("0x1FFFFFFFFFFFFFFFF").is_err());
+        assert!(item.parse_value("-1").is_err());
+
+        let ranged = ConfigItem {
+            range: Some((0x1000, 0x7FFF_FFFF_FFFF_FFFF)),
+            ..item.clone()
+        };
+        assert!(ranged.validate(&Value::Integer(0x2000)).is_ok());
+        assert!(ranged.validate(&ranged.file_value(&max)).is_err());
+
+        // Only the "0x..." form reaches past i64::MAX.
+        assert!(item.validate(&Value::Integer(-1)).is_err());
+        assert!(item
+            .validate(&Value::String("0xffffffffffffffff".to_string()))
+            .is_ok());
+        assert!(item.validate(&Value::String("-1".to_string())).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_list() -> anyhow::Result<()> {
+        let parsed: KconfigFile = toml::from_str(
+            r#"
+            [[config]]
+            name = "ORIGINS"
+            type = "string_list"
+            default = ["localhost", "example.com"]
+            desc = "Allowed origins"
+            regex = "^[a-z.]+$"
+        "#,
+        )?;
+        let item = &parsed.configs.unwrap()[0];
+        let origins = item.default.clone().unwrap();
+
+        assert!(item.validate(&origins).is_ok());
+        assert!(item
+            .validate(&Value::Array(vec![Value::String("Bad Host".into())]))
+            .is_err());
+        assert!(item
+            .validate(&Value::Array(vec![Value::Integer(1)]))
+            .is_err());
+        assert!(item.validate(&Value::String("localhost".into())).is_err());
+        assert_eq!(
+            item.parse_value(" a, b ,, c"),
+            Ok(Value::Array(vec!["a".into(), "b".into(), "c".into()]))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_list_format() {
+        let list = Value::Array(vec![Value::String("a".into()), Value::String("b".into())]);
+        assert_eq!(
+            ConfigType::StringList.format_value_c(&list),
+            Some("{\"a\", \"b\"}".to_string())
+        );
+        assert_eq!(
+            ConfigType::StringList.format_value_rust(&list),
+            Some("&[\"a\", \"b\"]".to_string())
+        );
+        assert_eq!(ConfigType::StringList.rust_type(), "&[&str]");
+        assert_eq!(
+            ConfigType::StringList.format_value_rust(&Value::Array(Vec::new())),
+            Some("&[]".to_string())
+        );
+    }
+
+    #[test]
+    fn test_optional_choice() -> anyhow::Result<()> {
+        let parsed: KconfigFile = toml::from_str(
+            r#"
+            [[config]]
+            name = "LOGGER"
+            type = "choice"
+            desc = "Logger"
+            options = ["uart", "rtt"]
+            optional = true
+
+            [[config]]
+            name = "BACKEND"
+            type = "choice"
+            desc = "Backend"
+            options = ["poll", "epoll"]
+        "#,
+        )?;
+        let configs = parsed.configs.unwrap();
+        let none = Value::String(String::new());
+
+        assert!(configs[0].optional);
+        assert!(configs[0].validate(&none).is_ok());
+        assert!(configs[0].validate(&Value::String("rtt".into())).is_ok());
+        assert!(configs[0].validate(&Value::String("usb".into())).is_err());
+
+        assert!(!configs[1].optional);
+        assert!(configs[1].validate(&none).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_choice_option_conditions() -> anyhow::Result<()> {
+        let parsed: KconfigFile = toml::from_str(
+            r#"
+            [[config]]
+            name = "BACKEND"
+            type = "choice"
+            desc = "Backend"
+            options = ["poll", { value = "epoll", depends_on = "LINUX" }]
+        "#,
+        )?;
+        let item = &parsed.configs.unwrap()[0];
+        assert_eq!(item.option_values(), vec!["poll", "epoll"]);
+
+        let epoll = Value::String("epoll".to_string());
+        let mut evaluator = Evaluator::new();
+        evaluator.set_variable("LINUX", &Value::Boolean(false))?;
+        assert!(item.validate(&epoll).is_ok());
+        assert!(item.validate_with(&epoll, &evaluator).is_err());
+        assert_eq!(item.enabled_options(&evaluator).len(), 1);
+
+        evaluator.set_variable("LINUX", &Value::Boolean(true))?;
+        assert!(item.validate_with(&epoll, &evaluator).is_ok());
+        assert_eq!(item.enabled_options(&evaluator).len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_symbols() {
+        fn menu(names: &[&str], children: Vec<ConfigNode>) -> ConfigNode {
+            ConfigNode {
+                desc: String::new(),
+                configs: names
+                    .iter()
+                    .map(|name| ConfigItem::test_item(name, ConfigType::Bool))
+                    .collect(),
+                children,
+                path: String::new(),
+                depends_on: None,
+                help: None,
+                exclusive_group: None,
+                file: String::new(),
+            }
+        }
+
+        // Root [DEBUG] -> Net [NET], Drivers [DRIVERS] -> USB [], Serial [UART, SPI]
+        let drivers = menu(
+            &["DRIVERS"],
+            vec![menu(&[], vec![]), menu(&["UART", "SPI"], vec![])],
+        );
+        let root = menu(&["DEBUG"], vec![menu(&["NET"], vec![]), drivers]);
+
+        let index = root.index_symbols();
+        assert_eq!(index.len(), 5);
+        assert_eq!(index["DEBUG"], Vec::<usize>::new());
+        assert_eq!(index["NET"], vec![0]);
+        assert_eq!(index["DRIVERS"], vec![1]);
+        assert_eq!(index["SPI"], vec![1, 1]);
+    }
+
+    #[test]
+    fn test_choice_option_labels() -> anyhow::Result<()> {
+        let parsed: KconfigFile = toml::from_str(
+            r#"
+            [[config]]
+            name = "ARCH"
+            type = "choice"
+            desc = "Architecture"
+            options = ["riscv64", { value = "x86_64", label = "Intel/AMD 64-bit" }]
+        "#,
+        )?;
+        let item = &parsed.configs.unwrap()[0];
+        let options = item.options.as_ref().unwrap();
+        assert_eq!(options[0].label(), "riscv64");
+        assert_eq!(options[1].label(), "Intel/AMD 64-bit");
+        assert_eq!(item.option_values(), vec!["riscv64", "x86_64"]);
+
+        assert!(item.validate(&Value::String("x86_64".to_string())).is_ok());
+        assert!(item
+            .validate(&Value::String("Intel/AMD 64-bit".to_string()))
+            .is_err());
+        Ok(())
     }
 }
 
@@ -217,16 +892,51 @@ pub struct Menu {
 pub struct KconfigFile {
     pub title: Option<String>,
     pub depends_on: Option<String>,
+    /// Longer description of the menu, shown under the breadcrumb in the TUI.
+    pub help: Option<String>,
+    /// Bool symbols of this file of which at most one may be enabled.
+    pub exclusive_group: Option<Vec<String>>,
+    /// Extra Kconfig files to include, relative to this file.
+    pub source: Option<Vec<String>>,
     #[serde(rename = "config")]
     pub configs: Option<Vec<ConfigItem>>,
 }
 
 /// Represents a node in the configuration hierarchy
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigNode {
     pub desc: String,
     pub configs: Vec<ConfigItem>,
     pub children: Vec<ConfigNode>,
     pub path: String,
     pub depends_on: Option<String>,
+    pub help: Option<String>,
+    pub exclusive_group: Option<Vec<String>>,
+    /// The schema file the node was parsed from, relative to the scan root
+    /// (absolute for files sourced from outside it).
+    #[serde(default)]
+    pub file: String,
+}
+
+impl ConfigNode {
+    /// Maps every symbol in the tree to the child indices leading from this
+    /// node to the menu that defines it, so lookups don't walk the tree.
+    pub fn index_symbols(&self) -> HashMap<String, Vec<usize>> {
+        fn walk(node: &ConfigNode, path: &mut Vec<usize>, index: &mut HashMap<String, Vec<usize>>) {
+            for item in &node.configs {
+                index
+                    .entry(item.name.clone())
+                    .or_insert_with(|| path.clone());
+            }
+            for (i, child) in node.children.iter().enumerate() {
+                path.push(i);
+                walk(child, path, index);
+                path.pop();
+            }
+        }
+
+        let mut index = HashMap::new();
+        walk(self, &mut Vec::new(), &mut index);
+        index
+    }
 }
@@ -0,0 +1,93 @@
+//! Styled terminal text for CLI output.
+//!
+//! Colors are only emitted when the target stream is a terminal and the
+//! `NO_COLOR` environment variable (<https://no-color.org>) is unset or empty.
+
+use std::ffi::OsStr;
+use std::io::IsTerminal;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+}
+
+impl Color {
+    fn ansi_code(self) -> u8 {
+        match self {
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+        }
+    }
+}
+
+/// Wraps text in ANSI colors when enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Styler {
+    enabled: bool,
+}
+
+impl Styler {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    /// Styler for output written to `stream`.
+    pub fn for_stream(stream: &impl IsTerminal) -> Self {
+        let no_color = std::env::var_os("NO_COLOR");
+        Self::new(colors_enabled(no_color.as_deref(), stream.is_terminal()))
+    }
+
+    pub fn stdout() -> Self {
+        Self::for_stream(&std::io::stdout())
+    }
+
+    pub fn stderr() -> Self {
+        Self::for_stream(&std::io::stderr())
+    }
+
+    pub fn paint(&self, text: &str, color: Color) -> String {
+        if self.enabled {
+            format!("\x1b[{}m{}\x1b[0m", color.ansi_code(), text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// `Error:` prefix for diagnostics.
+    pub fn error(&self) -> String {
+        self.paint("Error:", Color::Red)
+    }
+
+    /// `Warning:` prefix for diagnostics.
+    pub fn warning(&self) -> String {
+        self.paint("Warning:", Color::Yellow)
+    }
+}
+
+/// Whether to emit colors given the value of `NO_COLOR` and whether the
+/// stream is a terminal.
+pub fn colors_enabled(no_color: Option<&OsStr>, is_terminal: bool) -> bool {
+    is_terminal && no_color.is_none_or(|v| v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_color_suppresses_colors() {
+        assert!(colors_enabled(None, true));
+        assert!(colors_enabled(Some(OsStr::new("")), true));
+        assert!(!colors_enabled(Some(OsStr::new("1")), true));
+        assert!(!colors_enabled(None, false));
+
+        assert_eq!(Styler::new(false).paint("ok", Color::Green), "ok");
+        assert_eq!(
+            Styler::new(true).paint("ok", Color::Green),
+            "\x1b[32mok\x1b[0m"
+        );
+    }
+}
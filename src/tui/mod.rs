@@ -1,28 +1,48 @@
 use crate::config_io;
+use crate::evaluator::Evaluator;
 use crate::parser;
-use crate::schema::{ConfigItem, ConfigNode};
+use crate::schema::{ChoiceOption, ConfigItem, ConfigNode};
 use anyhow::Result;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseButton,
+        MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::{Backend, CrosstermBackend},
+    layout::Rect,
     widgets::ListState,
     Terminal,
 };
-use std::collections::HashMap;
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use toml::Value;
 
+pub mod state;
+pub mod theme;
 pub mod ui;
 
+pub use state::SavedPosition;
+pub use theme::Theme;
+
+/// Default number of edits kept on the undo stack.
+pub const DEFAULT_HISTORY_DEPTH: usize = 100;
+
+/// Maximum delay between two clicks on the same row to count as a double-click.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
 pub struct Editor {
     pub config: ConfigItem,
     pub input: String,
     pub choice_state: ListState,
+    /// In-progress value of a ranged int/hex config adjusted with Up/Down.
+    pub numeric: Option<i64>,
 }
 
 pub struct UiState {
@@ -31,6 +51,22 @@ pub struct UiState {
     pub notification: Option<String>,
     pub show_quit_confirm: bool,
     pub editor: Option<Editor>,
+    pub search_query: String,
+    pub search_active: bool,
+    /// Symbol name typed into the jump-to prompt, while it is open.
+    pub jump_query: Option<String>,
+    pub show_hidden: bool,
+    pub list_area: Rect,
+    pub last_click: Option<(Instant, usize)>,
+    pub help_item: Option<ConfigItem>,
+    pub undo_stack: Vec<HashMap<String, Value>>,
+    pub redo_stack: Vec<HashMap<String, Value>>,
+    pub history_depth: usize,
+    /// Set when the terminal is resized, so the next frame is drawn from a
+    /// cleared screen instead of diffed against the stale one.
+    pub needs_clear: bool,
+    /// Int symbols the user switched to hexadecimal display with `x`.
+    pub hex_display: HashSet<String>,
 }
 
 pub struct App {
@@ -40,14 +76,31 @@ pub struct App {
     pub should_quit: bool,
     pub flattened_items: Vec<ConfigItem>,
     pub is_dirty: bool,
+    /// Number of symbols whose value differs from the default.
+    pub modified_count: usize,
     pub evaluator: crate::evaluator::Evaluator,
     pub ui: UiState,
+    pub theme: Theme,
+    /// Whether saving drops the values of symbols whose dependencies are
+    /// unmet, see [`config_io::prune_hidden`].
+    pub prune_hidden: bool,
+    /// Menu path of every symbol, built on the first jump.
+    symbol_index: OnceCell<HashMap<String, Vec<usize>>>,
+    /// Opened on the first copy and kept alive, since on X11 copied text is
+    /// only served while the clipboard handle exists.
+    clipboard: Option<arboard::Clipboard>,
 }
 
 impl App {
     pub fn new(root_node: ConfigNode, config_path: PathBuf) -> Result<Self> {
         let flattened_items = parser::flatten_configs(&root_node);
-        let values = config_io::load_config(&config_path, &flattened_items)?;
+        // On first run don't create the file until the user saves.
+        let fresh = !config_path.exists();
+        let values = if fresh {
+            crate::evaluator::collect_defaults(&flattened_items)
+        } else {
+            config_io::load_config(&config_path, &flattened_items)?
+        };
         let mut list_state = ListState::default();
         list_state.select(Some(0));
 
@@ -55,29 +108,123 @@ impl App {
         for (name, val) in &values {
             let _ = evaluator.set_variable(name, val);
         }
+        evaluator.set_explicit(defined_symbols(&flattened_items, &values));
+        let modified_count =
+            config_io::get_minimal_config(&values, &flattened_items, &evaluator).len();
+        let saved = SavedPosition::load(&state::state_path(&config_path));
 
-        Ok(Self {
+        let mut app = Self {
             root_node,
             values,
             config_path,
             should_quit: false,
             flattened_items,
             is_dirty: false,
+            modified_count,
             evaluator,
             ui: UiState {
                 current_node_path: Vec::new(),
                 list_state,
-                notification: None,
+                notification: fresh
+                    .then(|| "No existing config; starting from defaults".to_string()),
                 show_quit_confirm: false,
                 editor: None,
+                search_query: String::new(),
+                search_active: false,
+                jump_query: None,
+                show_hidden: false,
+                list_area: Rect::default(),
+                last_click: None,
+                help_item: None,
+                undo_stack: Vec::new(),
+                redo_stack: Vec::new(),
+                history_depth: DEFAULT_HISTORY_DEPTH,
+                needs_clear: false,
+                hex_display: HashSet::new(),
             },
-        })
+            theme: Theme::default(),
+            prune_hidden: false,
+            symbol_index: OnceCell::new(),
+            clipboard: None,
+        };
+        if let Some(saved) = saved {
+            app.restore_position(&saved);
+        }
+        Ok(app)
+    }
+
+    /// The open menu and selected row, for saving on quit.
+    pub fn position(&self) -> SavedPosition {
+        SavedPosition {
+            path: self.ui.current_node_path.clone(),
+            selected: self.ui.list_state.selected().unwrap_or(0),
+        }
+    }
+
+    /// Reopens a saved menu. Parts of the path the tree no longer has are
+    /// dropped, and the selection falls back to the first row when the menu
+    /// changed or the row is gone.
+    pub fn restore_position(&mut self, saved: &SavedPosition) {
+        let path = state::clamp_node_path(&self.root_node, &saved.path);
+        let same_menu = path == saved.path;
+        self.ui.current_node_path = path;
+
+        let (configs, children) = self.get_visible_items();
+        let total = configs.len() + children.len();
+        if same_menu && saved.selected < total && self.is_selectable(saved.selected) {
+            self.ui.list_state.select(Some(saved.selected));
+        } else {
+            self.ui.list_state.select(Some(0));
+            if !self.is_selectable(0) {
+                self.next();
+            }
+        }
     }
 
     pub fn update_evaluator(&mut self) {
+        // Rebuild from scratch so symbols removed from `values` don't linger.
+        self.evaluator = crate::evaluator::Evaluator::new();
         for (name, val) in &self.values {
             let _ = self.evaluator.set_variable(name, val);
         }
+        self.evaluator
+            .set_explicit(defined_symbols(&self.flattened_items, &self.values));
+        self.modified_count =
+            config_io::get_minimal_config(&self.values, &self.flattened_items, &self.evaluator)
+                .len();
+    }
+
+    /// Snapshots `values` onto the undo stack before a mutating operation.
+    pub fn record_history(&mut self) {
+        self.ui.undo_stack.push(self.values.clone());
+        if self.ui.undo_stack.len() > self.ui.history_depth {
+            self.ui.undo_stack.remove(0);
+        }
+        self.ui.redo_stack.clear();
+    }
+
+    pub fn undo(&mut self) {
+        match self.ui.undo_stack.pop() {
+            Some(previous) => {
+                let current = std::mem::replace(&mut self.values, previous);
+                self.ui.redo_stack.push(current);
+                self.is_dirty = true;
+                self.update_evaluator();
+            }
+            None => self.notify("Nothing to undo".to_string()),
+        }
+    }
+
+    pub fn redo(&mut self) {
+        match self.ui.redo_stack.pop() {
+            Some(next) => {
+                let current = std::mem::replace(&mut self.values, next);
+                self.ui.undo_stack.push(current);
+                self.is_dirty = true;
+                self.update_evaluator();
+            }
+            None => self.notify("Nothing to redo".to_string()),
+        }
     }
 
     pub fn get_current_node(&self) -> &ConfigNode {
@@ -98,12 +245,14 @@ impl App {
         path.join(" > ")
     }
 
+    /// Whether the symbol's prompt is shown: both `depends_on` and
+    /// `visible_if` must hold.
     pub fn is_visible_config(&self, config: &ConfigItem) -> bool {
         config
             .depends_on
-            .as_ref()
-            .map(|expr| self.evaluator.check_dependency(expr).unwrap_or(true))
-            .unwrap_or(true)
+            .iter()
+            .chain(&config.visible_if)
+            .all(|expr| self.evaluator.check_dependency(expr).unwrap_or(true))
     }
 
     pub fn is_visible_node(&self, node: &ConfigNode) -> bool {
@@ -115,36 +264,102 @@ impl App {
 
     pub fn get_visible_items(&self) -> (Vec<&ConfigItem>, Vec<&ConfigNode>) {
         let node = self.get_current_node();
+        let query = &self.ui.search_query;
         let configs: Vec<&ConfigItem> = node
             .configs
             .iter()
-            .filter(|c| self.is_visible_config(c))
+            .filter(|c| self.ui.show_hidden || self.is_visible_config(c))
+            .filter(|c| matches_filter(query, &c.name) || matches_filter(query, &c.desc))
             .collect();
         let children: Vec<&ConfigNode> = node
             .children
             .iter()
-            .filter(|n| self.is_visible_node(n))
+            .filter(|n| self.ui.show_hidden || self.is_visible_node(n))
+            .filter(|n| matches_filter(query, &n.desc))
             .collect();
         (configs, children)
     }
 
+    pub fn start_search(&mut self) {
+        self.ui.search_active = true;
+    }
+
+    /// Navigates to the menu defining `name` and selects it.
+    pub fn jump_to(&mut self, name: &str) {
+        let Some(path) = self.symbol_path(name) else {
+            self.notify(format!("Symbol '{}' not found", name));
+            return;
+        };
+        self.ui.current_node_path = path.clone();
+        self.ui.search_query.clear();
+
+        let node = self.get_current_node();
+        let config = node
+            .configs
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+            .cloned();
+        if let Some(config) = config {
+            if !self.is_visible_config(&config) {
+                self.ui.show_hidden = true;
+            }
+            let index = self
+                .get_visible_items()
+                .0
+                .iter()
+                .position(|c| c.name == config.name);
+            self.ui.list_state.select(index.or(Some(0)));
+        }
+    }
+
+    /// Looks up the menu path of `name` in the symbol index, falling back
+    /// to a case-insensitive match.
+    fn symbol_path(&self, name: &str) -> Option<&Vec<usize>> {
+        let index = self
+            .symbol_index
+            .get_or_init(|| self.root_node.index_symbols());
+        index.get(name).or_else(|| {
+            index
+                .iter()
+                .find(|(symbol, _)| symbol.eq_ignore_ascii_case(name))
+                .map(|(_, path)| path)
+        })
+    }
+
+    pub fn clear_search(&mut self) {
+        self.ui.search_active = false;
+        self.ui.search_query.clear();
+        self.ui.list_state.select(Some(0));
+    }
+
+    /// Whether the listed row at `index` can be selected. Rows for symbols
+    /// with unmet dependencies are only listed when `show_hidden` is on.
+    pub fn is_selectable(&self, index: usize) -> bool {
+        let (configs, children) = self.get_visible_items();
+        if let Some(config) = configs.get(index) {
+            self.is_visible_config(config)
+        } else {
+            children
+                .get(index - configs.len())
+                .map(|n| self.is_visible_node(n))
+                .unwrap_or(false)
+        }
+    }
+
     pub fn next(&mut self) {
         let (configs, children) = self.get_visible_items();
         let total = configs.len() + children.len();
         if total == 0 {
             return;
         }
-        let i = match self.ui.list_state.selected() {
-            Some(i) => {
-                if i >= total - 1 {
-                    0
-                } else {
-                    i + 1
-                }
+        let mut i = self.ui.list_state.selected().unwrap_or(total - 1);
+        for _ in 0..total {
+            i = if i >= total - 1 { 0 } else { i + 1 };
+            if self.is_selectable(i) {
+                self.ui.list_state.select(Some(i));
+                return;
             }
-            None => 0,
-        };
-        self.ui.list_state.select(Some(i));
+        }
     }
 
     pub fn previous(&mut self) {
@@ -153,17 +368,54 @@ impl App {
         if total == 0 {
             return;
         }
-        let i = match self.ui.list_state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    total - 1
-                } else {
-                    i - 1
-                }
+        let mut i = self.ui.list_state.selected().unwrap_or(0);
+        for _ in 0..total {
+            i = if i == 0 { total - 1 } else { i - 1 };
+            if self.is_selectable(i) {
+                self.ui.list_state.select(Some(i));
+                return;
             }
-            None => 0,
+        }
+    }
+
+    /// Copies the selected symbol's name, or with `with_value` its `.config`
+    /// assignment, to the system clipboard.
+    pub fn copy_selected(&mut self, with_value: bool) {
+        let Some(config) = self.selected_config() else {
+            return;
+        };
+        let text = clipboard_text(&config, self.values.get(&config.name), with_value);
+        let copied = match &mut self.clipboard {
+            Some(clipboard) => clipboard.set_text(text.clone()),
+            None => arboard::Clipboard::new()
+                .and_then(|clipboard| self.clipboard.insert(clipboard).set_text(text.clone())),
         };
-        self.ui.list_state.select(Some(i));
+        match copied {
+            Ok(()) => self.notify(format!("Copied: {}", text)),
+            Err(e) => self.notify(format!("Clipboard unavailable: {}", e)),
+        }
+    }
+
+    /// Flips the selected int symbol between decimal and hex display. The
+    /// stored value is untouched.
+    pub fn toggle_hex_display(&mut self) {
+        let Some(config) = self.selected_config() else {
+            return;
+        };
+        if config.config_type != crate::schema::ConfigType::Int {
+            return;
+        }
+        if !self.ui.hex_display.remove(&config.name) {
+            self.ui.hex_display.insert(config.name);
+        }
+    }
+
+    pub fn toggle_show_hidden(&mut self) {
+        self.ui.show_hidden = !self.ui.show_hidden;
+        self.ui.list_state.select(Some(0));
+        if !self.is_selectable(0) {
+            self.next();
+        }
     }
 
     pub fn enter(&mut self) {
@@ -172,7 +424,10 @@ impl App {
 
         if selected >= configs.len() {
             let child_index_in_visible = selected - configs.len();
-            if let Some(target_node) = children.get(child_index_in_visible) {
+            if let Some(target_node) = children
+                .get(child_index_in_visible)
+                .filter(|n| self.is_visible_node(n))
+            {
                 let parent_node = self.get_current_node();
                 let real_index = parent_node
                     .children
@@ -181,6 +436,7 @@ impl App {
 
                 if let Some(idx) = real_index {
                     self.ui.current_node_path.push(idx);
+                    self.ui.search_query.clear();
                     self.ui.list_state.select(Some(0));
                 }
             }
@@ -190,21 +446,72 @@ impl App {
     pub fn back(&mut self) {
         if !self.ui.current_node_path.is_empty() {
             self.ui.current_node_path.pop();
+            self.ui.search_query.clear();
             self.ui.list_state.select(Some(0));
         }
     }
 
-    pub fn toggle_bool(&mut self) {
+    pub fn selected_config(&self) -> Option<ConfigItem> {
         let selected = self.ui.list_state.selected().unwrap_or(0);
         let (visible_configs, _) = self.get_visible_items();
+        visible_configs
+            .get(selected)
+            .filter(|c| self.is_visible_config(c))
+            .map(|c| (*c).clone())
+    }
 
-        let config = if selected < visible_configs.len() {
-            Some(visible_configs[selected].clone())
-        } else {
-            None
-        };
+    pub fn show_help(&mut self) {
+        self.ui.help_item = self.selected_config();
+    }
 
-        if let Some(config) = config {
+    /// Sets every visible bool config in the current menu to `enabled` as a
+    /// single undoable step.
+    pub fn set_all_bools(&mut self, enabled: bool) {
+        let names: Vec<String> = self
+            .get_current_node()
+            .configs
+            .iter()
+            .filter(|c| c.config_type == crate::schema::ConfigType::Bool)
+            .filter(|c| self.is_visible_config(c))
+            .map(|c| c.name.clone())
+            .collect();
+        if names.is_empty() {
+            return;
+        }
+
+        self.record_history();
+        for name in &names {
+            self.values.insert(name.clone(), Value::Boolean(enabled));
+        }
+        self.is_dirty = true;
+        self.update_evaluator();
+        self.notify(format!(
+            "{} {} symbols",
+            if enabled { "Enabled" } else { "Disabled" },
+            names.len()
+        ));
+    }
+
+    pub fn reset_to_default(&mut self) {
+        if let Some(config) = self.selected_config() {
+            let default = config.effective_default(&self.evaluator);
+            self.record_history();
+            match default {
+                Some(default) => {
+                    self.values.insert(config.name.clone(), default);
+                }
+                None => {
+                    self.values.remove(&config.name);
+                }
+            }
+            self.is_dirty = true;
+            self.update_evaluator();
+            self.notify(format!("Reset {} to default", config.name));
+        }
+    }
+
+    pub fn toggle_bool(&mut self) {
+        if let Some(config) = self.selected_config() {
             match config.config_type {
                 crate::schema::ConfigType::Bool => {
                     let current_val = self
@@ -212,91 +519,164 @@ impl App {
                         .get(&config.name)
                         .and_then(|v| v.as_bool())
                         .unwrap_or(false);
+                    self.record_history();
                     self.values
                         .insert(config.name.clone(), Value::Boolean(!current_val));
+                    if !current_val {
+                        self.disable_exclusive_peers(&config.name);
+                    }
                     self.is_dirty = true;
                     self.update_evaluator();
                 }
                 crate::schema::ConfigType::Int
                 | crate::schema::ConfigType::Hex
+                | crate::schema::ConfigType::Float
                 | crate::schema::ConfigType::String => {
-                    let input = self
-                        .values
-                        .get(&config.name)
+                    let current = self.values.get(&config.name);
+                    let input = current
                         .map(|v| match v {
-                            Value::Integer(i) => i.to_string(),
+                            Value::Integer(i) => format_numeric(&config, *i),
+                            Value::Float(f) => f.to_string(),
                             Value::String(s) => s.clone(),
                             _ => String::new(),
                         })
                         .unwrap_or_default();
+                    let numeric = config.range.and(current.and_then(|v| v.as_integer()));
                     self.ui.editor = Some(Editor {
                         config,
                         input,
                         choice_state: ListState::default(),
+                        numeric,
+                    });
+                }
+                crate::schema::ConfigType::StringList => {
+                    let input = self
+                        .values
+                        .get(&config.name)
+                        .and_then(crate::schema::string_list)
+                        .unwrap_or_default()
+                        .join("\n");
+                    self.ui.editor = Some(Editor {
+                        config,
+                        input,
+                        choice_state: ListState::default(),
+                        numeric: None,
                     });
                 }
                 crate::schema::ConfigType::Choice => {
+                    let current = self.values.get(&config.name).and_then(|v| v.as_str());
+                    let index = current
+                        .and_then(|c| {
+                            choice_entries(&config, &self.evaluator)
+                                .iter()
+                                .position(|o| o.map_or("", |o| o.value.as_str()) == c)
+                        })
+                        .unwrap_or(0);
                     let mut choice_state = ListState::default();
-                    choice_state.select(Some(0));
+                    choice_state.select(Some(index));
                     self.ui.editor = Some(Editor {
                         config,
                         input: String::new(),
                         choice_state,
+                        numeric: None,
                     });
                 }
             }
         }
     }
 
+    /// Turns off the other enabled members of `name`'s `exclusive_group`.
+    fn disable_exclusive_peers(&mut self, name: &str) {
+        let Some(group) = parser::exclusive_group_of(&self.root_node, name) else {
+            return;
+        };
+        let mut disabled = Vec::new();
+        for member in group.iter().filter(|m| *m != name) {
+            if let Some(val) = self.values.get_mut(member) {
+                if val.as_bool() == Some(true) {
+                    *val = Value::Boolean(false);
+                    disabled.push(member.clone());
+                }
+            }
+        }
+        if !disabled.is_empty() {
+            self.notify(format!(
+                "Disabled {} (exclusive with {})",
+                disabled.join(", "),
+                name
+            ));
+        }
+    }
+
+    /// Number of selectable options in the open choice editor.
+    fn enabled_choice_count(&self) -> usize {
+        self.ui
+            .editor
+            .as_ref()
+            .map(|e| choice_entries(&e.config, &self.evaluator).len())
+            .unwrap_or(0)
+    }
+
     pub fn submit_choice(&mut self) {
         if let Some(editor) = self.ui.editor.take() {
             let config = editor.config;
-            if let Some(options) = &config.options {
-                if let Some(selected) = editor.choice_state.selected() {
-                    if let Some(opt) = options.get(selected) {
-                        self.values.insert(config.name, Value::String(opt.clone()));
-                        self.is_dirty = true;
-                        self.update_evaluator();
-                        self.notify(format!("Selected: {}", opt));
+            let options = choice_entries(&config, &self.evaluator);
+            if let Some(selected) = editor.choice_state.selected() {
+                if let Some(opt) = options.get(selected) {
+                    let label = opt.map_or(NO_CHOICE_LABEL, ChoiceOption::label);
+                    let val = Value::String(opt.map(|o| o.value.clone()).unwrap_or_default());
+                    if let Err(e) = config.validate_with(&val, &self.evaluator) {
+                        self.notify(format!("Error: {}", e));
+                        return;
                     }
+                    let msg = format!("Selected: {}", label);
+                    self.record_history();
+                    self.values.insert(config.name.clone(), val);
+                    self.is_dirty = true;
+                    self.update_evaluator();
+                    self.notify(msg);
                 }
             }
         }
     }
 
     pub fn next_choice(&mut self) {
+        let count = self.enabled_choice_count();
+        if count == 0 {
+            return;
+        }
         if let Some(editor) = &mut self.ui.editor {
-            if let Some(options) = &editor.config.options {
-                let i = match editor.choice_state.selected() {
-                    Some(i) => {
-                        if i >= options.len() - 1 {
-                            0
-                        } else {
-                            i + 1
-                        }
+            let i = match editor.choice_state.selected() {
+                Some(i) => {
+                    if i >= count - 1 {
+                        0
+                    } else {
+                        i + 1
                     }
-                    None => 0,
-                };
-                editor.choice_state.select(Some(i));
-            }
+                }
+                None => 0,
+            };
+            editor.choice_state.select(Some(i));
         }
     }
 
     pub fn previous_choice(&mut self) {
+        let count = self.enabled_choice_count();
+        if count == 0 {
+            return;
+        }
         if let Some(editor) = &mut self.ui.editor {
-            if let Some(options) = &editor.config.options {
-                let i = match editor.choice_state.selected() {
-                    Some(i) => {
-                        if i == 0 {
-                            options.len() - 1
-                        } else {
-                            i - 1
-                        }
+            let i = match editor.choice_state.selected() {
+                Some(i) => {
+                    if i == 0 {
+                        count - 1
+                    } else {
+                        i - 1
                     }
-                    None => 0,
-                };
-                editor.choice_state.select(Some(i));
-            }
+                }
+                None => 0,
+            };
+            editor.choice_state.select(Some(i));
         }
     }
 
@@ -308,81 +688,181 @@ impl App {
         self.ui.notification = None;
     }
 
+    /// Parses and validates the editor input. On failure the editor stays
+    /// open so the user can correct the value.
     pub fn submit_input(&mut self) {
-        if let Some(editor) = self.ui.editor.take() {
-            let config = editor.config;
-            let value = match config.config_type {
-                crate::schema::ConfigType::Int => match editor.input.parse::<i64>() {
-                    Ok(i) => Some(Value::Integer(i)),
-                    Err(_) => {
-                        self.notify("Invalid integer".to_string());
-                        None
-                    }
-                },
-                crate::schema::ConfigType::Hex => {
-                    let res = if editor.input.starts_with("0x") || editor.input.starts_with("0X") {
-                        i64::from_str_radix(&editor.input[2..], 16)
-                    } else {
-                        i64::from_str_radix(&editor.input, 16)
-                    };
-                    match res {
-                        Ok(i) => Some(Value::Integer(i)),
-                        Err(_) => {
-                            self.notify("Invalid hex value".to_string());
-                            None
-                        }
-                    }
-                }
-                crate::schema::ConfigType::String => Some(Value::String(editor.input.clone())),
-                _ => None,
-            };
+        let Some(editor) = &self.ui.editor else {
+            return;
+        };
+        let config = &editor.config;
+        let parsed = match config.config_type {
+            crate::schema::ConfigType::Int => parse_numeric(config, &editor.input)
+                .map(Value::Integer)
+                .ok_or_else(|| "Invalid integer".to_string()),
+            crate::schema::ConfigType::Hex => parse_numeric(config, &editor.input)
+                .map(Value::Integer)
+                .ok_or_else(|| "Invalid hex value".to_string()),
+            crate::schema::ConfigType::Float => editor
+                .input
+                .parse::<f64>()
+                .map(Value::Float)
+                .map_err(|_| "Invalid float".to_string()),
+            crate::schema::ConfigType::String => Ok(Value::String(editor.input.clone())),
+            crate::schema::ConfigType::StringList => Ok(Value::Array(
+                editor
+                    .input
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(|line| Value::String(line.to_string()))
+                    .collect(),
+            )),
+            _ => return,
+        };
+        let result = parsed.and_then(|val| {
+            config
+                .validate(&config.file_value(&val))
+                .map(|_| val)
+                .map_err(|e| format!("Error: {}", e))
+        });
 
-            if let Some(val) = value {
-                match config.validate(&val) {
-                    Ok(_) => {
-                        self.values.insert(config.name, val);
-                        self.is_dirty = true;
-                        self.update_evaluator();
-                        self.notify("Value updated".to_string());
-                    }
-                    Err(e) => {
-                        self.notify(format!("Error: {}", e));
-                    }
-                }
+        match result {
+            Ok(val) => {
+                let name = config.name.clone();
+                self.ui.editor = None;
+                self.record_history();
+                self.values.insert(name, val);
+                self.is_dirty = true;
+                self.update_evaluator();
+                self.notify("Value updated".to_string());
             }
+            Err(e) => self.notify(e),
         }
     }
 
+    /// Submits the editor input. A string list editor instead starts a new
+    /// line, and only submits on Enter at an empty line.
+    pub fn enter_input(&mut self) {
+        if let Some(editor) = &mut self.ui.editor {
+            let on_empty_line = editor.input.is_empty() || editor.input.ends_with('\n');
+            if editor.config.config_type == crate::schema::ConfigType::StringList && !on_empty_line
+            {
+                editor.input.push('\n');
+                return;
+            }
+        }
+        self.submit_input();
+    }
+
+    /// Moves a ranged int/hex editor value by `direction` steps, clamped to
+    /// the config's range. Typed input is honoured if it parses.
+    pub fn step_input(&mut self, direction: i64) {
+        let Some(editor) = &mut self.ui.editor else {
+            return;
+        };
+        let Some(range) = editor.config.range else {
+            return;
+        };
+        let current = parse_numeric(&editor.config, &editor.input)
+            .or(editor.numeric)
+            .unwrap_or(range.0);
+        let step = editor.config.step.unwrap_or(1);
+        let value = step_value(current, direction, step, range);
+        editor.numeric = Some(value);
+        editor.input = format_numeric(&editor.config, value);
+    }
+
     pub fn cancel_input(&mut self) {
         self.ui.editor = None;
     }
 
     pub fn save(&mut self) -> Result<()> {
-        config_io::save_config(&self.config_path, &self.values)?;
+        config_io::save_config_with(
+            &self.config_path,
+            &self.root_node,
+            &self.values,
+            self.prune_hidden,
+        )?;
         self.is_dirty = false;
         self.notify(format!("Config saved to {:?}", self.config_path));
         Ok(())
     }
 
     pub fn handle_event(&mut self, event: Event) -> io::Result<bool> {
-        if let Event::Key(key) = event {
-            return self.handle_key_event(key);
+        match event {
+            Event::Key(key) => return self.handle_key_event(key),
+            Event::Mouse(mouse) => self.handle_mouse_event(mouse),
+            Event::Resize(..) => self.ui.needs_clear = true,
+            _ => {}
         }
         Ok(false)
     }
 
+    fn handle_mouse_event(&mut self, mouse: event::MouseEvent) {
+        let has_popup = self.ui.notification.is_some()
+            || self.ui.help_item.is_some()
+            || self.ui.show_quit_confirm
+            || self.ui.editor.is_some()
+            || self.ui.search_active;
+        if has_popup {
+            return;
+        }
+
+        match mouse.kind {
+            MouseEventKind::ScrollDown => self.next(),
+            MouseEventKind::ScrollUp => self.previous(),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let (configs, children) = self.get_visible_items();
+                let (config_count, total) = (configs.len(), configs.len() + children.len());
+                let index = row_to_index(self.ui.list_area, self.ui.list_state.offset(), mouse.row)
+                    .filter(|&i| i < total && self.is_selectable(i));
+                let Some(index) = index else {
+                    return;
+                };
+
+                let now = Instant::now();
+                let is_double = matches!(
+                    self.ui.last_click,
+                    Some((at, last)) if last == index && now.duration_since(at) <= DOUBLE_CLICK_INTERVAL
+                );
+                self.ui.list_state.select(Some(index));
+
+                if is_double {
+                    self.ui.last_click = None;
+                    if index < config_count {
+                        self.toggle_bool();
+                    } else {
+                        self.enter();
+                    }
+                } else {
+                    self.ui.last_click = Some((now, index));
+                }
+            }
+            _ => {}
+        }
+    }
+
     fn handle_key_event(&mut self, key: event::KeyEvent) -> io::Result<bool> {
         if self.ui.notification.is_some() {
             self.clear_notification();
             return Ok(false);
         }
 
+        if self.ui.help_item.is_some() {
+            self.ui.help_item = None;
+            return Ok(false);
+        }
+
         if self.ui.show_quit_confirm {
             return self.handle_quit_confirm(key);
         }
 
         if self.ui.editor.is_some() {
             self.handle_editing_key(key);
+        } else if self.ui.search_active {
+            self.handle_search_key(key);
+        } else if self.ui.jump_query.is_some() {
+            self.handle_jump_key(key);
         } else {
             return self.handle_main_key(key);
         }
@@ -422,8 +902,10 @@ impl App {
             }
         } else {
             match key.code {
-                KeyCode::Enter => self.submit_input(),
+                KeyCode::Enter => self.enter_input(),
                 KeyCode::Esc => self.cancel_input(),
+                KeyCode::Up => self.step_input(1),
+                KeyCode::Down => self.step_input(-1),
                 KeyCode::Backspace => {
                     if let Some(editor) = &mut self.ui.editor {
                         editor.input.pop();
@@ -439,8 +921,49 @@ impl App {
         }
     }
 
+    fn handle_jump_key(&mut self, key: event::KeyEvent) {
+        let Some(query) = &mut self.ui.jump_query else {
+            return;
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let name = query.trim().to_string();
+                self.ui.jump_query = None;
+                if !name.is_empty() {
+                    self.jump_to(&name);
+                }
+            }
+            KeyCode::Esc => self.ui.jump_query = None,
+            KeyCode::Backspace => {
+                query.pop();
+            }
+            KeyCode::Char(c) => query.push(c),
+            _ => {}
+        }
+    }
+
+    fn handle_search_key(&mut self, key: event::KeyEvent) {
+        match key.code {
+            KeyCode::Enter => self.ui.search_active = false,
+            KeyCode::Esc => self.clear_search(),
+            KeyCode::Backspace => {
+                self.ui.search_query.pop();
+                self.ui.list_state.select(Some(0));
+            }
+            KeyCode::Char(c) => {
+                self.ui.search_query.push(c);
+                self.ui.list_state.select(Some(0));
+            }
+            _ => {}
+        }
+    }
+
     fn handle_main_key(&mut self, key: event::KeyEvent) -> io::Result<bool> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
         match key.code {
+            KeyCode::Char('r') | KeyCode::Char('y') if ctrl => self.redo(),
+            KeyCode::Char('z') if ctrl => self.undo(),
+            KeyCode::Char('u') => self.undo(),
             KeyCode::Char('q') => {
                 if self.is_dirty {
                     self.ui.show_quit_confirm = true;
@@ -451,7 +974,18 @@ impl App {
             KeyCode::Down | KeyCode::Char('j') => self.next(),
             KeyCode::Up | KeyCode::Char('k') => self.previous(),
             KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => self.enter(),
+            KeyCode::Esc if !self.ui.search_query.is_empty() => self.clear_search(),
             KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') => self.back(),
+            KeyCode::Char('/') => self.start_search(),
+            KeyCode::Char('g') => self.ui.jump_query = Some(String::new()),
+            KeyCode::Char('?') => self.show_help(),
+            KeyCode::Char('r') => self.reset_to_default(),
+            KeyCode::Char('v') => self.toggle_show_hidden(),
+            KeyCode::Char('x') => self.toggle_hex_display(),
+            KeyCode::Char('c') if !ctrl => self.copy_selected(false),
+            KeyCode::Char('C') => self.copy_selected(true),
+            KeyCode::Char('A') => self.set_all_bools(true),
+            KeyCode::Char('Z') => self.set_all_bools(false),
             KeyCode::Char(' ') | KeyCode::Char('y') | KeyCode::Char('i') => self.toggle_bool(),
             KeyCode::Char('s') => {
                 let _ = self.save();
@@ -462,14 +996,176 @@ impl App {
     }
 }
 
+/// Maps a terminal row to a list index for a bordered list drawn in `area`
+/// and scrolled by `offset`. Returns `None` for rows on or outside the border.
+pub fn row_to_index(area: Rect, offset: usize, row: u16) -> Option<usize> {
+    let first = area.y + 1;
+    let last = (area.y + area.height).checked_sub(1)?;
+    if row < first || row >= last {
+        return None;
+    }
+    Some(offset + (row - first) as usize)
+}
+
+/// The symbols `defined(NAME)` is true for in the editor. Saving writes every
+/// value back, so all of them count as explicitly set.
+fn defined_symbols(items: &[ConfigItem], values: &HashMap<String, Value>) -> HashSet<String> {
+    let explicit = values.keys().cloned().collect();
+    crate::evaluator::defined_symbols(items, values, &explicit)
+}
+
+/// Label shown for the empty selection of an optional choice.
+pub const NO_CHOICE_LABEL: &str = "(none)";
+
+/// Entries of the choice popup: the enabled options, preceded by `None`
+/// standing for "(none)" when the choice is optional.
+pub fn choice_entries<'a>(
+    config: &'a ConfigItem,
+    evaluator: &Evaluator,
+) -> Vec<Option<&'a ChoiceOption>> {
+    config
+        .optional
+        .then_some(None)
+        .into_iter()
+        .chain(config.enabled_options(evaluator).into_iter().map(Some))
+        .collect()
+}
+
+/// The text copied for `config`: its name, or with `with_value` the line
+/// that sets it in `.config`, e.g. `MAX_CONN = 64`.
+pub fn clipboard_text(config: &ConfigItem, value: Option<&Value>, with_value: bool) -> String {
+    match value.filter(|_| with_value) {
+        Some(Value::Integer(i)) => format!("{} = {}", config.name, format_numeric(config, *i)),
+        Some(value) => format!("{} = {}", config.name, value),
+        None => config.name.clone(),
+    }
+}
+
+/// Formats an int value for the menu list, in hex when `hex` is set.
+pub fn format_int(value: i64, hex: bool) -> String {
+    match (hex, value < 0) {
+        (false, _) => value.to_string(),
+        (true, false) => format!("0x{:x}", value),
+        (true, true) => format!("-0x{:x}", value.unsigned_abs()),
+    }
+}
+
+/// Formats an int/hex value the way the editor expects it back.
+fn format_numeric(config: &ConfigItem, value: i64) -> String {
+    match config.config_type {
+        crate::schema::ConfigType::Hex => format!("0x{:x}", value as u64),
+        _ => value.to_string(),
+    }
+}
+
+fn parse_numeric(config: &ConfigItem, input: &str) -> Option<i64> {
+    match config.config_type {
+        crate::schema::ConfigType::Hex => crate::schema::parse_hex(input)?.as_integer(),
+        _ => input.parse().ok(),
+    }
+}
+
+/// Moves `value` by `direction * step`, clamped to `[min, max]`.
+pub fn step_value(value: i64, direction: i64, step: i64, (min, max): (i64, i64)) -> i64 {
+    value
+        .saturating_add(direction.saturating_mul(step.max(1)))
+        .max(min)
+        .min(max)
+}
+
+/// Annotates every symbol in a `depends_on` expression with its current
+/// value, e.g. `ENABLE_NET && MAX > 10` becomes
+/// `ENABLE_NET=true && MAX=4 > 10`. Quoted string literals are left alone.
+pub fn annotate_expression(expr: &str, values: &HashMap<String, Value>) -> String {
+    let mut out = String::with_capacity(expr.len());
+    let mut ident = String::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    let flush = |ident: &mut String, out: &mut String| {
+        out.push_str(ident);
+        if let Some(val) = values.get(ident.as_str()) {
+            out.push('=');
+            out.push_str(&val.to_string());
+        }
+        ident.clear();
+    };
+
+    for c in expr.chars() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            out.push(c);
+        } else if c.is_alphanumeric() || c == '_' {
+            ident.push(c);
+        } else {
+            flush(&mut ident, &mut out);
+            in_string = c == '"';
+            out.push(c);
+        }
+    }
+    flush(&mut ident, &mut out);
+    out
+}
+
+/// Returns the child indices leading from `node` to the menu that defines
+/// the symbol `name` (compared case-insensitively).
+pub fn find_symbol_path(node: &ConfigNode, name: &str) -> Option<Vec<usize>> {
+    if node
+        .configs
+        .iter()
+        .any(|c| c.name.eq_ignore_ascii_case(name))
+    {
+        return Some(Vec::new());
+    }
+    node.children.iter().enumerate().find_map(|(i, child)| {
+        let mut path = find_symbol_path(child, name)?;
+        path.insert(0, i);
+        Some(path)
+    })
+}
+
+/// Case-insensitive substring match used by the TUI search filter.
+/// An empty query matches everything.
+pub fn matches_filter(query: &str, text: &str) -> bool {
+    query.is_empty()
+        || text
+            .to_ascii_lowercase()
+            .contains(&query.to_ascii_lowercase())
+}
+
 pub fn run(root_node: ConfigNode, config_path: PathBuf) -> Result<()> {
+    run_with_theme(root_node, config_path, Theme::default())
+}
+
+pub fn run_with_theme(root_node: ConfigNode, config_path: PathBuf, theme: Theme) -> Result<()> {
+    run_with_options(root_node, config_path, theme, false)
+}
+
+/// Like [`run_with_theme`], optionally pruning the values of hidden symbols
+/// on save, see [`App::prune_hidden`].
+pub fn run_with_options(
+    root_node: ConfigNode,
+    config_path: PathBuf,
+    theme: Theme,
+    prune_hidden: bool,
+) -> Result<()> {
+    // Load before taking over the terminal, so errors leave it usable.
+    let mut app = App::new(root_node, config_path)?;
+    app.theme = theme;
+    app.prune_hidden = prune_hidden;
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let app = App::new(root_node, config_path)?;
     let res = run_app(&mut terminal, app);
 
     disable_raw_mode()?;
@@ -489,34 +1185,39 @@ pub fn run(root_node: ConfigNode, config_path: PathBuf) -> Result<()> {
 
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
     loop {
-        terminal.draw(|f| ui::draw(f, &mut app))?;
+        draw_frame(terminal, &mut app)?;
 
         if app.handle_event(event::read()?)? {
+            let _ = app.position().save(&state::state_path(&app.config_path));
             return Ok(());
         }
     }
 }
 
+/// Draws the app, clearing the screen first after a resize. Popups are laid
+/// out against the frame area on every draw, so they follow the new size.
+fn draw_frame<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    if std::mem::take(&mut app.ui.needs_clear) {
+        terminal.autoresize()?;
+        terminal.clear()?;
+    }
+    terminal.draw(|f| ui::draw(f, app))?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::schema::{ConfigItem, ConfigNode, ConfigType};
+    use crate::schema::{ChoiceOption, ConfigItem, ConfigNode, ConfigType};
     use std::path::PathBuf;
 
     fn mock_app() -> App {
         let root = ConfigNode {
             desc: "Root".to_string(),
             configs: vec![ConfigItem {
-                name: "cfg1".to_string(),
-                config_type: ConfigType::Bool,
                 default: Some(toml::Value::Boolean(false)),
                 desc: "Desc 1".to_string(),
-                depends_on: None,
-                help: None,
-                options: None,
-                feature: None,
-                range: None,
-                regex: None,
+                ..ConfigItem::test_item("cfg1", ConfigType::Bool)
             }],
             children: vec![ConfigNode {
                 desc: "Child".to_string(),
@@ -524,13 +1225,54 @@ mod tests {
                 children: vec![],
                 path: "root.child".to_string(),
                 depends_on: None,
+                help: None,
+                exclusive_group: None,
+                file: String::new(),
             }],
             path: "root".to_string(),
             depends_on: None,
+            help: None,
+            exclusive_group: None,
+            file: String::new(),
         };
         App::new(root, PathBuf::from("dummy.toml")).unwrap()
     }
 
+    #[test]
+    fn test_first_run_starts_from_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("boards/devkit/.config");
+        let root = mock_app().root_node;
+        let mut app = App::new(root, config_path.clone()).unwrap();
+
+        assert!(!config_path.exists());
+        assert_eq!(app.values.get("cfg1"), Some(&Value::Boolean(false)));
+        assert_eq!(
+            app.ui.notification.as_deref(),
+            Some("No existing config; starting from defaults")
+        );
+
+        app.save().unwrap();
+        assert!(config_path.exists());
+    }
+
+    #[test]
+    fn test_modified_count_tracks_minimal_config() {
+        let mut app = mock_app();
+        assert_eq!(app.modified_count, 0);
+
+        app.ui.list_state.select(Some(0));
+        app.toggle_bool();
+        assert_eq!(app.modified_count, 1);
+        assert_eq!(
+            app.modified_count,
+            config_io::get_minimal_config(&app.values, &app.flattened_items, &app.evaluator).len()
+        );
+
+        app.undo();
+        assert_eq!(app.modified_count, 0);
+    }
+
     #[test]
     fn test_navigation_next_prev() {
         let mut app = mock_app();
@@ -557,4 +1299,692 @@ mod tests {
         app.back();
         assert_eq!(app.ui.current_node_path.len(), 0);
     }
+
+    #[test]
+    fn test_restore_position_clamps_stale_paths() {
+        let mut app = mock_app();
+        app.restore_position(&SavedPosition {
+            path: Vec::new(),
+            selected: 1,
+        });
+        assert_eq!(app.position().selected, 1);
+
+        app.restore_position(&SavedPosition {
+            path: vec![0, 3, 1],
+            selected: 5,
+        });
+        assert_eq!(
+            app.position(),
+            SavedPosition {
+                path: vec![0],
+                selected: 0,
+            }
+        );
+        assert_eq!(app.get_path_string(), "Root > Child");
+    }
+
+    #[test]
+    fn test_step_value_clamps() {
+        assert_eq!(step_value(10, 1, 1, (1, 20)), 11);
+        assert_eq!(step_value(10, -1, 5, (1, 20)), 5);
+        assert_eq!(step_value(18, 1, 5, (1, 20)), 20);
+        assert_eq!(step_value(3, -1, 5, (1, 20)), 1);
+        assert_eq!(step_value(i64::MAX, 1, 1, (0, i64::MAX)), i64::MAX);
+    }
+
+    #[test]
+    fn test_step_input_hex() {
+        let mut app = mock_app();
+        app.ui.editor = Some(Editor {
+            config: ConfigItem {
+                desc: "Base".to_string(),
+                range: Some((0x10, 0x40)),
+                step: Some(0x10),
+                ..ConfigItem::test_item("BASE", ConfigType::Hex)
+            },
+            input: "0x30".to_string(),
+            choice_state: ListState::default(),
+            numeric: None,
+        });
+
+        app.step_input(1);
+        assert_eq!(app.ui.editor.as_ref().unwrap().input, "0x40");
+        app.step_input(1);
+        assert_eq!(app.ui.editor.as_ref().unwrap().numeric, Some(0x40));
+        app.step_input(-1);
+        assert_eq!(app.ui.editor.as_ref().unwrap().input, "0x30");
+
+        app.submit_input();
+        assert_eq!(app.values.get("BASE"), Some(&Value::Integer(0x30)));
+    }
+
+    #[test]
+    fn test_set_all_bools_only_touches_visible_bools() {
+        let mut app = mock_app();
+        let item = |name: &str, config_type: ConfigType, depends_on: Option<&str>| ConfigItem {
+            desc: name.to_string(),
+            depends_on: depends_on.map(|s| s.to_string()),
+            ..ConfigItem::test_item(name, config_type)
+        };
+        app.root_node
+            .configs
+            .push(item("cfg2", ConfigType::Bool, None));
+        app.root_node
+            .configs
+            .push(item("cfg3", ConfigType::Bool, Some("false")));
+        app.root_node
+            .configs
+            .push(item("num", ConfigType::Int, None));
+
+        app.set_all_bools(true);
+        assert_eq!(app.values.get("cfg1"), Some(&Value::Boolean(true)));
+        assert_eq!(app.values.get("cfg2"), Some(&Value::Boolean(true)));
+        assert_eq!(app.values.get("cfg3"), None);
+        assert_eq!(app.values.get("num"), None);
+        assert!(app.is_dirty);
+
+        app.set_all_bools(false);
+        assert_eq!(app.values.get("cfg1"), Some(&Value::Boolean(false)));
+        app.undo();
+        assert_eq!(app.values.get("cfg2"), Some(&Value::Boolean(true)));
+    }
+
+    #[test]
+    fn test_find_symbol_path() {
+        let mut app = mock_app();
+        let deep = ConfigItem {
+            name: "DEEP".to_string(),
+            ..app.root_node.configs[0].clone()
+        };
+        app.root_node.children.push(ConfigNode {
+            desc: "Second".to_string(),
+            configs: vec![],
+            children: vec![ConfigNode {
+                desc: "Nested".to_string(),
+                configs: vec![deep],
+                children: vec![],
+                path: "root.second.nested".to_string(),
+                depends_on: None,
+                help: None,
+                exclusive_group: None,
+                file: String::new(),
+            }],
+            path: "root.second".to_string(),
+            depends_on: None,
+            help: None,
+            exclusive_group: None,
+            file: String::new(),
+        });
+
+        assert_eq!(find_symbol_path(&app.root_node, "cfg1"), Some(vec![]));
+        assert_eq!(find_symbol_path(&app.root_node, "deep"), Some(vec![1, 0]));
+        assert_eq!(find_symbol_path(&app.root_node, "MISSING"), None);
+
+        app.jump_to("DEEP");
+        assert_eq!(app.ui.current_node_path, vec![1, 0]);
+        assert_eq!(app.ui.list_state.selected(), Some(0));
+
+        app.jump_to("MISSING");
+        assert_eq!(
+            app.ui.notification.as_deref(),
+            Some("Symbol 'MISSING' not found")
+        );
+    }
+
+    #[test]
+    fn test_annotate_expression() {
+        let mut values = HashMap::new();
+        values.insert("ENABLE_NET".to_string(), Value::Boolean(true));
+        values.insert("MAX".to_string(), Value::Integer(4));
+        values.insert("MODE".to_string(), Value::String("PROD".to_string()));
+
+        assert_eq!(
+            annotate_expression("ENABLE_NET && MAX > 10", &values),
+            "ENABLE_NET=true && MAX=4 > 10"
+        );
+        assert_eq!(
+            annotate_expression("!UNKNOWN || MODE == \"MAX\"", &values),
+            "!UNKNOWN || MODE=\"PROD\" == \"MAX\""
+        );
+    }
+
+    #[test]
+    fn test_matches_filter() {
+        assert!(matches_filter("", "anything"));
+        assert!(matches_filter("net", "ENABLE_NET"));
+        assert!(matches_filter("Sock", "max sockets"));
+        assert!(!matches_filter("usb", "ENABLE_NET"));
+    }
+
+    #[test]
+    fn test_search_filters_visible_items() {
+        let mut app = mock_app();
+        app.ui.search_query = "child".to_string();
+        let (configs, children) = app.get_visible_items();
+        assert_eq!(configs.len(), 0);
+        assert_eq!(children.len(), 1);
+
+        // Selection 0 now maps to the filtered child, not the first config.
+        app.ui.list_state.select(Some(0));
+        app.enter();
+        assert_eq!(app.ui.current_node_path, vec![0]);
+        assert!(app.ui.search_query.is_empty());
+    }
+
+    #[test]
+    fn test_show_help_for_selected_config() {
+        let mut app = mock_app();
+        app.ui.list_state.select(Some(0));
+        app.show_help();
+        assert_eq!(
+            app.ui.help_item.as_ref().map(|c| c.name.as_str()),
+            Some("cfg1")
+        );
+
+        // Menus have no help popup.
+        app.ui.help_item = None;
+        app.ui.list_state.select(Some(1));
+        app.show_help();
+        assert!(app.ui.help_item.is_none());
+    }
+
+    #[test]
+    fn test_reset_to_default() {
+        let mut app = mock_app();
+        app.ui.list_state.select(Some(0));
+        app.toggle_bool();
+        assert_eq!(app.values.get("cfg1"), Some(&Value::Boolean(true)));
+
+        app.reset_to_default();
+        assert_eq!(app.values.get("cfg1"), Some(&Value::Boolean(false)));
+        assert!(app.is_dirty);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut app = mock_app();
+        let original = app.values.clone();
+        app.ui.list_state.select(Some(0));
+
+        app.toggle_bool();
+        app.toggle_bool();
+        app.toggle_bool();
+        assert_eq!(app.values.get("cfg1"), Some(&Value::Boolean(true)));
+        assert_eq!(app.ui.undo_stack.len(), 3);
+
+        app.undo();
+        app.undo();
+        app.undo();
+        assert_eq!(app.values, original);
+        assert!(app.ui.undo_stack.is_empty());
+
+        app.redo();
+        assert_eq!(app.values.get("cfg1"), Some(&Value::Boolean(true)));
+
+        // A fresh edit invalidates the redo history.
+        app.toggle_bool();
+        assert!(app.ui.redo_stack.is_empty());
+    }
+
+    #[test]
+    fn test_undo_history_depth() {
+        let mut app = mock_app();
+        app.ui.history_depth = 2;
+        app.ui.list_state.select(Some(0));
+        for _ in 0..5 {
+            app.toggle_bool();
+        }
+        assert_eq!(app.ui.undo_stack.len(), 2);
+    }
+
+    #[test]
+    fn test_choice_editor_preselects_current_value() {
+        let root = ConfigNode {
+            desc: "Root".to_string(),
+            configs: vec![ConfigItem {
+                default: Some(toml::Value::String("B".to_string())),
+                desc: "Mode".to_string(),
+                options: Some(vec!["A".into(), "B".into(), "C".into()]),
+                ..ConfigItem::test_item("MODE", ConfigType::Choice)
+            }],
+            children: vec![],
+            path: "root".to_string(),
+            depends_on: None,
+            help: None,
+            exclusive_group: None,
+            file: String::new(),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::new(root, dir.path().join(".config")).unwrap();
+        app.ui.list_state.select(Some(0));
+        app.toggle_bool();
+
+        let editor = app.ui.editor.as_ref().unwrap();
+        assert_eq!(editor.choice_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn test_choice_editor_stores_value_not_label() {
+        let mut app = mock_app();
+        app.root_node.configs[0] = ConfigItem {
+            name: "ARCH".to_string(),
+            config_type: ConfigType::Choice,
+            default: Some(toml::Value::String("riscv64".to_string())),
+            options: Some(vec![
+                "riscv64".into(),
+                ChoiceOption {
+                    value: "x86_64".to_string(),
+                    depends_on: None,
+                    label: Some("Intel/AMD 64-bit".to_string()),
+                },
+            ]),
+            ..app.root_node.configs[0].clone()
+        };
+        app.ui.list_state.select(Some(0));
+
+        app.toggle_bool();
+        app.next_choice();
+        app.submit_choice();
+        assert_eq!(
+            app.values.get("ARCH"),
+            Some(&toml::Value::String("x86_64".to_string()))
+        );
+        assert_eq!(
+            app.ui.notification.as_deref(),
+            Some("Selected: Intel/AMD 64-bit")
+        );
+    }
+
+    #[test]
+    fn test_optional_choice_offers_none() {
+        let root = ConfigNode {
+            desc: "Root".to_string(),
+            configs: vec![ConfigItem {
+                default: Some(toml::Value::String("rtt".to_string())),
+                desc: "Logger".to_string(),
+                options: Some(vec!["uart".into(), "rtt".into()]),
+                optional: true,
+                ..ConfigItem::test_item("LOGGER", ConfigType::Choice)
+            }],
+            children: vec![],
+            path: "root".to_string(),
+            depends_on: None,
+            help: None,
+            exclusive_group: None,
+            file: String::new(),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::new(root, dir.path().join(".config")).unwrap();
+        app.ui.list_state.select(Some(0));
+        app.toggle_bool();
+        assert_eq!(
+            app.ui.editor.as_ref().unwrap().choice_state.selected(),
+            Some(2)
+        );
+
+        app.next_choice();
+        app.submit_choice();
+        assert_eq!(
+            app.values.get("LOGGER"),
+            Some(&toml::Value::String(String::new()))
+        );
+        assert_eq!(app.ui.notification.as_deref(), Some("Selected: (none)"));
+    }
+
+    #[test]
+    fn test_submit_input_rejects_out_of_range() {
+        let root = ConfigNode {
+            desc: "Root".to_string(),
+            configs: vec![ConfigItem {
+                default: Some(toml::Value::Integer(80)),
+                desc: "Port".to_string(),
+                range: Some((1, 1024)),
+                ..ConfigItem::test_item("PORT", ConfigType::Int)
+            }],
+            children: vec![],
+            path: "root".to_string(),
+            depends_on: None,
+            help: None,
+            exclusive_group: None,
+            file: String::new(),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::new(root, dir.path().join(".config")).unwrap();
+        app.ui.list_state.select(Some(0));
+        app.toggle_bool();
+        app.ui.editor.as_mut().unwrap().input = "70000".to_string();
+        app.submit_input();
+
+        assert_eq!(app.values.get("PORT"), Some(&Value::Integer(80)));
+        assert!(app.ui.editor.is_some());
+        assert!(app
+            .ui
+            .notification
+            .as_ref()
+            .unwrap()
+            .contains("out of range"));
+        assert!(!app.is_dirty);
+    }
+
+    #[test]
+    fn test_submit_input_rejects_regex_mismatch() -> io::Result<()> {
+        let mut app = mock_app();
+        app.root_node.configs[0] = ConfigItem {
+            name: "HOSTNAME".to_string(),
+            config_type: ConfigType::String,
+            default: Some(toml::Value::String("anaxa".to_string())),
+            regex: Some("^[a-z]+$".to_string()),
+            ..app.root_node.configs[0].clone()
+        };
+        app.values
+            .insert("HOSTNAME".to_string(), Value::String("anaxa".to_string()));
+        app.ui.list_state.select(Some(0));
+
+        app.toggle_bool();
+        app.ui.editor.as_mut().unwrap().input = "Bad-Host".to_string();
+        app.submit_input();
+
+        assert_eq!(
+            app.values.get("HOSTNAME"),
+            Some(&Value::String("anaxa".to_string()))
+        );
+        assert!(app.ui.editor.is_some());
+        assert!(app.ui.notification.as_ref().unwrap().contains("^[a-z]+$"));
+        assert!(!app.is_dirty);
+
+        app.ui.notification = None;
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 30))?;
+        draw_frame(&mut terminal, &mut app)?;
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(screen.contains("Edit HOSTNAME (String) /^[a-z]+$/"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_submit_input_checks_hex_range() {
+        let mut app = mock_app();
+        app.root_node.configs[0] = ConfigItem {
+            name: "BASE".to_string(),
+            config_type: ConfigType::Hex,
+            default: Some(toml::Value::Integer(0x1000)),
+            range: Some((0x1000, 0xFFFF)),
+            ..app.root_node.configs[0].clone()
+        };
+        app.ui.list_state.select(Some(0));
+
+        app.toggle_bool();
+        app.ui.editor.as_mut().unwrap().input = "0x10000".to_string();
+        app.submit_input();
+        assert!(app.ui.editor.is_some());
+        assert!(app
+            .ui
+            .notification
+            .as_ref()
+            .unwrap()
+            .contains("out of range [0x1000, 0xffff]"));
+        assert_eq!(app.values.get("BASE"), None);
+
+        app.ui.editor.as_mut().unwrap().input = "0x2000".to_string();
+        app.submit_input();
+        assert!(app.ui.editor.is_none());
+        assert_eq!(app.values.get("BASE"), Some(&Value::Integer(0x2000)));
+    }
+
+    #[test]
+    fn test_exclusive_group_disables_peers() {
+        let mut app = mock_app();
+        let cfg2 = ConfigItem {
+            name: "cfg2".to_string(),
+            ..app.root_node.configs[0].clone()
+        };
+        app.root_node.configs.push(cfg2);
+        app.root_node.exclusive_group = Some(vec!["cfg1".to_string(), "cfg2".to_string()]);
+        app.values.insert("cfg2".to_string(), Value::Boolean(false));
+
+        app.ui.list_state.select(Some(0));
+        app.toggle_bool();
+        assert_eq!(app.values.get("cfg1"), Some(&Value::Boolean(true)));
+
+        app.ui.list_state.select(Some(1));
+        app.toggle_bool();
+        assert_eq!(app.values.get("cfg2"), Some(&Value::Boolean(true)));
+        assert_eq!(app.values.get("cfg1"), Some(&Value::Boolean(false)));
+        assert!(config_io::check_exclusive_groups(
+            &parser::flatten_configs(&app.root_node),
+            &app.values
+        )
+        .is_ok());
+
+        app.toggle_bool();
+        assert_eq!(app.values.get("cfg2"), Some(&Value::Boolean(false)));
+        assert_eq!(app.values.get("cfg1"), Some(&Value::Boolean(false)));
+    }
+
+    #[test]
+    fn test_string_list_editor_adds_and_removes_lines() {
+        let mut app = mock_app();
+        app.root_node.configs[0] = ConfigItem {
+            name: "ORIGINS".to_string(),
+            config_type: ConfigType::StringList,
+            default: None,
+            ..app.root_node.configs[0].clone()
+        };
+        app.values.insert(
+            "ORIGINS".to_string(),
+            Value::Array(vec!["a.com".into(), "b.com".into()]),
+        );
+        app.ui.list_state.select(Some(0));
+
+        app.toggle_bool();
+        assert_eq!(app.ui.editor.as_ref().unwrap().input, "a.com\nb.com");
+
+        app.enter_input();
+        assert_eq!(app.ui.editor.as_ref().unwrap().input, "a.com\nb.com\n");
+
+        app.ui.editor.as_mut().unwrap().input = "a.com\nc.com\n".to_string();
+        app.enter_input();
+        assert!(app.ui.editor.is_none());
+        assert_eq!(
+            app.values.get("ORIGINS"),
+            Some(&Value::Array(vec!["a.com".into(), "c.com".into()]))
+        );
+    }
+
+    #[test]
+    fn test_show_hidden_lists_but_skips_unmet_symbols() {
+        let mut app = mock_app();
+        app.root_node.configs.push(ConfigItem {
+            desc: "Desc 2".to_string(),
+            depends_on: Some("cfg1".to_string()),
+            ..ConfigItem::test_item("cfg2", ConfigType::Bool)
+        });
+        app.update_evaluator();
+
+        assert_eq!(app.get_visible_items().0.len(), 1);
+
+        app.toggle_show_hidden();
+        assert_eq!(app.get_visible_items().0.len(), 2);
+        assert!(app.is_selectable(0));
+        assert!(!app.is_selectable(1));
+
+        // Navigation jumps over the dimmed row straight to the child menu.
+        app.ui.list_state.select(Some(0));
+        app.next();
+        assert_eq!(app.ui.list_state.selected(), Some(2));
+        app.previous();
+        assert_eq!(app.ui.list_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_visible_if_hides_prompt_but_keeps_value() {
+        let mut app = mock_app();
+        let forced = ConfigItem {
+            name: "FORCED".to_string(),
+            visible_if: Some("false".to_string()),
+            ..app.root_node.configs[0].clone()
+        };
+        let unmet = ConfigItem {
+            name: "UNMET".to_string(),
+            depends_on: Some("false".to_string()),
+            ..app.root_node.configs[0].clone()
+        };
+        app.root_node.configs.push(forced);
+        app.root_node.configs.push(unmet);
+        app.values
+            .insert("FORCED".to_string(), Value::Boolean(true));
+        app.values.insert("UNMET".to_string(), Value::Boolean(true));
+        app.update_evaluator();
+
+        let (configs, _) = app.get_visible_items();
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].name, "cfg1");
+
+        // Pruning on save drops only the symbol with unmet dependencies.
+        let dir = tempfile::tempdir().unwrap();
+        app.config_path = dir.path().join(".config");
+        app.prune_hidden = true;
+        app.save().unwrap();
+        let saved: toml::Table =
+            toml::from_str(&std::fs::read_to_string(&app.config_path).unwrap()).unwrap();
+        assert_eq!(saved.get("FORCED"), Some(&Value::Boolean(true)));
+        assert!(!saved.contains_key("UNMET"));
+        assert!(app.values.contains_key("UNMET"));
+    }
+
+    #[test]
+    fn test_resize_requests_redraw() -> io::Result<()> {
+        let mut app = mock_app();
+        app.notify("Saved".to_string());
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(80, 24))?;
+        draw_frame(&mut terminal, &mut app)?;
+
+        terminal.backend_mut().resize(60, 30);
+        assert!(!app.handle_event(Event::Resize(60, 30))?);
+        assert!(app.ui.needs_clear);
+
+        draw_frame(&mut terminal, &mut app)?;
+        assert!(!app.ui.needs_clear);
+        let buffer = terminal.backend().buffer();
+        assert_eq!(buffer.area, Rect::new(0, 0, 60, 30));
+        let screen: String = buffer.content().iter().map(|c| c.symbol()).collect();
+        assert!(screen.contains("Saved"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hex_display_toggle() {
+        assert_eq!(format_int(255, false), "255");
+        assert_eq!(format_int(255, true), "0xff");
+        assert_eq!(format_int(-16, true), "-0x10");
+        assert_eq!(format_int(i64::MIN, true), "-0x8000000000000000");
+
+        let mut app = mock_app();
+        app.root_node.configs[0] = ConfigItem {
+            name: "IRQ_MASK".to_string(),
+            config_type: ConfigType::Int,
+            default: Some(toml::Value::Integer(255)),
+            ..app.root_node.configs[0].clone()
+        };
+        app.values
+            .insert("IRQ_MASK".to_string(), toml::Value::Integer(255));
+        app.ui.list_state.select(Some(0));
+
+        app.toggle_hex_display();
+        assert!(app.ui.hex_display.contains("IRQ_MASK"));
+        assert_eq!(app.values.get("IRQ_MASK"), Some(&toml::Value::Integer(255)));
+        assert!(!app.is_dirty);
+        app.toggle_hex_display();
+        assert!(app.ui.hex_display.is_empty());
+    }
+
+    #[test]
+    fn test_clipboard_text() {
+        let config = ConfigItem::test_item("LOAD_ADDR", ConfigType::Hex);
+        let addr = toml::Value::Integer(0x8000);
+        assert_eq!(clipboard_text(&config, Some(&addr), false), "LOAD_ADDR");
+        assert_eq!(
+            clipboard_text(&config, Some(&addr), true),
+            "LOAD_ADDR = 0x8000"
+        );
+        assert_eq!(clipboard_text(&config, None, true), "LOAD_ADDR");
+
+        let config = ConfigItem::test_item("LOAD_ADDR", ConfigType::String);
+        let host = toml::Value::String("example.com".to_string());
+        assert_eq!(
+            clipboard_text(&config, Some(&host), true),
+            "LOAD_ADDR = \"example.com\""
+        );
+    }
+
+    #[test]
+    fn test_ctrl_c_does_not_copy() -> io::Result<()> {
+        let mut app = mock_app();
+        app.clear_notification();
+        let ctrl_c = event::KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert!(!app.handle_main_key(ctrl_c)?);
+        assert!(app.ui.notification.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_row_to_index() {
+        let area = Rect::new(0, 3, 80, 10);
+        assert_eq!(row_to_index(area, 0, 3), None); // top border
+        assert_eq!(row_to_index(area, 0, 4), Some(0));
+        assert_eq!(row_to_index(area, 0, 11), Some(7));
+        assert_eq!(row_to_index(area, 0, 12), None); // bottom border
+        assert_eq!(row_to_index(area, 5, 4), Some(5));
+        assert_eq!(row_to_index(Rect::default(), 0, 0), None);
+    }
+
+    #[test]
+    fn test_choice_editor_skips_disabled_options() {
+        let root = ConfigNode {
+            desc: "Root".to_string(),
+            configs: vec![
+                ConfigItem {
+                    default: Some(toml::Value::Boolean(false)),
+                    desc: "Linux".to_string(),
+                    ..ConfigItem::test_item("LINUX", ConfigType::Bool)
+                },
+                ConfigItem {
+                    default: Some(toml::Value::String("poll".to_string())),
+                    desc: "Backend".to_string(),
+                    options: Some(vec![
+                        "poll".into(),
+                        ChoiceOption {
+                            value: "epoll".to_string(),
+                            depends_on: Some("LINUX".to_string()),
+                            label: None,
+                        },
+                        "select".into(),
+                    ]),
+                    ..ConfigItem::test_item("BACKEND", ConfigType::Choice)
+                },
+            ],
+            children: vec![],
+            path: "root".to_string(),
+            depends_on: None,
+            help: None,
+            exclusive_group: None,
+            file: String::new(),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::new(root, dir.path().join(".config")).unwrap();
+        app.ui.list_state.select(Some(1));
+        app.toggle_bool();
+        app.next_choice();
+        app.submit_choice();
+
+        assert_eq!(
+            app.values.get("BACKEND"),
+            Some(&Value::String("select".to_string()))
+        );
+    }
 }
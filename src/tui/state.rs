@@ -0,0 +1,91 @@
+//! The menu position remembered between `menuconfig` sessions.
+
+use crate::schema::ConfigNode;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the state file, kept next to the config file.
+pub const STATE_FILE_NAME: &str = ".anaxa_state";
+
+/// Where the user was when they last quit the TUI.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedPosition {
+    /// Child indices from the root to the open menu.
+    pub path: Vec<usize>,
+    /// Selected row in that menu.
+    pub selected: usize,
+}
+
+impl SavedPosition {
+    /// Reads a saved position. A missing or malformed file yields `None`.
+    pub fn load(path: &Path) -> Option<Self> {
+        toml::from_str(&fs::read_to_string(path).ok()?).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// The state file used for the config at `config_path`.
+pub fn state_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(STATE_FILE_NAME)
+}
+
+/// The longest prefix of `path` that still leads to a menu in `root`, so a
+/// position saved before the tree changed can't point past it.
+pub fn clamp_node_path(root: &ConfigNode, path: &[usize]) -> Vec<usize> {
+    let mut node = root;
+    let mut clamped = Vec::new();
+    for &index in path {
+        let Some(child) = node.children.get(index) else {
+            break;
+        };
+        clamped.push(index);
+        node = child;
+    }
+    clamped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu(children: Vec<ConfigNode>) -> ConfigNode {
+        ConfigNode {
+            desc: String::new(),
+            configs: Vec::new(),
+            children,
+            path: String::new(),
+            depends_on: None,
+            help: None,
+            exclusive_group: None,
+            file: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_clamp_node_path() -> Result<()> {
+        // Root -> [A -> [A1], B]
+        let root = menu(vec![menu(vec![menu(Vec::new())]), menu(Vec::new())]);
+
+        assert_eq!(clamp_node_path(&root, &[0, 0]), vec![0, 0]);
+        assert_eq!(clamp_node_path(&root, &[0, 0, 3, 1]), vec![0, 0]);
+        assert_eq!(clamp_node_path(&root, &[1, 0]), vec![1]);
+        assert_eq!(clamp_node_path(&root, &[5]), Vec::<usize>::new());
+
+        let dir = tempfile::tempdir()?;
+        let path = state_path(&dir.path().join(".config"));
+        assert_eq!(SavedPosition::load(&path), None);
+        let saved = SavedPosition {
+            path: vec![0, 0],
+            selected: 2,
+        };
+        saved.save(&path)?;
+        assert_eq!(SavedPosition::load(&path), Some(saved));
+        Ok(())
+    }
+}
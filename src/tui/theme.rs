@@ -0,0 +1,127 @@
+use ratatui::style::{Color, Modifier, Style};
+
+/// Colors used by the TUI, selectable by name with `menuconfig --theme`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Regular text such as symbol names.
+    pub fg: Color,
+    /// Secondary text: descriptions, labels and breadcrumbs.
+    pub muted: Color,
+    /// Titles, popup borders and enabled bools.
+    pub accent: Color,
+    /// Submenu entries.
+    pub menu: Color,
+    /// The arrow after a submenu entry.
+    pub link: Color,
+    /// Numeric values, the input popup and warnings.
+    pub value: Color,
+    /// String and choice values.
+    pub string: Color,
+    /// Background of the selected row.
+    pub highlight: Color,
+    /// Disabled bools and hidden or deprecated symbols.
+    pub disabled: Color,
+    /// The unsaved-changes prompt.
+    pub alert: Color,
+}
+
+impl Theme {
+    /// Names accepted by [`Theme::from_name`].
+    pub const NAMES: [&'static str; 3] = ["default", "mono", "solarized"];
+
+    pub const DEFAULT: Theme = Theme {
+        fg: Color::White,
+        muted: Color::Gray,
+        accent: Color::Cyan,
+        menu: Color::Magenta,
+        link: Color::Blue,
+        value: Color::Yellow,
+        string: Color::Green,
+        highlight: Color::Indexed(237),
+        disabled: Color::DarkGray,
+        alert: Color::LightRed,
+    };
+
+    /// Uses the terminal's own colors only; emphasis comes from modifiers.
+    pub const MONO: Theme = Theme {
+        fg: Color::Reset,
+        muted: Color::Reset,
+        accent: Color::Reset,
+        menu: Color::Reset,
+        link: Color::Reset,
+        value: Color::Reset,
+        string: Color::Reset,
+        highlight: Color::Reset,
+        disabled: Color::Reset,
+        alert: Color::Reset,
+    };
+
+    pub const SOLARIZED: Theme = Theme {
+        fg: Color::Rgb(0x93, 0xa1, 0xa1),
+        muted: Color::Rgb(0x83, 0x94, 0x96),
+        accent: Color::Rgb(0x2a, 0xa1, 0x98),
+        menu: Color::Rgb(0x6c, 0x71, 0xc4),
+        link: Color::Rgb(0x26, 0x8b, 0xd2),
+        value: Color::Rgb(0xb5, 0x89, 0x00),
+        string: Color::Rgb(0x85, 0x99, 0x00),
+        highlight: Color::Rgb(0x07, 0x36, 0x42),
+        disabled: Color::Rgb(0x58, 0x6e, 0x75),
+        alert: Color::Rgb(0xdc, 0x32, 0x2f),
+    };
+
+    pub fn from_name(name: &str) -> Option<Theme> {
+        match name {
+            "default" => Some(Self::DEFAULT),
+            "mono" => Some(Self::MONO),
+            "solarized" => Some(Self::SOLARIZED),
+            _ => None,
+        }
+    }
+
+    /// Style of the selected row in lists.
+    pub fn highlight_style(&self) -> Style {
+        let style = if self.highlight == Color::Reset {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().bg(self.highlight)
+        };
+        style.add_modifier(Modifier::BOLD)
+    }
+
+    /// Dark text on a `bg` background, or reversed video when `bg` is the
+    /// terminal default.
+    pub fn badge(&self, bg: Color) -> Style {
+        if bg == Color::Reset {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().fg(Color::Black).bg(bg)
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named_themes() {
+        for name in Theme::NAMES {
+            assert!(Theme::from_name(name).is_some(), "{}", name);
+        }
+        assert_eq!(Theme::from_name("default"), Some(Theme::default()));
+        assert_eq!(Theme::from_name("neon"), None);
+
+        let mono = Theme::MONO.highlight_style();
+        assert!(mono.add_modifier.contains(Modifier::REVERSED));
+        assert_eq!(
+            Theme::DEFAULT.highlight_style().bg,
+            Some(Color::Indexed(237))
+        );
+    }
+}
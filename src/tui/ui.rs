@@ -1,18 +1,23 @@
-use crate::schema::ConfigType;
-use crate::tui::App;
+use crate::graph::ConfigGraph;
+use crate::schema::{string_list, ChoiceOption, ConfigItem, ConfigType};
+use crate::tui::theme::Theme;
+use crate::tui::{
+    annotate_expression, choice_entries, format_int, format_numeric, App, NO_CHOICE_LABEL,
+};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 
 pub fn draw(f: &mut Frame, app: &mut App) {
+    let header_height = if node_help(app).is_some() { 4 } else { 3 };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3),
+            Constraint::Length(header_height),
             Constraint::Min(0),
             Constraint::Length(3),
         ])
@@ -36,36 +41,60 @@ pub fn draw(f: &mut Frame, app: &mut App) {
         }
     }
 
+    if let Some(item) = &app.ui.help_item {
+        let dependents = ConfigGraph::build(&app.flattened_items)
+            .map(|g| g.dependents(&item.name))
+            .unwrap_or_default();
+        draw_help_popup(f, item, &dependents, &app.theme);
+    }
+
     if let Some(msg) = &app.ui.notification {
-        draw_notification(f, msg);
+        draw_notification(f, msg, &app.theme);
     }
 
     if app.ui.show_quit_confirm {
-        draw_quit_confirm(f);
+        draw_quit_confirm(f, &app.theme);
     }
 }
 
 fn draw_header(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let breadcrumbs = app.get_path_string();
-    let header_text = vec![Line::from(vec![
+    let mut header_text = vec![Line::from(vec![
         Span::styled(
             " ANAXA BUILDER ",
             Style::default()
-                .fg(Color::Cyan)
+                .fg(theme.accent)
                 .add_modifier(Modifier::BOLD),
         ),
         Span::raw(" | "),
-        Span::styled(breadcrumbs, Style::default().fg(Color::Gray)),
+        Span::styled(breadcrumbs, Style::default().fg(theme.muted)),
     ])];
+    if let Some(help) = node_help(app) {
+        header_text.push(Line::from(Span::styled(
+            format!(" {}", help),
+            Style::default()
+                .fg(theme.muted)
+                .add_modifier(Modifier::ITALIC),
+        )));
+    }
 
     let header = Paragraph::new(header_text).block(Block::default().borders(Borders::ALL));
     f.render_widget(header, area);
 }
 
 fn draw_main(f: &mut Frame, app: &mut App, area: Rect) {
+    app.ui.list_area = area;
+    let theme = app.theme;
     let (configs, children) = app.get_visible_items();
     let mut items = Vec::new();
 
+    let selected = app.ui.list_state.selected().unwrap_or(0);
+    let breadcrumb = configs
+        .get(selected)
+        .and_then(|c| c.depends_on.as_ref())
+        .map(|expr| format!(" depends on: {} ", annotate_expression(expr, &app.values)));
+
     for config in configs {
         let val = app.values.get(&config.name);
         let (val_str, val_style) = match config.config_type {
@@ -74,85 +103,238 @@ fn draw_main(f: &mut Frame, app: &mut App, area: Rect) {
                     (
                         "[X]".to_string(),
                         Style::default()
-                            .fg(Color::Cyan)
+                            .fg(theme.accent)
                             .add_modifier(Modifier::BOLD),
                     )
                 } else {
-                    ("[ ]".to_string(), Style::default().fg(Color::DarkGray))
+                    ("[ ]".to_string(), Style::default().fg(theme.disabled))
                 }
             }
             ConfigType::Int => (
-                val.and_then(|v| v.as_integer()).unwrap_or(0).to_string(),
-                Style::default().fg(Color::Yellow),
+                format_int(
+                    val.and_then(|v| v.as_integer()).unwrap_or(0),
+                    app.ui.hex_display.contains(&config.name),
+                ),
+                Style::default().fg(theme.value),
             ),
             ConfigType::Hex => (
-                format!("0x{:x}", val.and_then(|v| v.as_integer()).unwrap_or(0)),
-                Style::default().fg(Color::Yellow),
+                format!(
+                    "0x{:x}",
+                    val.and_then(crate::schema::hex_value).unwrap_or(0)
+                ),
+                Style::default().fg(theme.value),
+            ),
+            ConfigType::Float => (
+                val.and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+                    .unwrap_or(0.0)
+                    .to_string(),
+                Style::default().fg(theme.value),
             ),
             ConfigType::String | ConfigType::Choice => (
                 val.and_then(|v| v.as_str()).unwrap_or("").to_string(),
-                Style::default().fg(Color::Green),
+                Style::default().fg(theme.string),
+            ),
+            ConfigType::StringList => (
+                format!(
+                    "[{}]",
+                    val.and_then(string_list).unwrap_or_default().join(", ")
+                ),
+                Style::default().fg(theme.string),
             ),
         };
 
-        items.push(ListItem::new(Line::from(vec![
-            Span::styled(
-                format!("{:<30}", config.name),
-                Style::default().fg(Color::White),
-            ),
-            Span::styled(format!(" {} ", val_str), val_style),
-            Span::styled(
-                format!(" - {}", config.desc),
-                Style::default().fg(Color::Gray),
-            ),
-        ])));
+        let enabled = app.is_visible_config(config);
+        let dim = |style: Style| if enabled { style } else { hidden_style(&theme) };
+        let query = &app.ui.search_query;
+        let name_style = if config.deprecated.is_some() {
+            Style::default()
+                .fg(theme.disabled)
+                .add_modifier(Modifier::CROSSED_OUT)
+        } else {
+            Style::default().fg(theme.fg)
+        };
+        let mut spans = highlight(
+            format!("{:<30}", config.name),
+            query,
+            dim(name_style),
+            &theme,
+        );
+        spans.push(Span::styled(format!(" {} ", val_str), dim(val_style)));
+        spans.push(Span::styled(" - ", dim(Style::default().fg(theme.muted))));
+        spans.extend(highlight(
+            config.desc.clone(),
+            query,
+            dim(Style::default().fg(theme.muted)),
+            &theme,
+        ));
+        if !enabled {
+            if let Some(dep) = &config.depends_on {
+                spans.push(Span::styled(
+                    format!("  (depends on: {})", dep),
+                    hidden_style(&theme),
+                ));
+            }
+        }
+        items.push(ListItem::new(Line::from(spans)));
     }
 
     for child in children {
-        items.push(ListItem::new(Line::from(vec![
-            Span::styled(
-                format!("{:<30}", child.desc),
-                Style::default()
-                    .fg(Color::Magenta)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(" ➔ ", Style::default().fg(Color::Blue)),
-        ])));
+        let enabled = app.is_visible_node(child);
+        let style = if enabled {
+            Style::default().fg(theme.menu).add_modifier(Modifier::BOLD)
+        } else {
+            hidden_style(&theme)
+        };
+        let mut spans = highlight(
+            format!("{:<30}", child.desc),
+            &app.ui.search_query,
+            style,
+            &theme,
+        );
+        if enabled {
+            spans.push(Span::styled(" ➔ ", Style::default().fg(theme.link)));
+        } else if let Some(dep) = &child.depends_on {
+            spans.push(Span::styled(
+                format!("  (depends on: {})", dep),
+                hidden_style(&theme),
+            ));
+        }
+        items.push(ListItem::new(Line::from(spans)));
     }
 
-    let title = format!(" Configuration {} ", if app.is_dirty { "*" } else { "" });
+    let mut title = format!(" Configuration {} ", if app.is_dirty { "*" } else { "" });
+    if app.ui.show_hidden {
+        title.push_str("[all] ");
+    }
+    if app.ui.search_active || !app.ui.search_query.is_empty() {
+        title.push_str(&format!(
+            "[/{}{}] ",
+            app.ui.search_query,
+            if app.ui.search_active { "_" } else { "" }
+        ));
+    }
+    if let Some(query) = &app.ui.jump_query {
+        title.push_str(&format!("[go to: {}_] ", query));
+    }
+    let mut block = Block::default().borders(Borders::ALL).title(title);
+    if let Some(breadcrumb) = breadcrumb {
+        block = block.title_bottom(Line::from(Span::styled(
+            breadcrumb,
+            Style::default().fg(theme.muted),
+        )));
+    }
     let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title(title))
-        .highlight_style(
-            Style::default()
-                .bg(Color::Indexed(237))
-                .add_modifier(Modifier::BOLD),
-        )
+        .block(block)
+        .highlight_style(theme.highlight_style())
         .highlight_symbol("▶ ");
 
     f.render_stateful_widget(list, area, &mut app.ui.list_state);
 }
 
+/// First line of the current menu's help text, if it has any.
+fn node_help(app: &App) -> Option<&str> {
+    app.get_current_node()
+        .help
+        .as_deref()
+        .and_then(|help| help.lines().map(str::trim).find(|line| !line.is_empty()))
+}
+
+/// Style for symbols listed in show-hidden mode whose dependencies are unmet.
+fn hidden_style(theme: &Theme) -> Style {
+    Style::default()
+        .fg(theme.disabled)
+        .add_modifier(Modifier::DIM)
+}
+
+/// Splits `text` into spans, emphasizing every case-insensitive occurrence of `query`.
+fn highlight(text: String, query: &str, style: Style, theme: &Theme) -> Vec<Span<'static>> {
+    if query.is_empty() {
+        return vec![Span::styled(text, style)];
+    }
+
+    let haystack = text.to_ascii_lowercase();
+    let needle = query.to_ascii_lowercase();
+    let match_style = style.patch(theme.badge(theme.value));
+    let mut spans = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = haystack[pos..].find(&needle) {
+        let start = pos + offset;
+        let end = start + needle.len();
+        if start > pos {
+            spans.push(Span::styled(text[pos..start].to_string(), style));
+        }
+        spans.push(Span::styled(text[start..end].to_string(), match_style));
+        pos = end;
+    }
+    if pos < text.len() {
+        spans.push(Span::styled(text[pos..].to_string(), style));
+    }
+    spans
+}
+
 fn draw_input_popup(f: &mut Frame, app: &App) {
+    let theme = &app.theme;
     if let Some(editor) = &app.ui.editor {
         let area = centered_rect(60, 20, f.area());
         f.render_widget(Clear, area);
 
+        let range = editor.config.range.map(|(min, max)| {
+            format!(
+                "[{}, {}]",
+                format_numeric(&editor.config, min),
+                format_numeric(&editor.config, max)
+            )
+        });
         let block = Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Yellow))
+            .border_style(Style::default().fg(theme.value))
             .title(format!(
-                " Edit {} ({}) ",
-                editor.config.name, editor.config.config_type
+                " Edit {} ({}) {}{}",
+                editor.config.name,
+                editor.config.config_type,
+                range
+                    .as_deref()
+                    .map(|r| format!("{} ", r))
+                    .unwrap_or_default(),
+                editor
+                    .config
+                    .regex
+                    .as_deref()
+                    .map(|re| format!("/{}/ ", re))
+                    .unwrap_or_default()
             ));
 
-        let mut lines = vec![Line::from(editor.input.as_str()), Line::from("")];
+        let is_list = editor.config.config_type == ConfigType::StringList;
+        let mut lines: Vec<Line> = if is_list {
+            editor
+                .input
+                .split('\n')
+                .map(|line| Line::from(format!("- {}", line)))
+                .collect()
+        } else {
+            vec![Line::from(editor.input.as_str())]
+        };
+        lines.push(Line::from(""));
+
+        if is_list {
+            lines.push(Line::from(Span::styled(
+                "[Enter] new line  [Enter] on an empty line to save  [Backspace] remove",
+                Style::default()
+                    .fg(theme.muted)
+                    .add_modifier(Modifier::ITALIC),
+            )));
+        }
 
-        if let Some((min, max)) = editor.config.range {
+        if let Some(range) = &range {
             lines.push(Line::from(Span::styled(
-                format!("Range: [{}, {}]", min, max),
+                format!(
+                    "Range: {}  [Up/Down] ±{}",
+                    range,
+                    format_numeric(&editor.config, editor.config.step.unwrap_or(1))
+                ),
                 Style::default()
-                    .fg(Color::Gray)
+                    .fg(theme.muted)
                     .add_modifier(Modifier::ITALIC),
             )));
         }
@@ -161,50 +343,45 @@ fn draw_input_popup(f: &mut Frame, app: &App) {
             lines.push(Line::from(Span::styled(
                 format!("Regex: {}", regex),
                 Style::default()
-                    .fg(Color::Gray)
+                    .fg(theme.muted)
                     .add_modifier(Modifier::ITALIC),
             )));
         }
 
         let text = Paragraph::new(lines)
             .block(block)
-            .style(Style::default().fg(Color::Yellow));
+            .style(Style::default().fg(theme.value));
 
         f.render_widget(text, area);
     }
 }
 
 fn draw_choice_popup(f: &mut Frame, app: &mut App) {
+    let theme = app.theme;
     if let Some(editor) = &mut app.ui.editor {
         let area = centered_rect(50, 40, f.area());
         f.render_widget(Clear, area);
 
         let block = Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Green))
+            .border_style(Style::default().fg(theme.string))
             .title(format!(" Select Option for {} ", editor.config.name));
 
-        let default_options = Vec::new();
-        let options = editor.config.options.as_ref().unwrap_or(&default_options);
-        let items: Vec<ListItem> = options
-            .iter()
-            .map(|opt| ListItem::new(opt.as_str()))
+        let items: Vec<ListItem> = choice_entries(&editor.config, &app.evaluator)
+            .into_iter()
+            .map(|opt| ListItem::new(opt.map_or(NO_CHOICE_LABEL, ChoiceOption::label)))
             .collect();
 
         let list = List::new(items)
             .block(block)
-            .highlight_style(
-                Style::default()
-                    .bg(Color::Indexed(237))
-                    .add_modifier(Modifier::BOLD),
-            )
+            .highlight_style(theme.highlight_style())
             .highlight_symbol("▶ ");
 
         f.render_stateful_widget(list, area, &mut editor.choice_state);
     }
 }
 
-fn draw_notification(f: &mut Frame, msg: &str) {
+fn draw_notification(f: &mut Frame, msg: &str, theme: &Theme) {
     let area = centered_rect(60, 20, f.area());
     f.render_widget(Clear, area);
 
@@ -213,7 +390,7 @@ fn draw_notification(f: &mut Frame, msg: &str) {
         .title(" Notification ")
         .border_style(
             Style::default()
-                .fg(Color::Cyan)
+                .fg(theme.accent)
                 .add_modifier(Modifier::BOLD),
         );
 
@@ -224,7 +401,79 @@ fn draw_notification(f: &mut Frame, msg: &str) {
     f.render_widget(text, area);
 }
 
-fn draw_quit_confirm(f: &mut Frame) {
+fn draw_help_popup(f: &mut Frame, item: &ConfigItem, dependents: &[&str], theme: &Theme) {
+    let area = centered_rect(70, 60, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Help: {} ", item.name))
+        .border_style(
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let label = Style::default().fg(theme.muted);
+    let default_str = item
+        .default
+        .as_ref()
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "(none)".to_string());
+    let mut lines = vec![
+        Line::from(Span::styled(
+            item.desc.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Type:       ", label),
+            Span::raw(item.config_type.to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("Default:    ", label),
+            Span::raw(default_str),
+        ]),
+    ];
+    if let Some(dep) = &item.depends_on {
+        lines.push(Line::from(vec![
+            Span::styled("Depends on: ", label),
+            Span::raw(dep.clone()),
+        ]));
+    }
+    if !dependents.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("Needed by:  ", label),
+            Span::raw(dependents.join(", ")),
+        ]));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("Copy:       ", label),
+        Span::raw("[C] name  [Shift+C] name = value"),
+    ]));
+    if let Some(message) = item.deprecation_message() {
+        lines.push(Line::from(Span::styled(
+            message,
+            Style::default().fg(theme.value),
+        )));
+    }
+    lines.push(Line::from(""));
+    match &item.help {
+        Some(help) => lines.extend(help.lines().map(|l| Line::from(l.to_string()))),
+        None => lines.push(Line::from(Span::styled(
+            "No help available",
+            label.add_modifier(Modifier::ITALIC),
+        ))),
+    }
+
+    let text = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(text, area);
+}
+
+fn draw_quit_confirm(f: &mut Frame, theme: &Theme) {
     let area = centered_rect(50, 25, f.area());
     f.render_widget(Clear, area);
 
@@ -233,7 +482,7 @@ fn draw_quit_confirm(f: &mut Frame) {
         .title(" Unsaved Changes ")
         .border_style(
             Style::default()
-                .fg(Color::LightRed)
+                .fg(theme.alert)
                 .add_modifier(Modifier::BOLD),
         );
 
@@ -249,21 +498,31 @@ fn draw_quit_confirm(f: &mut Frame) {
 fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Min(0), Constraint::Length(15)])
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(16),
+            Constraint::Length(15),
+        ])
         .split(area);
 
     let help_text = if app.ui.show_quit_confirm {
         " [Y] Save & Quit  [N] Discard & Quit  [Esc] Stay "
     } else if app.ui.notification.is_some() {
         " [Any Key] Close Notification "
+    } else if app.ui.help_item.is_some() {
+        " [Any Key] Close Help "
     } else if let Some(editor) = &app.ui.editor {
         if editor.config.config_type == crate::schema::ConfigType::Choice {
             " [Enter] Select  [Esc] Cancel  [J/K] Navigate "
         } else {
             " [Enter] Confirm  [Esc] Cancel  [Backspace] Delete "
         }
+    } else if app.ui.search_active {
+        " [Enter] Apply Filter  [Esc] Clear  [Backspace] Delete "
+    } else if app.ui.jump_query.is_some() {
+        " [Enter] Go to Symbol  [Esc] Cancel  [Backspace] Delete "
     } else {
-        " [Enter/L] Enter  [Esc/H] Back  [Space/Y/I] Edit  [S] Save  [Q] Quit "
+        " [Enter/L] Enter  [Esc/H] Back  [Space/Y/I] Edit  [R] Reset  [U] Undo  [V] Show All  [X] Hex  [C] Copy  [A/Z] All On/Off  [/] Search  [G] Go to  [?] Help  [S] Save  [Q] Quit "
     };
 
     let status_text = if app.is_dirty {
@@ -272,9 +531,9 @@ fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
         " SAVED "
     };
     let status_style = if app.is_dirty {
-        Style::default().fg(Color::Black).bg(Color::Yellow)
+        app.theme.badge(app.theme.value)
     } else {
-        Style::default().fg(Color::Black).bg(Color::Green)
+        app.theme.badge(app.theme.string)
     };
 
     let help = Paragraph::new(help_text).block(Block::default().borders(Borders::ALL));
@@ -283,8 +542,13 @@ fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
         .style(status_style)
         .alignment(ratatui::layout::Alignment::Center);
 
+    let modified = Paragraph::new(format!(" {} modified ", app.modified_count))
+        .block(Block::default().borders(Borders::ALL))
+        .alignment(ratatui::layout::Alignment::Center);
+
     f.render_widget(help, chunks[0]);
-    f.render_widget(status, chunks[1]);
+    f.render_widget(modified, chunks[1]);
+    f.render_widget(status, chunks[2]);
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {